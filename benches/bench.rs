@@ -181,6 +181,14 @@ macro_rules! generate_benches {
         )*
     };
 
+    (typed_non_power_two, $c: tt, $rb: tt, $ty: tt, $fn: tt, $bmfunc: tt, $($i:tt),*) => {
+        $(
+            $c.bench_function(&format!("{} {} 1M capacity not power of two {}", stringify!($rb), stringify!($bmfunc), stringify!($i)), |b| $bmfunc(b, || {
+                $rb::<$ty, $i>::$fn()
+            }));
+        )*
+    };
+
     (compare, $c: tt, $rb: tt, $ty: tt, $fn: tt, $bmfunc: tt, $($i:tt),*) => {
         $(
             $bmfunc($i, stringify!($rb), stringify!($bmfunc), $c, || {
@@ -290,6 +298,42 @@ fn criterion_benchmark(c: &mut Criterion) {
         8192,
         8195
     ];
+    generate_benches![
+        typed_non_power_two,
+        c,
+        ConstGenericRingBuffer,
+        i32,
+        new,
+        benchmark_push,
+        17,
+        60,
+        1000,
+        8195
+    ];
+    generate_benches![
+        typed_non_power_two,
+        c,
+        ConstGenericRingBuffer,
+        i32,
+        new,
+        benchmark_various,
+        17,
+        60,
+        1000,
+        8195
+    ];
+    generate_benches![
+        typed_non_power_two,
+        c,
+        ConstGenericRingBuffer,
+        i32,
+        new,
+        benchmark_push_dequeue,
+        17,
+        60,
+        1000,
+        8195
+    ];
     generate_benches![
         typed,
         c,
@@ -0,0 +1,310 @@
+use core::ops::Range;
+
+use crate::Pod;
+
+/// The bookkeeping of a bip buffer over `capacity` slots, shared by [`ConstGenericBipBuffer`] and
+/// [`BipBuffer`](crate::BipBuffer).
+///
+/// The data lives in up to two regions. The primary region ends at `write` while the buffer isn't
+/// inverted. Once the writer gets more room from wrapping to the start of the storage than from
+/// continuing at the end, it starts a secondary region there and the buffer is inverted: the
+/// primary region then ends at the watermark, and the secondary region is `0..write`. When the
+/// reader reaches the watermark, the secondary region becomes the primary one.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub(crate) struct BipState {
+    read: usize,
+    write: usize,
+    watermark: usize,
+    inverted: bool,
+}
+
+impl BipState {
+    /// The slots of the elements at the front of the buffer, up to where they wrap around.
+    pub(crate) fn readable(&self) -> Range<usize> {
+        if self.inverted {
+            self.read..self.watermark
+        } else {
+            self.read..self.write
+        }
+    }
+
+    /// The largest run of free slots.
+    pub(crate) fn writable(&self, capacity: usize) -> Range<usize> {
+        if self.inverted {
+            self.write..self.read
+        } else if capacity - self.write >= self.read {
+            self.write..capacity
+        } else {
+            0..self.read
+        }
+    }
+
+    pub(crate) fn len(&self) -> usize {
+        if self.inverted {
+            self.watermark - self.read + self.write
+        } else {
+            self.write - self.read
+        }
+    }
+
+    pub(crate) fn advance_write(&mut self, capacity: usize, n: usize) {
+        let writable = self.writable(capacity);
+        let free = writable.len();
+        assert!(
+            n <= free,
+            "cannot advance the write pointer by {n} with only {free} contiguous free space"
+        );
+        if n == 0 {
+            return;
+        }
+
+        if self.inverted || writable.start == self.write {
+            self.write += n;
+        } else {
+            // start the secondary region
+            self.watermark = self.write;
+            self.write = n;
+            self.inverted = true;
+        }
+    }
+
+    pub(crate) fn advance_read(&mut self, n: usize) {
+        let len = self.readable().len();
+        assert!(
+            n <= len,
+            "cannot advance the read pointer by {n} with only {len} contiguous elements"
+        );
+
+        self.read += n;
+        if self.inverted && self.read == self.watermark {
+            // the secondary region becomes the primary one
+            self.read = 0;
+            self.watermark = 0;
+            self.inverted = false;
+        } else if !self.inverted && self.read == self.write {
+            // start over at the start of the storage, where there's the most room
+            *self = Self::default();
+        }
+    }
+}
+
+/// The `ConstGenericBipBuffer` is a bip buffer (bipartite buffer) which stores its elements in an
+/// array, like [`ConstGenericRingBuffer`](crate::ConstGenericRingBuffer). See
+/// [`BipBuffer`](crate::BipBuffer) for one that allocates on the heap.
+///
+/// Unlike a ringbuffer, a bip buffer never splits a region at the end of its storage. Instead
+/// [`writable`](ConstGenericBipBuffer::writable) always hands out the largest contiguous run of
+/// free slots, either after the last element or at the start of the storage, and
+/// [`readable`](ConstGenericBipBuffer::readable) hands out the elements up to where the writer
+/// wrapped around. This is useful for APIs which need a single slice, like some codecs. The price
+/// is that the free space at the end of the storage is unused while the writer is at its start.
+///
+/// Elements are never overwritten. Because [`writable`](ConstGenericBipBuffer::writable) exposes
+/// slots which were never written, elements must be [`Pod`].
+///
+/// # Example
+/// ```
+/// use ringbuffer::ConstGenericBipBuffer;
+///
+/// let mut buffer = ConstGenericBipBuffer::<u8, 8>::new();
+///
+/// buffer.writable()[..6].copy_from_slice(b"hello ");
+/// buffer.advance_write(6);
+/// buffer.advance_read(4);
+///
+/// // there are only 2 free slots at the end, so writing continues at the start
+/// assert_eq!(buffer.writable().len(), 4);
+/// buffer.writable()[..3].copy_from_slice(b"bip");
+/// buffer.advance_write(3);
+///
+/// assert_eq!(buffer.readable(), b"o ");
+/// buffer.advance_read(2);
+/// assert_eq!(buffer.readable(), b"bip");
+/// ```
+#[derive(Debug, Clone)]
+pub struct ConstGenericBipBuffer<T: Pod, const CAP: usize> {
+    buf: [T; CAP],
+    state: BipState,
+}
+
+impl<T: Pod, const CAP: usize> ConstGenericBipBuffer<T, CAP> {
+    const ERROR_CAPACITY_IS_NOT_ALLOWED_TO_BE_ZERO: () =
+        assert!(CAP != 0, "Capacity is not allowed to be zero");
+
+    /// Creates an empty `ConstGenericBipBuffer`.
+    ///
+    /// # Panics
+    /// Fails to compile when `CAP` is zero.
+    #[inline]
+    #[must_use]
+    pub fn new() -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::ERROR_CAPACITY_IS_NOT_ALLOWED_TO_BE_ZERO;
+
+        Self {
+            // Safety: zero is a valid bit pattern for Pod types
+            buf: unsafe { core::mem::zeroed() },
+            state: BipState::default(),
+        }
+    }
+
+    /// Returns the number of elements in the buffer, in both regions.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.state.len()
+    }
+
+    /// Returns true if the buffer holds no elements.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the capacity of the buffer.
+    #[inline]
+    #[must_use]
+    pub const fn capacity(&self) -> usize {
+        CAP
+    }
+
+    /// Returns the elements at the front of the buffer as one slice, up to where the writer
+    /// wrapped around. The rest is available after [`advance_read`](Self::advance_read) consumed
+    /// this slice.
+    #[inline]
+    #[must_use]
+    pub fn readable(&self) -> &[T] {
+        &self.buf[self.state.readable()]
+    }
+
+    /// Returns the largest contiguous run of free slots. Write to the start of it, and then call
+    /// [`advance_write`](Self::advance_write) to add what was written to the buffer.
+    #[inline]
+    pub fn writable(&mut self) -> &mut [T] {
+        &mut self.buf[self.state.writable(CAP)]
+    }
+
+    /// Adds the first `n` elements of [`writable`](Self::writable) to the back of the buffer.
+    ///
+    /// # Panics
+    /// Panics if [`writable`](Self::writable) is shorter than `n`.
+    #[inline]
+    pub fn advance_write(&mut self, n: usize) {
+        self.state.advance_write(CAP, n);
+    }
+
+    /// Removes the first `n` elements of [`readable`](Self::readable) from the front of the
+    /// buffer.
+    ///
+    /// # Panics
+    /// Panics if [`readable`](Self::readable) is shorter than `n`.
+    #[inline]
+    pub fn advance_read(&mut self, n: usize) {
+        self.state.advance_read(n);
+    }
+
+    /// Empties the buffer.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.state = BipState::default();
+    }
+}
+
+impl<T: Pod, const CAP: usize> Default for ConstGenericBipBuffer<T, CAP> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{BipState, ConstGenericBipBuffer};
+
+    #[test]
+    fn test_state() {
+        let mut state = BipState::default();
+        assert_eq!(state.writable(8), 0..8);
+
+        state.advance_write(8, 6);
+        state.advance_read(4);
+        assert_eq!(state.readable(), 4..6);
+        // 2 slots left at the end, 4 at the start
+        assert_eq!(state.writable(8), 0..4);
+
+        state.advance_write(8, 3);
+        assert_eq!(state.len(), 5);
+        assert_eq!(state.readable(), 4..6);
+        // the writer continues in the secondary region, up to the reader
+        assert_eq!(state.writable(8), 3..4);
+
+        state.advance_write(8, 1);
+        assert_eq!(state.writable(8), 4..4);
+
+        // reaching the watermark moves the reader to the secondary region
+        state.advance_read(2);
+        assert_eq!(state.readable(), 0..4);
+        assert_eq!(state.writable(8), 4..8);
+
+        // an empty buffer starts over at the start
+        state.advance_read(4);
+        assert_eq!(state, BipState::default());
+    }
+
+    #[test]
+    fn test_prefers_the_end_when_it_is_larger() {
+        let mut state = BipState::default();
+        state.advance_write(8, 3);
+        state.advance_read(2);
+        assert_eq!(state.writable(8), 3..8);
+        state.advance_write(8, 5);
+        assert_eq!(state.writable(8), 0..2);
+    }
+
+    #[test]
+    fn test_stream() {
+        let mut buffer = ConstGenericBipBuffer::<u32, 10>::new();
+        let mut written = 0;
+        let mut read = 0;
+
+        for round in 0..100 {
+            let writable = buffer.writable();
+            let n = writable.len().min(round % 4 + 1);
+            for v in &mut writable[..n] {
+                *v = written;
+                written += 1;
+            }
+            buffer.advance_write(n);
+
+            let readable = buffer.readable();
+            let n = readable.len().min(round % 3 + 1);
+            for v in &readable[..n] {
+                assert_eq!(*v, read);
+                read += 1;
+            }
+            buffer.advance_read(n);
+            assert_eq!(buffer.len(), (written - read) as usize);
+        }
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "cannot advance the write pointer by 5 with only 4 contiguous free space"
+    )]
+    fn test_advance_write_too_far() {
+        let mut buffer = ConstGenericBipBuffer::<u8, 8>::new();
+        buffer.advance_write(6);
+        buffer.advance_read(4);
+        buffer.advance_write(5);
+    }
+
+    #[test]
+    #[should_panic(
+        expected = "cannot advance the read pointer by 3 with only 2 contiguous elements"
+    )]
+    fn test_advance_read_too_far() {
+        let mut buffer = ConstGenericBipBuffer::<u8, 8>::new();
+        buffer.advance_write(2);
+        buffer.advance_read(3);
+    }
+}
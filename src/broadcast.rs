@@ -0,0 +1,244 @@
+use crate::{RingBuffer, SeqError, Sequenced};
+use core::fmt;
+
+/// The error returned by [`Broadcast::recv`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecvError {
+    /// The reader has seen every element sent so far.
+    Empty,
+    /// The writer overwrote this many elements the reader hadn't seen yet. The reader continues
+    /// at the oldest element still in the buffer.
+    Lagged(u64),
+}
+
+impl fmt::Display for RecvError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Empty => write!(f, "no new elements were sent"),
+            Self::Lagged(n) => write!(f, "the reader lagged behind by {n} elements"),
+        }
+    }
+}
+
+/// The position of one reader of a [`Broadcast`], created with [`Broadcast::subscribe`].
+///
+/// A `Reader` doesn't borrow the broadcast it reads from, so the writer can keep sending while
+/// readers exist. Pass it to [`Broadcast::recv`] to read the next element.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Reader {
+    next: u64,
+}
+
+impl Reader {
+    /// Returns the sequence number of the next element this reader will read.
+    #[inline]
+    #[must_use]
+    pub const fn next_seq(&self) -> u64 {
+        self.next
+    }
+}
+
+/// Sends every element to any number of readers, which each read at their own pace.
+///
+/// The elements live in the wrapped ringbuffer, so the writer never blocks: when the buffer is
+/// full, sending overwrites the oldest element, even when some reader hasn't read it yet. Such a
+/// reader gets [`RecvError::Lagged`] with the number of elements it missed, and then continues at
+/// the oldest element still available.
+///
+/// Every element gets a sequence number when it is sent, starting at 0, and each [`Reader`] is
+/// just the sequence number of the next element it reads.
+///
+/// # Example
+/// ```
+/// use ringbuffer::{Broadcast, ConstGenericRingBuffer, RecvError};
+///
+/// let mut broadcast = Broadcast::new(ConstGenericRingBuffer::<_, 2>::new());
+/// let mut fast = broadcast.subscribe();
+/// let mut slow = broadcast.subscribe();
+///
+/// for i in 0..3 {
+///     let _ = broadcast.send(i);
+///     assert_eq!(broadcast.recv(&mut fast), Ok(&i));
+/// }
+/// assert_eq!(broadcast.recv(&mut fast), Err(RecvError::Empty));
+///
+/// // the first element was overwritten before the slow reader got to it
+/// assert_eq!(broadcast.recv(&mut slow), Err(RecvError::Lagged(1)));
+/// assert_eq!(broadcast.recv(&mut slow), Ok(&1));
+/// assert_eq!(broadcast.recv(&mut slow), Ok(&2));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Broadcast<B, T> {
+    sequenced: Sequenced<B, T>,
+}
+
+impl<B: RingBuffer<T>, T> Broadcast<B, T> {
+    /// Creates a broadcast which keeps its elements in `rb`. Elements already in `rb` get the
+    /// first sequence numbers, so they are only seen by readers from
+    /// [`subscribe_from_oldest`](Self::subscribe_from_oldest).
+    #[inline]
+    pub fn new(rb: B) -> Self {
+        Self {
+            sequenced: Sequenced::new(rb),
+        }
+    }
+
+    /// Gives back the wrapped ringbuffer.
+    #[inline]
+    pub fn into_inner(self) -> B {
+        self.sequenced.into_inner()
+    }
+
+    /// Returns the wrapped ringbuffer, which holds the elements that can still be read.
+    #[inline]
+    pub const fn inner(&self) -> &B {
+        self.sequenced.inner()
+    }
+
+    /// Returns the sequence number of the oldest element that can still be read.
+    #[inline]
+    #[must_use]
+    pub fn first_seq(&self) -> u64 {
+        self.sequenced.first_seq()
+    }
+
+    /// Returns the sequence number the next sent element will get.
+    #[inline]
+    #[must_use]
+    pub const fn next_seq(&self) -> u64 {
+        self.sequenced.next_seq()
+    }
+
+    /// Sends `value` to every reader. Returns the oldest element if it had to be overwritten.
+    #[inline]
+    pub fn send(&mut self, value: T) -> Option<T> {
+        self.sequenced.enqueue(value)
+    }
+
+    /// Creates a reader which reads the elements sent from now on.
+    #[inline]
+    #[must_use]
+    pub const fn subscribe(&self) -> Reader {
+        Reader {
+            next: self.next_seq(),
+        }
+    }
+
+    /// Creates a reader which starts at the oldest element that can still be read.
+    #[inline]
+    #[must_use]
+    pub fn subscribe_from_oldest(&self) -> Reader {
+        Reader {
+            next: self.first_seq(),
+        }
+    }
+
+    /// Returns how many elements `reader` can still read, including the ones it lagged behind on.
+    #[inline]
+    #[must_use]
+    pub fn pending(&self, reader: &Reader) -> u64 {
+        self.next_seq() - reader.next
+    }
+
+    /// Reads the next element for `reader`, and moves it past that element.
+    ///
+    /// # Errors
+    /// Fails with [`RecvError::Empty`] when `reader` has read every element sent so far. Fails
+    /// with [`RecvError::Lagged`] when elements `reader` hadn't read yet were overwritten. Then
+    /// `reader` is moved to the oldest element, so the next call reads that element.
+    ///
+    /// # Panics
+    /// Panics if `reader` was created by another broadcast, and is ahead of this one.
+    pub fn recv(&self, reader: &mut Reader) -> Result<&T, RecvError> {
+        assert!(
+            reader.next <= self.next_seq(),
+            "the reader is ahead of the broadcast it reads from"
+        );
+
+        match self.sequenced.get_by_seq(reader.next) {
+            Ok(value) => {
+                reader.next += 1;
+                Ok(value)
+            }
+            Err(SeqError::Evicted) => {
+                let first = self.first_seq();
+                let lagged = first - reader.next;
+                reader.next = first;
+                Err(RecvError::Lagged(lagged))
+            }
+            Err(SeqError::NotYetWritten) => Err(RecvError::Empty),
+        }
+    }
+
+    /// Empties the buffer. Readers which hadn't read every element get [`RecvError::Lagged`].
+    #[inline]
+    pub fn clear(&mut self) {
+        self.sequenced.clear();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Broadcast, RecvError};
+    use crate::{ConstGenericRingBuffer, RingBuffer};
+
+    #[test]
+    fn test_readers_at_different_speeds() {
+        let mut broadcast = Broadcast::new(ConstGenericRingBuffer::<u32, 4>::new());
+        let mut fast = broadcast.subscribe();
+        let mut slow = broadcast.subscribe();
+        assert_eq!(broadcast.recv(&mut fast), Err(RecvError::Empty));
+
+        for i in 0..10 {
+            let _ = broadcast.send(i);
+            assert_eq!(broadcast.recv(&mut fast), Ok(&i));
+            if i % 2 == 0 {
+                let _ = broadcast.recv(&mut slow);
+            }
+        }
+        assert_eq!(broadcast.pending(&fast), 0);
+
+        // the slow reader read 0, 1, 2, 3 and 4, and the last 4 elements are 6 to 9
+        assert_eq!(broadcast.pending(&slow), 5);
+        assert_eq!(broadcast.recv(&mut slow), Err(RecvError::Lagged(1)));
+        assert_eq!(slow.next_seq(), 6);
+        for i in 6..10 {
+            assert_eq!(broadcast.recv(&mut slow), Ok(&i));
+        }
+        assert_eq!(broadcast.recv(&mut slow), Err(RecvError::Empty));
+    }
+
+    #[test]
+    fn test_subscribe() {
+        let mut rb = ConstGenericRingBuffer::<u32, 4>::new();
+        let _ = rb.enqueue(1);
+        let _ = rb.enqueue(2);
+
+        let mut broadcast = Broadcast::new(rb);
+        assert_eq!((broadcast.first_seq(), broadcast.next_seq()), (0, 2));
+        let mut new = broadcast.subscribe();
+        let mut oldest = broadcast.subscribe_from_oldest();
+        let _ = broadcast.send(3);
+
+        assert_eq!(broadcast.recv(&mut new), Ok(&3));
+        assert_eq!(broadcast.recv(&mut oldest), Ok(&1));
+        assert_eq!(broadcast.pending(&oldest), 2);
+
+        broadcast.clear();
+        assert_eq!(broadcast.first_seq(), 3);
+        assert_eq!(broadcast.recv(&mut oldest), Err(RecvError::Lagged(2)));
+        assert_eq!(broadcast.recv(&mut oldest), Err(RecvError::Empty));
+        assert_eq!(broadcast.recv(&mut new), Err(RecvError::Empty));
+    }
+
+    #[test]
+    #[should_panic(expected = "the reader is ahead of the broadcast it reads from")]
+    fn test_reader_of_other_broadcast() {
+        let mut first = Broadcast::new(ConstGenericRingBuffer::<u32, 4>::new());
+        let _ = first.send(1);
+        let mut reader = first.subscribe();
+
+        let second = Broadcast::new(ConstGenericRingBuffer::<u32, 4>::new());
+        let _ = second.recv(&mut reader);
+    }
+}
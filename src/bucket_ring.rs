@@ -0,0 +1,220 @@
+use core::time::Duration;
+
+use crate::{ConstGenericRingBuffer, RingBuffer, RingBufferSlices};
+
+/// The `BucketRing` counts values in `N` buckets which each cover the same span of time, such as
+/// events per second over the last minute with 60 buckets of a second.
+///
+/// Values are [`record`](BucketRing::record)ed into the bucket for the time they happened at.
+/// When time moves past the newest bucket, the oldest buckets are dropped and new empty ones are
+/// started, including empty ones for any buckets in between. After an idle gap longer than all
+/// buckets together, every bucket is empty.
+///
+/// The buffer never reads a clock itself: every method which moves it forward takes the current
+/// time as a [`Duration`] since any starting point the caller picks, such as
+/// `start.elapsed()` of a `std::time::Instant`. Tests can pass whatever times they need, and this
+/// doesn't need `std`. Times before the newest bucket are counted in the newest bucket.
+///
+/// # Example
+/// ```
+/// use core::time::Duration;
+/// use ringbuffer::BucketRing;
+///
+/// // the last minute, per second
+/// let mut requests = BucketRing::<60>::new(Duration::from_secs(1));
+///
+/// requests.record(Duration::from_millis(100), 1);
+/// requests.record(Duration::from_millis(900), 1);
+/// requests.record(Duration::from_millis(59_500), 4);
+/// assert_eq!(requests.sum_over(Duration::from_secs(1)), 4);
+/// assert_eq!(requests.total(), 6);
+/// assert_eq!(requests.rate(), 0.1);
+///
+/// // almost a minute later, only the last events are in range
+/// requests.advance(Duration::from_millis(118_000));
+/// assert_eq!(requests.total(), 4);
+/// ```
+#[derive(Debug, Clone)]
+pub struct BucketRing<const N: usize> {
+    // always full, the newest bucket at the back
+    buckets: ConstGenericRingBuffer<u64, N>,
+    bucket_width: Duration,
+    // the number of bucket widths from the starting point to the start of the newest bucket
+    newest: u128,
+}
+
+impl<const N: usize> BucketRing<N> {
+    /// Creates a `BucketRing` with every bucket empty, of which the newest starts at time zero.
+    ///
+    /// # Panics
+    /// Panics if `bucket_width` is zero
+    #[must_use]
+    pub fn new(bucket_width: Duration) -> Self {
+        assert!(
+            !bucket_width.is_zero(),
+            "Bucket width must be greater than 0"
+        );
+
+        let mut buckets = ConstGenericRingBuffer::new();
+        buckets.fill(0);
+        Self {
+            buckets,
+            bucket_width,
+            newest: 0,
+        }
+    }
+
+    /// Returns the span of time every bucket covers.
+    #[inline]
+    #[must_use]
+    pub fn bucket_width(&self) -> Duration {
+        self.bucket_width
+    }
+
+    /// Returns the span of time all buckets cover together.
+    #[inline]
+    #[must_use]
+    pub fn window(&self) -> Duration {
+        u32::try_from(N)
+            .ok()
+            .and_then(|n| self.bucket_width.checked_mul(n))
+            .unwrap_or(Duration::MAX)
+    }
+
+    /// Moves the buffer forward to `now`, starting empty buckets for the time since the newest
+    /// bucket and dropping as many of the oldest ones.
+    pub fn advance(&mut self, now: Duration) {
+        let bucket = now.as_nanos() / self.bucket_width.as_nanos();
+        if bucket <= self.newest {
+            return;
+        }
+
+        // after a gap of N buckets or more, all of them are empty, no matter how long it was
+        let skipped = (bucket - self.newest).min(N as u128);
+        for _ in 0..skipped {
+            let _ = self.buckets.enqueue(0);
+        }
+        self.newest = bucket;
+    }
+
+    /// Adds `value` to the bucket for `now`, after moving the buffer forward to it.
+    pub fn record(&mut self, now: Duration, value: u64) {
+        self.advance(now);
+        if let Some(newest) = self.buckets.back_mut() {
+            *newest = newest.saturating_add(value);
+        }
+    }
+
+    /// Returns the sum of the newest buckets which cover `duration`, counting buckets which it
+    /// only covers partly, and at most all of them.
+    #[must_use]
+    pub fn sum_over(&self, duration: Duration) -> u64 {
+        let buckets = duration.as_nanos().div_ceil(self.bucket_width.as_nanos());
+        let buckets = usize::try_from(buckets).map_or(N, |buckets| buckets.min(N));
+        let (first, second) = self.buckets.as_slices();
+        // the newest buckets are at the back
+        let from_second = buckets.min(second.len());
+        let from_first = buckets - from_second;
+        first[first.len() - from_first..]
+            .iter()
+            .chain(&second[second.len() - from_second..])
+            .fold(0, |sum, value| sum.saturating_add(*value))
+    }
+
+    /// Returns the sum of all buckets.
+    #[inline]
+    #[must_use]
+    pub fn total(&self) -> u64 {
+        self.sum_over(self.window())
+    }
+
+    /// Returns the sum of all buckets per second of the time they cover together.
+    ///
+    /// The newest bucket may still be filling up, which makes this a little low until it is
+    /// complete.
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub fn rate(&self) -> f64 {
+        self.total() as f64 / self.window().as_secs_f64()
+    }
+
+    /// Returns the buckets, oldest first.
+    #[must_use]
+    pub fn buckets(&self) -> impl DoubleEndedIterator<Item = u64> + ExactSizeIterator + '_ {
+        self.buckets.iter().copied()
+    }
+
+    /// Empties all buckets, without moving the buffer.
+    pub fn clear(&mut self) {
+        self.buckets.fill(0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BucketRing;
+    use alloc::vec::Vec;
+    use core::time::Duration;
+
+    fn ms(ms: u64) -> Duration {
+        Duration::from_millis(ms)
+    }
+
+    #[test]
+    fn test_skipped_buckets_are_cleared() {
+        let mut ring = BucketRing::<4>::new(ms(10));
+        for t in 0..4 {
+            ring.record(ms(t * 10 + 5), t + 1);
+        }
+        assert_eq!(ring.buckets().collect::<Vec<_>>(), [1, 2, 3, 4]);
+
+        // skips the buckets at 40 and 50
+        ring.record(ms(61), 7);
+        assert_eq!(ring.buckets().collect::<Vec<_>>(), [4, 0, 0, 7]);
+        assert_eq!(ring.sum_over(ms(10)), 7);
+        assert_eq!(ring.sum_over(ms(11)), 7);
+        assert_eq!(ring.sum_over(ms(40)), 11);
+        assert_eq!(ring.sum_over(Duration::MAX), 11);
+        assert_eq!(ring.sum_over(Duration::ZERO), 0);
+
+        // an idle gap much longer than all buckets
+        ring.advance(Duration::from_secs(1_000_000));
+        assert_eq!(ring.total(), 0);
+        ring.record(Duration::from_secs(1_000_000), 2);
+        assert_eq!(ring.buckets().collect::<Vec<_>>(), [0, 0, 0, 2]);
+    }
+
+    #[test]
+    fn test_earlier_times_count_in_newest_bucket() {
+        let mut ring = BucketRing::<3>::new(ms(10));
+        ring.record(ms(25), 1);
+        ring.record(ms(5), 1);
+        ring.record(ms(29), 1);
+        assert_eq!(ring.buckets().collect::<Vec<_>>(), [0, 0, 3]);
+
+        ring.clear();
+        assert_eq!(ring.total(), 0);
+        ring.record(ms(30), 1);
+        assert_eq!(ring.buckets().collect::<Vec<_>>(), [0, 0, 1]);
+    }
+
+    #[test]
+    fn test_rate() {
+        let mut ring = BucketRing::<60>::new(Duration::from_secs(1));
+        assert_eq!(ring.window(), Duration::from_secs(60));
+        for s in 0..120 {
+            ring.record(Duration::from_secs(s), 3);
+        }
+        assert_eq!(ring.total(), 180);
+        assert_eq!(ring.rate(), 3.0);
+
+        ring.record(Duration::from_secs(119), u64::MAX);
+        assert_eq!(ring.total(), u64::MAX);
+    }
+
+    #[test]
+    #[should_panic(expected = "Bucket width must be greater than 0")]
+    fn test_zero_width() {
+        let _ = BucketRing::<4>::new(Duration::ZERO);
+    }
+}
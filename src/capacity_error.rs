@@ -0,0 +1,66 @@
+use core::fmt;
+
+/// The error returned by the `TryFrom` conversions into a ringbuffer, when the source holds more
+/// elements than the ringbuffer has room for.
+///
+/// Converting with [`From`] would have silently dropped the oldest elements instead.
+/// The source is handed back untouched through [`into_inner`](CapacityError::into_inner).
+///
+/// # Example
+/// ```
+/// use ringbuffer::ConstGenericRingBuffer;
+///
+/// let err = ConstGenericRingBuffer::<_, 2>::try_from(vec![1, 2, 3]).unwrap_err();
+/// assert_eq!(err.source_len(), 3);
+/// assert_eq!(err.capacity(), 2);
+/// assert_eq!(err.into_inner(), vec![1, 2, 3]);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CapacityError<S> {
+    source: S,
+    len: usize,
+    capacity: usize,
+}
+
+impl<S> CapacityError<S> {
+    /// Hands `source` back if `len` elements fit in `capacity`, fails otherwise.
+    pub(crate) fn check(source: S, len: usize, capacity: usize) -> Result<S, Self> {
+        if len > capacity {
+            Err(Self {
+                source,
+                len,
+                capacity,
+            })
+        } else {
+            Ok(source)
+        }
+    }
+
+    /// The number of elements in the source of the conversion.
+    #[must_use]
+    pub fn source_len(&self) -> usize {
+        self.len
+    }
+
+    /// The capacity of the ringbuffer that was converted into.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Gives back the source of the conversion.
+    #[must_use]
+    pub fn into_inner(self) -> S {
+        self.source
+    }
+}
+
+impl<S> fmt::Display for CapacityError<S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "can't fit {} elements in a ringbuffer with capacity {}",
+            self.len, self.capacity
+        )
+    }
+}
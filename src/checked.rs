@@ -0,0 +1,86 @@
+use crate::RingBuffer;
+use core::marker::PhantomData;
+use core::ops::{Deref, DerefMut, Index, IndexMut};
+
+/// Wraps a ringbuffer so that indexing it panics on indices which are out of bounds, instead of
+/// wrapping them around the length of the buffer.
+///
+/// Indexing goes through [`get_checked`](RingBuffer::get_checked) and
+/// [`get_checked_mut`](RingBuffer::get_checked_mut). Everything else is available on the wrapped
+/// buffer, through [`Deref`].
+///
+/// # Example
+/// ```should_panic
+/// use ringbuffer::{Checked, ConstGenericRingBuffer, RingBuffer};
+///
+/// let rb = ConstGenericRingBuffer::<_, 4>::from([1, 2, 3, 4]);
+/// // wraps around to the element at index 1
+/// assert_eq!(rb[5], 2);
+///
+/// let rb = Checked::new(rb);
+/// assert_eq!(rb[1], 2);
+/// let _ = rb[5]; // panics
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Checked<B, T> {
+    rb: B,
+    // `RingBuffer` is generic over the element type, so implementing `Index` needs it as a parameter
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<B: RingBuffer<T>, T> Checked<B, T> {
+    /// Wraps `rb`, so that indexing it doesn't wrap around.
+    #[inline]
+    pub const fn new(rb: B) -> Self {
+        Self {
+            rb,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Gives back the wrapped ringbuffer.
+    #[inline]
+    pub fn into_inner(self) -> B {
+        self.rb
+    }
+}
+
+impl<B: RingBuffer<T>, T> From<B> for Checked<B, T> {
+    fn from(rb: B) -> Self {
+        Self::new(rb)
+    }
+}
+
+impl<B, T> Deref for Checked<B, T> {
+    type Target = B;
+
+    fn deref(&self) -> &Self::Target {
+        &self.rb
+    }
+}
+
+impl<B, T> DerefMut for Checked<B, T> {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.rb
+    }
+}
+
+impl<B: RingBuffer<T>, T> Index<usize> for Checked<B, T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        let len = self.rb.len();
+        self.rb
+            .get_checked(index)
+            .unwrap_or_else(|| panic!("index {index} out of bounds for length {len}"))
+    }
+}
+
+impl<B: RingBuffer<T>, T> IndexMut<usize> for Checked<B, T> {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        let len = self.rb.len();
+        self.rb
+            .get_checked_mut(index)
+            .unwrap_or_else(|| panic!("index {index} out of bounds for length {len}"))
+    }
+}
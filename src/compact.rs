@@ -0,0 +1,543 @@
+use crate::ringbuffer_trait::{RingBufferIntoIterator, RingBufferIterator, RingBufferMutIterator};
+use crate::{RingBuffer, RingBufferSlices, SetLen};
+use core::fmt::Debug;
+use core::iter::FromIterator;
+use core::mem::{self, MaybeUninit};
+use core::ops::{Index, IndexMut};
+use core::ptr;
+
+mod private {
+    pub trait Sealed {}
+}
+
+/// An unsigned integer type a [`CompactRingBuffer`] keeps its read and write pointers in.
+///
+/// This is implemented for `u8`, `u16` and `u32` (on targets where that fits a `usize`), and
+/// can't be implemented outside of this crate.
+pub trait RingIndex: Copy + Eq + Debug + private::Sealed {
+    /// Zero, which both pointers start at.
+    const ZERO: Self;
+    /// The largest value of the type, as a `usize`.
+    const MAX: usize;
+
+    /// Converts a `usize` to this type, keeping only the low bits. This is what makes the
+    /// pointers wrap around.
+    fn from_usize_wrapping(value: usize) -> Self;
+
+    /// Converts this type to a `usize`, which is lossless.
+    fn to_usize(self) -> usize;
+}
+
+macro_rules! impl_ring_index {
+    ($($t: ty),* $(,)?) => {
+        $(
+            impl private::Sealed for $t {}
+
+            impl RingIndex for $t {
+                const ZERO: Self = 0;
+                const MAX: usize = <$t>::MAX as usize;
+
+                #[inline]
+                #[allow(clippy::cast_possible_truncation)]
+                fn from_usize_wrapping(value: usize) -> Self {
+                    value as $t
+                }
+
+                #[inline]
+                fn to_usize(self) -> usize {
+                    self as usize
+                }
+            }
+        )*
+    };
+}
+
+impl_ring_index!(u8, u16);
+#[cfg(not(target_pointer_width = "16"))]
+impl_ring_index!(u32);
+
+/// The `CompactRingBuffer` is a [`ConstGenericRingBuffer`](crate::ConstGenericRingBuffer) which
+/// keeps its read and write pointers in a smaller integer type `I` instead of a `usize`.
+///
+/// A `ConstGenericRingBuffer<u8, 16>` spends two `usize`s on bookkeeping next to 16 bytes of
+/// elements, while a `CompactRingBuffer<u8, 16>` only spends two bytes. This matters on 8 and
+/// 16-bit microcontrollers, and when keeping many small buffers. It behaves the same as a
+/// `ConstGenericRingBuffer` otherwise, though it has no grants.
+///
+/// `I` is `u8` by default, which fits capacities up to 128. `u16` fits up to 32768 and `u32` up
+/// to 2<sup>31</sup>. Larger capacities are a compile time error, since the pointers of power of
+/// two sized buffers wrap around at the maximum of `I`, and those of other sizes go up to twice
+/// the capacity.
+///
+/// # Example
+/// ```
+/// use ringbuffer::{CompactRingBuffer, RingBuffer};
+///
+/// let mut buffer = CompactRingBuffer::<u8, 16>::new();
+/// assert_eq!(core::mem::size_of_val(&buffer), 18);
+///
+/// buffer.extend(0..20);
+/// assert_eq!(buffer.len(), 16);
+/// assert_eq!(buffer.front(), Some(&4));
+///
+/// // larger capacities need a larger index type
+/// let mut buffer = CompactRingBuffer::<u32, 1000, u16>::new();
+/// buffer.enqueue(1);
+/// assert_eq!(buffer.to_vec(), vec![1]);
+/// ```
+#[derive(Debug)]
+pub struct CompactRingBuffer<T, const CAP: usize, I: RingIndex = u8> {
+    buf: [MaybeUninit<T>; CAP],
+    readptr: I,
+    writeptr: I,
+}
+
+impl<T, const CAP: usize, I: RingIndex> CompactRingBuffer<T, CAP, I> {
+    const ERROR_CAPACITY_IS_NOT_ALLOWED_TO_BE_ZERO: () =
+        assert!(CAP != 0, "Capacity is not allowed to be zero");
+    const ERROR_CAPACITY_DOES_NOT_FIT_INDEX: () = assert!(
+        CAP <= I::MAX / 2 + 1,
+        "Capacity is too large for the index type"
+    );
+
+    /// Creates an empty compact ringbuffer, size is passed as a const generic.
+    ///
+    /// The size does not have to be a power of two, but it must fit the index type `I`, which
+    /// is checked at compile time.
+    #[inline]
+    #[must_use]
+    pub const fn new() -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::ERROR_CAPACITY_IS_NOT_ALLOWED_TO_BE_ZERO;
+        #[allow(clippy::let_unit_value)]
+        let () = Self::ERROR_CAPACITY_DOES_NOT_FIT_INDEX;
+
+        Self {
+            buf: [const { MaybeUninit::<T>::uninit() }; CAP],
+            readptr: I::ZERO,
+            writeptr: I::ZERO,
+        }
+    }
+
+    // The pointers work like those of a `ConstGenericRingBuffer`, only wrapping around at the
+    // maximum of `I` instead of `usize::MAX`. Since a power of two CAP divides the number of
+    // values of `I`, masking a wrapped pointer still gives the right slot. `I` can be as wide as
+    // a `usize` (`u16` on 16-bit targets, `u32` on 32-bit ones), so additions to the pointers
+    // wrap in `usize` as well.
+
+    /// Moves the read pointer forward by one. Unless CAP is a power of two, this wraps both
+    /// pointers back once the read pointer reaches the end of the buffer.
+    #[inline]
+    fn advance_readptr(&mut self) {
+        let readptr = self.readptr.to_usize().wrapping_add(1);
+        if !CAP.is_power_of_two() && readptr == CAP {
+            self.readptr = I::ZERO;
+            self.writeptr = I::from_usize_wrapping(self.writeptr.to_usize() - CAP);
+        } else {
+            self.readptr = I::from_usize_wrapping(readptr);
+        }
+    }
+
+    /// Maps a pointer, or a pointer plus an offset within the buffer, to an index into the buffer.
+    #[inline]
+    const fn mask(ptr: usize) -> usize {
+        if CAP.is_power_of_two() {
+            crate::mask_and(CAP, ptr)
+        } else if ptr >= CAP {
+            debug_assert!(ptr < 2 * CAP);
+            ptr - CAP
+        } else {
+            ptr
+        }
+    }
+
+    /// The index into the buffer of the element `offset` places after the read pointer.
+    #[inline]
+    unsafe fn ptr_index(rb: *const Self, offset: usize) -> usize {
+        Self::mask((*rb).readptr.to_usize().wrapping_add(offset))
+    }
+
+    /// Get a mut pointer to the buffer
+    #[inline]
+    unsafe fn ptr_base_mut(rb: *mut Self) -> *mut T {
+        ptr::addr_of_mut!((*rb).buf).cast()
+    }
+
+    /// Reduces a signed index to an offset from the read pointer, if the buffer isn't empty.
+    #[inline]
+    fn offset_signed(len: usize, index: isize) -> Option<usize> {
+        #[allow(clippy::cast_possible_wrap, clippy::cast_sign_loss)]
+        (len != 0).then(|| {
+            let index = if index >= 0 {
+                index
+            } else {
+                len as isize + index
+            };
+            index.rem_euclid(len as isize) as usize
+        })
+    }
+}
+
+impl<T, const CAP: usize, I: RingIndex> Drop for CompactRingBuffer<T, CAP, I> {
+    fn drop(&mut self) {
+        self.drain().for_each(drop);
+    }
+}
+
+impl<T: Clone, const CAP: usize, I: RingIndex> Clone for CompactRingBuffer<T, CAP, I> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+// We need to manually implement PartialEq because MaybeUninit isn't PartialEq
+impl<T: PartialEq, const CAP: usize, I: RingIndex> PartialEq for CompactRingBuffer<T, CAP, I> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq, const CAP: usize, I: RingIndex> Eq for CompactRingBuffer<T, CAP, I> {}
+
+impl<T, const CAP: usize, I: RingIndex> IntoIterator for CompactRingBuffer<T, CAP, I> {
+    type Item = T;
+    type IntoIter = RingBufferIntoIterator<T, Self>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        RingBufferIntoIterator::new(self)
+    }
+}
+
+#[allow(clippy::into_iter_without_iter)]
+// iter() is implemented on the trait
+impl<'a, T, const CAP: usize, I: RingIndex> IntoIterator for &'a CompactRingBuffer<T, CAP, I> {
+    type Item = &'a T;
+    type IntoIter = RingBufferIterator<'a, T, CompactRingBuffer<T, CAP, I>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[allow(clippy::into_iter_without_iter)]
+// iter_mut() is implemented on the trait
+impl<'a, T, const CAP: usize, I: RingIndex> IntoIterator for &'a mut CompactRingBuffer<T, CAP, I> {
+    type Item = &'a mut T;
+    type IntoIter = RingBufferMutIterator<'a, T, CompactRingBuffer<T, CAP, I>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T, const CAP: usize, I: RingIndex> Extend<T> for CompactRingBuffer<T, CAP, I> {
+    fn extend<A: IntoIterator<Item = T>>(&mut self, iter: A) {
+        for i in iter {
+            let _ = self.enqueue(i);
+        }
+    }
+}
+
+unsafe impl<T, const CAP: usize, I: RingIndex> RingBuffer<T> for CompactRingBuffer<T, CAP, I> {
+    #[inline]
+    unsafe fn ptr_len(rb: *const Self) -> usize {
+        // the pointers wrap around at the maximum of I, their distance is still the length
+        let distance = (*rb)
+            .writeptr
+            .to_usize()
+            .wrapping_sub((*rb).readptr.to_usize());
+        I::from_usize_wrapping(distance).to_usize()
+    }
+
+    #[inline]
+    unsafe fn ptr_capacity(_: *const Self) -> usize {
+        CAP
+    }
+
+    #[inline]
+    unsafe fn ptr_buffer_size(_: *const Self) -> usize {
+        CAP
+    }
+
+    #[inline]
+    fn enqueue(&mut self, value: T) -> Option<T> {
+        let mut ret = None;
+
+        if self.is_full() {
+            let index = Self::mask(self.readptr.to_usize());
+            let previous_value = mem::replace(&mut self.buf[index], MaybeUninit::uninit());
+            // SAFETY: the buffer is full, so this must be initialized
+            ret = Some(unsafe { previous_value.assume_init() });
+            self.advance_readptr();
+        }
+        let index = Self::mask(self.writeptr.to_usize());
+        self.buf[index] = MaybeUninit::new(value);
+        // the buffer holds fewer than CAP items here, so this stays below readptr + CAP
+        self.writeptr = I::from_usize_wrapping(self.writeptr.to_usize().wrapping_add(1));
+
+        ret
+    }
+
+    fn dequeue(&mut self) -> Option<T> {
+        if self.is_empty() {
+            None
+        } else {
+            let index = Self::mask(self.readptr.to_usize());
+            let res = mem::replace(&mut self.buf[index], MaybeUninit::uninit());
+            self.advance_readptr();
+
+            // SAFETY: the buffer isn't empty, so the element at the read pointer is initialized
+            unsafe { Some(res.assume_init()) }
+        }
+    }
+
+    #[inline]
+    fn fill_with<F: FnMut() -> T>(&mut self, mut f: F) {
+        self.clear();
+        self.readptr = I::ZERO;
+        self.writeptr = I::from_usize_wrapping(CAP);
+        self.buf.fill_with(|| MaybeUninit::new(f()));
+    }
+
+    #[inline]
+    fn clear(&mut self) {
+        self.drain().for_each(drop);
+
+        self.readptr = I::ZERO;
+        self.writeptr = I::ZERO;
+    }
+
+    #[inline]
+    fn get_signed(&self, index: isize) -> Option<&T> {
+        let offset = Self::offset_signed(self.len(), index)?;
+        // SAFETY: the offset is within the length, so the element is initialized
+        Some(unsafe { self.buf[Self::ptr_index(self, offset)].assume_init_ref() })
+    }
+
+    #[inline]
+    fn get(&self, index: usize) -> Option<&T> {
+        let len = self.len();
+        (len != 0).then(|| {
+            // SAFETY: the offset is within the length, so the element is initialized
+            unsafe { self.buf[Self::ptr_index(self, index % len)].assume_init_ref() }
+        })
+    }
+
+    #[inline]
+    #[doc(hidden)]
+    unsafe fn ptr_get_mut_signed(rb: *mut Self, index: isize) -> Option<*mut T> {
+        let offset = Self::offset_signed(Self::ptr_len(rb), index)?;
+        Some(Self::ptr_base_mut(rb).add(Self::ptr_index(rb, offset)))
+    }
+
+    #[inline]
+    #[doc(hidden)]
+    unsafe fn ptr_get_mut(rb: *mut Self, index: usize) -> Option<*mut T> {
+        let len = Self::ptr_len(rb);
+        (len != 0).then(|| Self::ptr_base_mut(rb).add(Self::ptr_index(rb, index % len)))
+    }
+
+    unsafe fn ptr_copy_to_slice(rb: *const Self, offset: usize, dst: &mut [T])
+    where
+        T: Copy,
+    {
+        let len = Self::ptr_len(rb);
+        let dst_len = dst.len();
+        assert!(
+            (offset == 0 && len == 0) || offset < len,
+            "offset ({offset}) is out of bounds for the current buffer length ({len})"
+        );
+        assert!(len - offset == dst_len, "destination slice length ({dst_len}) doesn't match buffer length ({len}) when considering the specified offset ({offset})");
+
+        if dst_len == 0 {
+            return;
+        }
+
+        let base: *const T = ptr::addr_of!((*rb).buf).cast();
+        // SAFETY: the index is within the buffer, and the length was checked against the buffer
+        // length
+        unsafe { crate::copy_wrapping_to_slice(base, CAP, Self::ptr_index(rb, offset), dst) };
+    }
+
+    unsafe fn ptr_copy_from_slice(rb: *mut Self, offset: usize, src: &[T])
+    where
+        T: Copy,
+    {
+        let len = Self::ptr_len(rb);
+        let src_len = src.len();
+        assert!(
+            (offset == 0 && len == 0) || offset < len,
+            "offset ({offset}) is out of bounds for the current buffer length ({len})"
+        );
+        assert!(len - offset == src_len, "source slice length ({src_len}) doesn't match buffer length ({len}) when considering the specified offset ({offset})");
+
+        if src_len == 0 {
+            return;
+        }
+
+        let base = Self::ptr_base_mut(rb);
+        let from_idx = Self::ptr_index(rb, offset);
+        let first = src_len.min(CAP - from_idx);
+        // SAFETY: both regions are within the buffer, and the length was checked against the
+        // buffer length
+        unsafe {
+            ptr::copy_nonoverlapping(src.as_ptr(), base.add(from_idx), first);
+            ptr::copy_nonoverlapping(src.as_ptr().add(first), base, src_len - first);
+        }
+    }
+}
+
+impl<T, const CAP: usize, I: RingIndex> Default for CompactRingBuffer<T, CAP, I> {
+    #[inline]
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T, const CAP: usize, I: RingIndex> FromIterator<T> for CompactRingBuffer<T, CAP, I> {
+    fn from_iter<It: IntoIterator<Item = T>>(iter: It) -> Self {
+        let mut res = Self::new();
+        res.extend(iter);
+        res
+    }
+}
+
+impl<T, const CAP: usize, I: RingIndex> Index<usize> for CompactRingBuffer<T, CAP, I> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get(index).expect("index out of bounds")
+    }
+}
+
+impl<T, const CAP: usize, I: RingIndex> IndexMut<usize> for CompactRingBuffer<T, CAP, I> {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        self.get_mut(index).expect("index out of bounds")
+    }
+}
+
+impl<T, const CAP: usize, I: RingIndex> RingBufferSlices<T> for CompactRingBuffer<T, CAP, I> {
+    fn as_slices(&self) -> (&[T], &[T]) {
+        let len = self.len();
+        let start = Self::mask(self.readptr.to_usize());
+        let first = len.min(CAP - start);
+        let base: *const T = self.buf.as_ptr().cast();
+        // SAFETY: both regions are within the buffer, and hold the len initialized elements
+        unsafe {
+            (
+                core::slice::from_raw_parts(base.add(start), first),
+                core::slice::from_raw_parts(base, len - first),
+            )
+        }
+    }
+}
+
+impl<T, const CAP: usize, I: RingIndex> SetLen for CompactRingBuffer<T, CAP, I> {
+    #[inline]
+    unsafe fn set_len(&mut self, new_len: usize) {
+        let cap = self.capacity();
+        assert!(
+            new_len <= cap,
+            "Cannot set the a length of {new_len} on a ringbuffer with capacity for {cap} items"
+        );
+        self.writeptr = I::from_usize_wrapping(self.readptr.to_usize().wrapping_add(new_len));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::CompactRingBuffer;
+    use crate::{ConstGenericRingBuffer, RingBuffer};
+    use alloc::vec::Vec;
+    use core::mem::size_of;
+
+    #[test]
+    fn test_size() {
+        assert_eq!(size_of::<CompactRingBuffer<u8, 16>>(), 18);
+        assert_eq!(size_of::<CompactRingBuffer<u8, 128, u16>>(), 132);
+        assert!(
+            size_of::<CompactRingBuffer<u8, 16>>() < size_of::<ConstGenericRingBuffer<u8, 16>>()
+        );
+    }
+
+    #[test]
+    fn test_counters_wrap_at_index_max() {
+        use crate::tests::check_counters_wrap;
+
+        // power of two sizes wrap around at the maximum of the index type, start them just before
+        let mut rb = CompactRingBuffer::<usize, 128>::new();
+        rb.readptr = 250;
+        rb.writeptr = 250;
+        check_counters_wrap(&mut rb);
+
+        let mut rb = CompactRingBuffer::<usize, 8, u16>::new();
+        rb.readptr = u16::MAX - 3;
+        rb.writeptr = u16::MAX - 3;
+        check_counters_wrap(&mut rb);
+
+        // on 32-bit targets these pointers are as wide as a usize, so adding to them overflows
+        #[cfg(not(target_pointer_width = "16"))]
+        {
+            let mut rb = CompactRingBuffer::<usize, 8, u32>::new();
+            rb.readptr = u32::MAX - 3;
+            rb.writeptr = u32::MAX - 3;
+            check_counters_wrap(&mut rb);
+            unsafe { crate::SetLen::set_len(&mut rb, 0) };
+            assert!(rb.is_empty());
+        }
+
+        // other sizes keep their pointers below 2 * CAP, the largest fills up all of u8
+        let mut rb = CompactRingBuffer::<usize, 127>::new();
+        rb.readptr = 120;
+        rb.writeptr = 120;
+        check_counters_wrap(&mut rb);
+        assert!(rb.readptr < 127);
+    }
+
+    #[test]
+    fn test_same_as_const_generic() {
+        fn check<const CAP: usize>() {
+            let mut compact = CompactRingBuffer::<usize, CAP>::new();
+            let mut reference = ConstGenericRingBuffer::<usize, CAP>::new();
+            for i in 0..1000 {
+                if i % 7 < 4 {
+                    assert_eq!(compact.enqueue(i), reference.enqueue(i));
+                } else {
+                    assert_eq!(compact.dequeue(), reference.dequeue());
+                }
+                assert_eq!(compact.len(), reference.len());
+                assert_eq!(compact.get_signed(-2), reference.get_signed(-2));
+                assert_eq!(compact.get(3), reference.get(3));
+                assert!(compact.iter().eq(reference.iter()));
+                assert!(compact.iter().rev().eq(reference.iter().rev()));
+            }
+        }
+
+        check::<1>();
+        check::<5>();
+        check::<8>();
+        check::<128>();
+    }
+
+    #[test]
+    fn test_copy_slices_across_wrap() {
+        let mut rb = CompactRingBuffer::<u32, 5>::new();
+        rb.extend(0..8);
+        let mut dst = [0; 4];
+        rb.copy_to_slice(1, &mut dst);
+        assert_eq!(dst, [4, 5, 6, 7]);
+
+        rb.copy_from_slice(0, &[10, 11, 12, 13, 14]);
+        assert_eq!(rb.to_vec(), Vec::from([10, 11, 12, 13, 14]));
+    }
+
+    #[test]
+    fn test_clone_and_eq() {
+        let mut rb = CompactRingBuffer::<u8, 4>::new();
+        rb.extend(0..6);
+        let clone = rb.clone();
+        assert_eq!(rb, clone);
+        let _ = rb.dequeue();
+        assert_ne!(rb, clone);
+    }
+}
@@ -0,0 +1,676 @@
+//! A test suite for implementations of [`RingBuffer`], including ones outside this crate.
+//!
+//! [`RingBuffer`] is an `unsafe trait`: [`iter_mut`](RingBuffer::iter_mut) relies on
+//! implementations returning distinct elements for distinct indices, among other things. The
+//! checks in this module exercise an implementation through the whole trait, and compare it
+//! against a `VecDeque` holding what the buffer should hold. They panic on the first difference.
+//!
+//! The suite creates its buffers through a [`Constructor`] for every element type a check uses:
+//! `usize` for most checks, [`Tracked`], which counts how often it is dropped, and the zero sized
+//! `()`. Use [`run_all`] to run every check, [`run_copy`] to run every check but
+//! [`check_drops`] for buffers which only hold `Copy` elements, or the `check_*` functions to run
+//! them one by one.
+//!
+//! This module requires the `conformance` feature.
+//!
+//! # Example
+//! ```
+//! use ringbuffer::conformance::{self, Constructor};
+//! use ringbuffer::{AllocRingBuffer, MirroredRingBuffer, Pod};
+//!
+//! struct Alloc(usize);
+//!
+//! impl<T> Constructor<T> for Alloc {
+//!     type Buffer = AllocRingBuffer<T>;
+//!
+//!     fn new_buffer(&self) -> Self::Buffer {
+//!         AllocRingBuffer::new(self.0)
+//!     }
+//! }
+//!
+//! conformance::run_all(&Alloc(5));
+//!
+//! struct Mirrored(usize);
+//!
+//! impl<T: Pod> Constructor<T> for Mirrored {
+//!     type Buffer = MirroredRingBuffer<T>;
+//!
+//!     fn new_buffer(&self) -> Self::Buffer {
+//!         MirroredRingBuffer::new(self.0)
+//!     }
+//! }
+//!
+//! conformance::run_copy(&Mirrored(5));
+//! ```
+
+extern crate alloc;
+
+use alloc::collections::VecDeque;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::cell::Cell;
+use core::ptr;
+
+use crate::{RingBuffer, RingBufferSlices};
+
+/// Creates the buffers with elements of type `T` which [`run_all`] and the `check_*` functions
+/// run against.
+///
+/// Implement this for every `T` when the buffer can hold any element type, or only for the
+/// element types it can hold, such as `T: Pod`.
+pub trait Constructor<T> {
+    /// Whether the buffer overwrites its oldest element when an element is enqueued while it is
+    /// full, like most ringbuffers do. Set this to `false` for buffers which grow instead, like
+    /// [`GrowableAllocRingBuffer`](crate::GrowableAllocRingBuffer).
+    const OVERWRITES: bool = true;
+
+    /// The ringbuffer under test.
+    type Buffer: RingBuffer<T>;
+
+    /// Creates an empty buffer with a capacity of at least 1. Every call should create a buffer
+    /// with the same capacity.
+    fn new_buffer(&self) -> Self::Buffer;
+}
+
+/// Runs every check of this module against the buffers `constructor` creates.
+pub fn run_all<C>(constructor: &C)
+where
+    C: Constructor<usize> + Constructor<()> + for<'a> Constructor<Tracked<'a>>,
+{
+    run_copy(constructor);
+    check_drops(constructor);
+}
+
+/// Runs every check of this module except [`check_drops`], which needs a buffer of an element
+/// type that isn't `Copy`, against the buffers `constructor` creates.
+pub fn run_copy<C>(constructor: &C)
+where
+    C: Constructor<usize> + Constructor<()>,
+{
+    check_basics(constructor);
+    check_wraparound(constructor);
+    check_indexing(constructor);
+    check_iter_mut_aliasing(constructor);
+    check_copy_slices(constructor);
+    check_zero_sized(constructor);
+}
+
+/// Enqueues `value` onto `model` like a buffer of the given capacity would.
+fn model_enqueue<C: Constructor<T>, T>(
+    model: &mut VecDeque<T>,
+    capacity: usize,
+    value: T,
+) -> Option<T> {
+    let overwritten = if C::OVERWRITES && model.len() == capacity {
+        model.pop_front()
+    } else {
+        None
+    };
+    model.push_back(value);
+    overwritten
+}
+
+/// Compares everything `rb` exposes through shared references against `model`.
+fn assert_matches_model<B: RingBuffer<usize>>(rb: &B, model: &VecDeque<usize>) {
+    let len = model.len();
+    assert_eq!(rb.len(), len, "len");
+    assert_eq!(rb.is_empty(), len == 0, "is_empty");
+    assert_eq!(
+        rb.to_vec(),
+        model.iter().copied().collect::<Vec<_>>(),
+        "to_vec"
+    );
+    assert!(rb.iter().eq(model.iter()), "iter");
+    assert!(rb.iter().rev().eq(model.iter().rev()), "iter().rev()");
+    assert_eq!(rb.iter().len(), len, "iter().len()");
+    assert_eq!(rb.front(), model.front(), "front");
+    assert_eq!(rb.peek(), model.front(), "peek");
+    assert_eq!(rb.back(), model.back(), "back");
+
+    for (i, expected) in model.iter().enumerate() {
+        assert_eq!(rb.get(i), Some(expected), "get({i})");
+        assert_eq!(rb.get_checked(i), Some(expected), "get_checked({i})");
+        assert_eq!(&rb[i], expected, "index {i}");
+
+        let from_back = i as isize - len as isize;
+        assert_eq!(
+            rb.get_signed(from_back),
+            Some(expected),
+            "get_signed({from_back})"
+        );
+        assert_eq!(
+            rb.get_checked_signed(from_back),
+            Some(expected),
+            "get_checked_signed({from_back})"
+        );
+    }
+    assert_eq!(rb.get_checked(len), None, "get_checked({len})");
+    assert_eq!(
+        rb.get_checked_signed(-(len as isize) - 1),
+        None,
+        "get_checked_signed past the front"
+    );
+}
+
+/// Checks the state of a new buffer, filling it up, enqueueing once it is full, dequeueing,
+/// draining, extending and clearing.
+pub fn check_basics<C: Constructor<usize>>(constructor: &C) {
+    let mut rb = constructor.new_buffer();
+    let capacity = rb.capacity();
+    assert!(capacity > 0, "the capacity must be at least 1");
+    let mut model = VecDeque::new();
+
+    assert_matches_model(&rb, &model);
+    assert_eq!(rb.dequeue(), None, "dequeue from an empty buffer");
+    assert_eq!(rb.get(0), None, "get from an empty buffer");
+    assert_eq!(rb.get_signed(-1), None, "get_signed from an empty buffer");
+
+    for i in 0..capacity {
+        assert_eq!(rb.enqueue(i), None, "enqueue while not full");
+        model.push_back(i);
+        assert_matches_model(&rb, &model);
+    }
+    assert!(rb.is_full(), "is_full once capacity elements were enqueued");
+
+    for i in capacity..capacity + 3 {
+        let expected = model_enqueue::<C, _>(&mut model, capacity, i);
+        assert_eq!(rb.enqueue(i), expected, "enqueue while full");
+        assert_matches_model(&rb, &model);
+    }
+    assert!(rb.contains(&(capacity + 2)), "contains the newest element");
+    assert!(
+        !rb.contains(&usize::MAX),
+        "contains an element never enqueued"
+    );
+
+    assert_eq!(rb.dequeue(), model.pop_front(), "dequeue");
+    assert_matches_model(&rb, &model);
+
+    let drained: Vec<_> = rb.drain().collect();
+    assert_eq!(drained, model.drain(..).collect::<Vec<_>>(), "drain");
+    assert_matches_model(&rb, &model);
+
+    rb.extend(0..capacity + 1);
+    for i in 0..capacity + 1 {
+        let _ = model_enqueue::<C, _>(&mut model, capacity, i);
+    }
+    assert_matches_model(&rb, &model);
+
+    rb.clear();
+    model.clear();
+    assert_matches_model(&rb, &model);
+    assert_eq!(rb.enqueue(1), None, "enqueue after clear");
+    model.push_back(1);
+    assert_matches_model(&rb, &model);
+}
+
+/// Checks a long mix of enqueues and dequeues, which moves the elements around the end of the
+/// buffer's storage many times.
+pub fn check_wraparound<C: Constructor<usize>>(constructor: &C) {
+    let mut rb = constructor.new_buffer();
+    let capacity = rb.capacity();
+    let mut model = VecDeque::new();
+
+    for i in 0..5 * capacity + 7 {
+        let expected = model_enqueue::<C, _>(&mut model, capacity, i);
+        assert_eq!(rb.enqueue(i), expected, "enqueue {i}");
+        if i % 3 == 0 {
+            assert_eq!(rb.dequeue(), model.pop_front(), "dequeue after enqueue {i}");
+        }
+        assert_matches_model(&rb, &model);
+    }
+}
+
+/// Checks that indexing past the length wraps around for the wrapping accessors, and doesn't
+/// for the checked ones, and that mutable access changes the right element.
+pub fn check_indexing<C: Constructor<usize>>(constructor: &C) {
+    let mut rb = constructor.new_buffer();
+    let capacity = rb.capacity();
+    let mut model = VecDeque::new();
+    for i in 0..capacity + capacity / 2 {
+        let _ = rb.enqueue(i);
+        let _ = model_enqueue::<C, _>(&mut model, capacity, i);
+    }
+
+    let len = model.len();
+    for i in 0..len {
+        assert_eq!(rb.get(i + len), model.get(i), "get wraps around the length");
+        assert_eq!(
+            rb.get_signed(-(i as isize) - 1 - len as isize),
+            model.get(len - 1 - i),
+            "get_signed wraps around the length"
+        );
+        assert_eq!(rb.get_checked(i + len), None, "get_checked past the length");
+    }
+
+    for i in 0..len {
+        *rb.get_mut(i).expect("get_mut within the length") += 100;
+        model[i] += 100;
+        rb[i] += 1;
+        model[i] += 1;
+        *rb.get_checked_mut(i)
+            .expect("get_checked_mut within the length") += 1;
+        model[i] += 1;
+    }
+    *rb.get_mut_signed(-1).expect("get_mut_signed(-1)") += 1;
+    *model.back_mut().expect("a full model") += 1;
+    *rb.front_mut().expect("front_mut") += 1;
+    *model.front_mut().expect("a full model") += 1;
+    *rb.back_mut().expect("back_mut") += 1;
+    *model.back_mut().expect("a full model") += 1;
+    assert_eq!(
+        rb.get_checked_mut(len),
+        None,
+        "get_checked_mut past the length"
+    );
+    assert_matches_model(&rb, &model);
+}
+
+/// Checks the safety requirement [`iter_mut`](RingBuffer::iter_mut) relies on: distinct indices
+/// give distinct elements. All mutable references from one iteration are kept alive and written
+/// through at the same time.
+pub fn check_iter_mut_aliasing<C: Constructor<usize>>(constructor: &C) {
+    let mut rb = constructor.new_buffer();
+    let capacity = rb.capacity();
+    let mut model = VecDeque::new();
+    for i in 0..2 * capacity + 1 {
+        let _ = rb.enqueue(i);
+        let _ = model_enqueue::<C, _>(&mut model, capacity, i);
+        if i % 4 == 0 {
+            let _ = rb.dequeue();
+            let _ = model.pop_front();
+        }
+    }
+    let len = model.len();
+
+    let from_get_mut: Vec<*mut usize> = (0..len)
+        .map(|i| ptr::from_mut(rb.get_mut(i).expect("get_mut within the length")))
+        .collect();
+    for (i, a) in from_get_mut.iter().enumerate() {
+        for b in &from_get_mut[i + 1..] {
+            assert_ne!(
+                a, b,
+                "get_mut returned the same element for different indices"
+            );
+        }
+    }
+
+    let mut all: Vec<&mut usize> = rb.iter_mut().collect();
+    assert_eq!(all.len(), len, "iter_mut length");
+    let from_iter_mut: Vec<*mut usize> = all.iter_mut().map(|r| ptr::from_mut(*r)).collect();
+    assert_eq!(
+        from_iter_mut, from_get_mut,
+        "iter_mut and get_mut give different elements"
+    );
+    for (i, element) in all.into_iter().enumerate() {
+        *element = 1000 + i;
+    }
+    for (i, element) in model.iter_mut().enumerate() {
+        *element = 1000 + i;
+    }
+    assert_matches_model(&rb, &model);
+
+    for element in rb.iter_mut().rev() {
+        *element += 1;
+    }
+    for element in &mut model {
+        *element += 1;
+    }
+    assert_matches_model(&rb, &model);
+}
+
+/// Counts how many [`Tracked`] elements exist.
+struct DropCounter {
+    alive: Cell<usize>,
+}
+
+/// The element type of the buffers [`check_drops`] creates, which counts how many of it exist,
+/// to find elements which are dropped twice or never.
+pub struct Tracked<'a> {
+    value: usize,
+    counter: &'a DropCounter,
+}
+
+impl<'a> Tracked<'a> {
+    fn new(value: usize, counter: &'a DropCounter) -> Self {
+        counter.alive.set(counter.alive.get() + 1);
+        Self { value, counter }
+    }
+}
+
+impl Clone for Tracked<'_> {
+    fn clone(&self) -> Self {
+        Self::new(self.value, self.counter)
+    }
+}
+
+impl Drop for Tracked<'_> {
+    fn drop(&mut self) {
+        let alive = self.counter.alive.get();
+        assert!(alive > 0, "an element was dropped twice");
+        self.counter.alive.set(alive - 1);
+    }
+}
+
+/// Checks that every element is dropped exactly once: when it is overwritten, dequeued,
+/// drained, cleared, replaced by [`fill`](RingBuffer::fill), or still in the buffer when it is
+/// dropped or turned into an iterator.
+pub fn check_drops<C>(constructor: &C)
+where
+    C: for<'a> Constructor<Tracked<'a>>,
+{
+    let counter = DropCounter {
+        alive: Cell::new(0),
+    };
+    let alive = || counter.alive.get();
+
+    {
+        let mut rb = constructor.new_buffer();
+        let capacity = rb.capacity();
+        for i in 0..2 * capacity + 1 {
+            drop(rb.enqueue(Tracked::new(i, &counter)));
+            assert_eq!(alive(), rb.len(), "elements alive after enqueue");
+        }
+
+        drop(rb.dequeue());
+        assert_eq!(alive(), rb.len(), "elements alive after dequeue");
+
+        rb.drain().take(1).for_each(drop);
+        assert_eq!(alive(), rb.len(), "elements alive after draining one");
+
+        rb.fill(Tracked::new(0, &counter));
+        assert_eq!(alive(), rb.len(), "elements alive after fill");
+
+        rb.clear();
+        assert_eq!(alive(), 0, "elements alive after clear");
+
+        for i in 0..capacity + 1 {
+            drop(rb.enqueue(Tracked::new(i, &counter)));
+        }
+        let mut iter = rb.into_iter();
+        let first = iter.next().map(|t| t.value);
+        assert!(first.is_some(), "into_iter of a non-empty buffer");
+        drop(iter);
+        assert_eq!(
+            alive(),
+            0,
+            "elements alive after dropping a partially consumed into_iter"
+        );
+    }
+
+    {
+        let mut rb = constructor.new_buffer();
+        for i in 0..rb.capacity() {
+            drop(rb.enqueue(Tracked::new(i, &counter)));
+        }
+        let _ = rb.dequeue();
+        drop(rb.enqueue(Tracked::new(0, &counter)));
+    }
+    assert_eq!(alive(), 0, "elements alive after dropping the buffer");
+}
+
+/// Checks [`copy_to_slice`](RingBuffer::copy_to_slice) and
+/// [`copy_from_slice`](RingBuffer::copy_from_slice) at every offset, for an empty, a partially
+/// filled, a full and a wrapped around buffer.
+pub fn check_copy_slices<C: Constructor<usize>>(constructor: &C) {
+    let mut rb = constructor.new_buffer();
+    let capacity = rb.capacity();
+    let mut model = VecDeque::new();
+
+    // copying nothing from an empty buffer is fine
+    rb.copy_to_slice(0, &mut []);
+    rb.copy_from_slice(0, &[]);
+    assert_matches_model(&rb, &model);
+
+    for round in 0..2 * capacity + 2 {
+        let _ = rb.enqueue(round);
+        let _ = model_enqueue::<C, _>(&mut model, capacity, round);
+        if round % 3 == 2 {
+            let _ = rb.dequeue();
+            let _ = model.pop_front();
+        }
+
+        let len = model.len();
+        for offset in 0..len {
+            let mut dst = vec![usize::MAX; len - offset];
+            rb.copy_to_slice(offset, &mut dst);
+            assert!(
+                dst.iter().eq(model.range(offset..)),
+                "copy_to_slice at offset {offset} of {len}"
+            );
+
+            let src: Vec<_> = (0..len - offset)
+                .map(|i| 10_000 * (round + 1) + i)
+                .collect();
+            rb.copy_from_slice(offset, &src);
+            for (element, value) in model.range_mut(offset..).zip(&src) {
+                *element = *value;
+            }
+            assert_matches_model(&rb, &model);
+        }
+    }
+}
+
+/// Checks [`as_slices`](RingBufferSlices::as_slices) and [`dot`](RingBufferSlices::dot) for
+/// every length, while the buffer fills up, wraps around and empties again.
+///
+/// [`run_all`] doesn't run this check, since not every [`RingBuffer`] implements
+/// [`RingBufferSlices`].
+pub fn check_slices<C>(constructor: &C)
+where
+    C: Constructor<usize>,
+    C::Buffer: RingBufferSlices<usize>,
+{
+    let mut rb = constructor.new_buffer();
+    let capacity = rb.capacity();
+    let mut model = VecDeque::new();
+
+    let check = |rb: &C::Buffer, model: &VecDeque<usize>| {
+        let (first, second) = rb.as_slices();
+        assert!(first.iter().chain(second).eq(model.iter()), "as_slices");
+        let kernel: Vec<_> = (1..=model.len()).collect();
+        assert_eq!(
+            rb.dot(&kernel),
+            model.iter().zip(&kernel).map(|(x, k)| x * k).sum(),
+            "dot"
+        );
+    };
+
+    check(&rb, &model);
+    for round in 0..2 * capacity + 2 {
+        let _ = rb.enqueue(round);
+        let _ = model_enqueue::<C, _>(&mut model, capacity, round);
+        check(&rb, &model);
+    }
+    while rb.dequeue().is_some() {
+        let _ = model.pop_front();
+        check(&rb, &model);
+    }
+}
+
+/// Checks a buffer of a zero sized type, which has no storage to index into.
+pub fn check_zero_sized<C: Constructor<()>>(constructor: &C) {
+    let mut rb = constructor.new_buffer();
+    let capacity = rb.capacity();
+    let mut len = 0;
+
+    // a buffer without storage may report an enormous capacity
+    for _ in 0..capacity.min(64) + 3 {
+        let overwritten = rb.enqueue(());
+        if C::OVERWRITES && len == capacity {
+            assert_eq!(overwritten, Some(()), "enqueue while full");
+        } else {
+            assert_eq!(overwritten, None, "enqueue while not full");
+            len += 1;
+        }
+        assert_eq!(rb.len(), len, "len");
+        assert_eq!(rb.iter().count(), len, "iter().count()");
+        assert_eq!(rb.iter_mut().count(), len, "iter_mut().count()");
+    }
+
+    assert_eq!(rb.get(len - 1), Some(&()), "get");
+    assert_eq!(rb.dequeue(), Some(()), "dequeue");
+    rb.clear();
+    assert!(rb.is_empty(), "is_empty after clear");
+    assert_eq!(rb.dequeue(), None, "dequeue from an empty buffer");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{check_slices, run_all, run_copy, Constructor};
+    use crate::{
+        AllocRingBuffer, CompactRingBuffer, ConstGenericRingBuffer, GrowableAllocRingBuffer,
+        MirroredRingBuffer, Pod, RingBufferCore, SliceRingBuffer,
+    };
+    use alloc::boxed::Box;
+    use core::mem::MaybeUninit;
+
+    struct Alloc(usize);
+
+    impl<T> Constructor<T> for Alloc {
+        type Buffer = AllocRingBuffer<T>;
+
+        fn new_buffer(&self) -> Self::Buffer {
+            AllocRingBuffer::new(self.0)
+        }
+    }
+
+    struct AllocExact(usize);
+
+    impl<T> Constructor<T> for AllocExact {
+        type Buffer = AllocRingBuffer<T>;
+
+        fn new_buffer(&self) -> Self::Buffer {
+            AllocRingBuffer::new_exact(self.0)
+        }
+    }
+
+    struct ConstGeneric<const CAP: usize>;
+
+    impl<T, const CAP: usize> Constructor<T> for ConstGeneric<CAP> {
+        type Buffer = ConstGenericRingBuffer<T, CAP>;
+
+        fn new_buffer(&self) -> Self::Buffer {
+            ConstGenericRingBuffer::new()
+        }
+    }
+
+    struct Compact<const CAP: usize>;
+
+    impl<T, const CAP: usize> Constructor<T> for Compact<CAP> {
+        type Buffer = CompactRingBuffer<T, CAP>;
+
+        fn new_buffer(&self) -> Self::Buffer {
+            CompactRingBuffer::new()
+        }
+    }
+
+    struct Core(usize);
+
+    impl<T> Constructor<T> for Core {
+        type Buffer = RingBufferCore<Box<[MaybeUninit<T>]>>;
+
+        fn new_buffer(&self) -> Self::Buffer {
+            RingBufferCore::new((0..self.0).map(|_| MaybeUninit::uninit()).collect())
+        }
+    }
+
+    struct Growable(usize);
+
+    impl<T> Constructor<T> for Growable {
+        const OVERWRITES: bool = false;
+
+        type Buffer = GrowableAllocRingBuffer<T>;
+
+        fn new_buffer(&self) -> Self::Buffer {
+            GrowableAllocRingBuffer::with_capacity(self.0)
+        }
+    }
+
+    struct Slice(usize);
+
+    impl<T: 'static> Constructor<T> for Slice {
+        type Buffer = SliceRingBuffer<'static, T>;
+
+        fn new_buffer(&self) -> Self::Buffer {
+            // leaks the storage, which is fine for the few buffers of a test
+            let buf = Box::leak((0..self.0).map(|_| MaybeUninit::uninit()).collect());
+            SliceRingBuffer::new(buf)
+        }
+    }
+
+    struct Mirrored(usize);
+
+    impl<T: Pod> Constructor<T> for Mirrored {
+        type Buffer = MirroredRingBuffer<T>;
+
+        fn new_buffer(&self) -> Self::Buffer {
+            MirroredRingBuffer::new(self.0)
+        }
+    }
+
+    #[cfg(all(feature = "mmap", target_os = "linux"))]
+    struct Mmap(usize);
+
+    #[cfg(all(feature = "mmap", target_os = "linux"))]
+    impl<T: Pod> Constructor<T> for Mmap {
+        type Buffer = crate::MmapRingBuffer<T>;
+
+        fn new_buffer(&self) -> Self::Buffer {
+            extern crate std;
+            use core::sync::atomic::{AtomicUsize, Ordering};
+            use std::format;
+
+            static COUNTER: AtomicUsize = AtomicUsize::new(0);
+            let path = std::env::temp_dir().join(format!(
+                "ringbuffer-conformance-{}-{}",
+                std::process::id(),
+                COUNTER.fetch_add(1, Ordering::Relaxed)
+            ));
+            let rb = crate::MmapRingBuffer::create(&path, self.0).unwrap();
+            // the mapping stays valid without the file
+            let _ = std::fs::remove_file(&path);
+            rb
+        }
+    }
+
+    #[test]
+    fn test_conformance() {
+        for capacity in [1, 2, 5, 8] {
+            run_all(&Alloc(capacity));
+            run_all(&AllocExact(capacity));
+            run_all(&Core(capacity));
+            run_all(&Growable(capacity));
+            // the leaked storage needs 'static elements, which Tracked isn't
+            run_copy(&Slice(capacity));
+            check_slices(&Alloc(capacity));
+            check_slices(&AllocExact(capacity));
+            check_slices(&Core(capacity));
+            check_slices(&Growable(capacity));
+            check_slices(&Slice(capacity));
+        }
+        run_all(&ConstGeneric::<1>);
+        run_all(&ConstGeneric::<7>);
+        run_all(&ConstGeneric::<8>);
+        run_all(&Compact::<1>);
+        run_all(&Compact::<7>);
+        run_all(&Compact::<128>);
+        check_slices(&ConstGeneric::<1>);
+        check_slices(&ConstGeneric::<7>);
+        check_slices(&Compact::<7>);
+        check_slices(&Compact::<128>);
+    }
+
+    #[test]
+    fn test_conformance_pod() {
+        for capacity in [1, 2, 5, 8] {
+            run_copy(&Mirrored(capacity));
+            check_slices(&Mirrored(capacity));
+            #[cfg(all(feature = "mmap", target_os = "linux"))]
+            {
+                run_copy(&Mmap(capacity));
+                check_slices(&Mmap(capacity));
+            }
+        }
+    }
+}
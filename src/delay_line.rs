@@ -0,0 +1,409 @@
+/// How a [`DelayLine`] reads between two samples when the delay isn't a whole number of samples.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Interpolation {
+    /// Draws a straight line between the two samples around the delay. Cheap, but it dampens
+    /// high frequencies more the closer the delay is to halfway between samples.
+    #[default]
+    Linear,
+    /// Fits a cubic Hermite (Catmull-Rom) spline through the four samples around the delay.
+    /// Flatter frequency response than linear, at about twice the cost.
+    Cubic,
+    /// Runs the samples through a first order allpass filter, which passes every frequency at
+    /// full volume, but depends on the previous output. It suits slowly modulated delays, such
+    /// as in a chorus, better than rapidly jumping ones. Delays below one sample are
+    /// interpolated linearly.
+    Allpass,
+}
+
+/// The `DelayLine` is a fixed size ringbuffer of `f32` samples, which are read back after a
+/// delay that doesn't have to be a whole number of samples.
+///
+/// Every sample which is [`tick`](DelayLine::tick)ed in is written into the buffer, and the
+/// sample [`delay`](DelayLine::delay) samples before it is read out, interpolating between the
+/// samples around it as set by the [`Interpolation`]. A delay of 0 returns the input itself.
+/// [`process`](DelayLine::process) does the same for a whole block of samples, and
+/// [`process_modulated`](DelayLine::process_modulated) takes a delay for every sample, for
+/// effects like chorus and flanger.
+///
+/// The buffer is stored inline, like a [`ConstGenericRingBuffer`](crate::ConstGenericRingBuffer),
+/// so this doesn't need `alloc`. It holds `N` samples, of which the cubic interpolation needs
+/// three around the delay, so the longest delay is `N - 3` samples. Longer delays are clamped to
+/// that. Like a [`FirFilter`](crate::FirFilter), the samples are stored twice, back to back, so
+/// the last `N` of them are always one contiguous slice. Reads index into that slice directly,
+/// without wrapping around the end of the buffer, at the cost of writing each sample twice.
+///
+/// # Example
+/// ```
+/// use ringbuffer::{DelayLine, Interpolation};
+///
+/// let mut delay = DelayLine::<16>::new(Interpolation::Linear);
+/// delay.set_delay(1.5);
+///
+/// let mut output = [0.0; 4];
+/// delay.process(&[2.0, 0.0, 0.0, 0.0], &mut output);
+/// assert_eq!(output, [0.0, 1.0, 1.0, 0.0]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct DelayLine<const N: usize> {
+    // the samples, written to both halves
+    buf: [[f32; N]; 2],
+    // the slot the next sample is written to, always below N between calls
+    writeptr: usize,
+    delay: f32,
+    interpolation: Interpolation,
+    // the previous output of the allpass interpolation
+    allpass_state: f32,
+}
+
+impl<const N: usize> DelayLine<N> {
+    const ERROR_SIZE_IS_TOO_SMALL: () = assert!(N >= 4, "Size must be at least 4 samples");
+
+    /// Creates a delay line filled with silence, with a delay of 0.
+    #[must_use]
+    pub const fn new(interpolation: Interpolation) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::ERROR_SIZE_IS_TOO_SMALL;
+
+        Self {
+            buf: [[0.0; N]; 2],
+            writeptr: 0,
+            delay: 0.0,
+            interpolation,
+            allpass_state: 0.0,
+        }
+    }
+
+    /// Returns the longest delay, in samples.
+    #[inline]
+    #[must_use]
+    #[allow(clippy::cast_precision_loss)]
+    pub const fn max_delay() -> f32 {
+        (N - 3) as f32
+    }
+
+    /// Returns the delay, in samples.
+    #[inline]
+    #[must_use]
+    pub fn delay(&self) -> f32 {
+        self.delay
+    }
+
+    /// Sets the delay, in samples. It is clamped to between 0 and
+    /// [`max_delay`](Self::max_delay).
+    #[inline]
+    pub fn set_delay(&mut self, delay: f32) {
+        self.delay = Self::clamp(delay);
+    }
+
+    /// Returns how reads are interpolated.
+    #[inline]
+    #[must_use]
+    pub fn interpolation(&self) -> Interpolation {
+        self.interpolation
+    }
+
+    /// Sets how reads are interpolated.
+    #[inline]
+    pub fn set_interpolation(&mut self, interpolation: Interpolation) {
+        self.interpolation = interpolation;
+        self.allpass_state = 0.0;
+    }
+
+    /// Fills the delay line with silence again.
+    pub fn clear(&mut self) {
+        self.buf = [[0.0; N]; 2];
+        self.allpass_state = 0.0;
+    }
+
+    #[inline]
+    fn clamp(delay: f32) -> f32 {
+        // also maps NaN to 0
+        if delay > 0.0 {
+            delay.min(Self::max_delay())
+        } else {
+            0.0
+        }
+    }
+
+    /// The last `N` samples written, oldest first.
+    #[inline]
+    fn window(&self) -> &[f32] {
+        // Safety: the two halves are arrays next to each other in an array, so together they
+        // are 2 * N contiguous samples, and writeptr is at most N
+        unsafe {
+            core::slice::from_raw_parts(self.buf.as_ptr().cast::<f32>().add(self.writeptr), N)
+        }
+    }
+
+    /// The sample `delay` whole samples before the last one written.
+    #[inline]
+    fn sample(&self, delay: usize) -> f32 {
+        self.window()[N - 1 - delay]
+    }
+
+    /// Writes a sample to both halves, without moving the `writeptr`.
+    #[inline]
+    fn write(&mut self, index: usize, input: f32) {
+        self.buf[0][index] = input;
+        self.buf[1][index] = input;
+    }
+
+    /// Splits a clamped delay into whole samples and the fraction in between.
+    #[inline]
+    #[allow(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    #[allow(clippy::cast_precision_loss)]
+    fn split(delay: f32) -> (usize, f32) {
+        let whole = delay as usize;
+        (whole, delay - whole as f32)
+    }
+
+    /// Reads the sample `delay` samples before the last one written, interpolating linearly.
+    /// This doesn't change the delay line, so it can be used for extra taps.
+    #[inline]
+    #[must_use]
+    pub fn read_linear(&self, delay: f32) -> f32 {
+        let (whole, frac) = Self::split(Self::clamp(delay));
+        let x0 = self.sample(whole);
+        let x1 = self.sample(whole + 1);
+        x0 + frac * (x1 - x0)
+    }
+
+    /// Reads the sample `delay` samples before the last one written, with cubic Hermite
+    /// interpolation. This doesn't change the delay line, so it can be used for extra taps.
+    #[inline]
+    #[must_use]
+    pub fn read_cubic(&self, delay: f32) -> f32 {
+        let (whole, frac) = Self::split(Self::clamp(delay));
+        // there is no sample after the last one written, so repeat it
+        let xm1 = self.sample(whole.saturating_sub(1));
+        let x0 = self.sample(whole);
+        let x1 = self.sample(whole + 1);
+        let x2 = self.sample(whole + 2);
+
+        let c1 = 0.5 * (x1 - xm1);
+        let c2 = xm1 - 2.5 * x0 + 2.0 * x1 - 0.5 * x2;
+        let c3 = 0.5 * (x2 - xm1) + 1.5 * (x0 - x1);
+        ((c3 * frac + c2) * frac + c1) * frac + x0
+    }
+
+    /// Reads the sample `delay` samples before the last one written with allpass interpolation,
+    /// which feeds back the previous output.
+    #[inline]
+    fn read_allpass(&mut self, delay: f32) -> f32 {
+        let delay = Self::clamp(delay);
+        if delay < 1.0 {
+            // there is no sample after the last one written to interpolate from
+            let out = self.read_linear(delay);
+            self.allpass_state = out;
+            return out;
+        }
+
+        // reads between the samples one earlier, with the fraction between 1 and 2. A fraction
+        // near 0 would put the pole of the filter near -1, where it rings at the Nyquist
+        // frequency for a long time, or forever at exactly 0. This keeps the pole within 1/3.
+        let (whole, frac) = Self::split(delay - 1.0);
+        let frac = frac + 1.0;
+        let eta = (1.0 - frac) / (1.0 + frac);
+        let out = eta * (self.sample(whole) - self.allpass_state) + self.sample(whole + 1);
+        self.allpass_state = out;
+        out
+    }
+
+    #[inline]
+    fn read(&mut self, delay: f32) -> f32 {
+        match self.interpolation {
+            Interpolation::Linear => self.read_linear(delay),
+            Interpolation::Cubic => self.read_cubic(delay),
+            Interpolation::Allpass => self.read_allpass(delay),
+        }
+    }
+
+    /// Writes a sample into the delay line, and returns the sample [`delay`](Self::delay)
+    /// samples before it.
+    #[inline]
+    pub fn tick(&mut self, input: f32) -> f32 {
+        self.write(self.writeptr, input);
+        self.writeptr = crate::mask_any(N, self.writeptr + 1);
+        self.read(self.delay)
+    }
+
+    /// Runs every sample of `input` through the delay line, like [`tick`](Self::tick), writing
+    /// the results to `output`.
+    ///
+    /// The block is written in contiguous passes, split where it wraps around the end of the
+    /// buffer. That is at most two passes for blocks no longer than the buffer. Within a pass,
+    /// the `writeptr` only counts up, and every read is a plain index into the contiguous last
+    /// `N` samples.
+    ///
+    /// # Panics
+    /// Panics if `input` and `output` differ in length.
+    pub fn process(&mut self, input: &[f32], output: &mut [f32]) {
+        let delay = self.delay;
+        self.process_with(input, output, |_| delay);
+    }
+
+    /// Runs every sample of `input` through the delay line, like [`process`](Self::process), but
+    /// reads each one after the delay at the same position in `delays`. The delays are clamped
+    /// like [`set_delay`](Self::set_delay) does, and [`delay`](Self::delay) is left as it is.
+    ///
+    /// # Panics
+    /// Panics if `input`, `delays` and `output` differ in length.
+    pub fn process_modulated(&mut self, input: &[f32], delays: &[f32], output: &mut [f32]) {
+        assert_eq!(
+            input.len(),
+            delays.len(),
+            "input and delays differ in length"
+        );
+        self.process_with(input, output, |i| delays[i]);
+    }
+
+    fn process_with(&mut self, input: &[f32], output: &mut [f32], delay: impl Fn(usize) -> f32) {
+        assert_eq!(
+            input.len(),
+            output.len(),
+            "input and output differ in length"
+        );
+
+        let mut done = 0;
+        while done < input.len() {
+            // the samples up to the end of the buffer, or the end of the block
+            let pass = (input.len() - done).min(N - self.writeptr);
+            let start = self.writeptr;
+            for i in 0..pass {
+                self.write(start + i, input[done + i]);
+                // may reach N at the end of the pass, where the window is the second half
+                self.writeptr = start + i + 1;
+                output[done + i] = self.read(delay(done + i));
+            }
+            self.writeptr = crate::mask_any(N, self.writeptr);
+            done += pass;
+        }
+    }
+}
+
+impl<const N: usize> Default for DelayLine<N> {
+    #[inline]
+    fn default() -> Self {
+        Self::new(Interpolation::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{DelayLine, Interpolation};
+
+    fn impulse_response<const N: usize>(delay: &mut DelayLine<N>, output: &mut [f32]) {
+        let mut input = [0.0; 64];
+        input[0] = 1.0;
+        delay.process(&input[..output.len()], output);
+    }
+
+    #[test]
+    fn test_whole_delays() {
+        for interpolation in [
+            Interpolation::Linear,
+            Interpolation::Cubic,
+            Interpolation::Allpass,
+        ] {
+            let mut delay = DelayLine::<8>::new(interpolation);
+            for d in 0..=5 {
+                // leaves the allpass state behind, which whole delays don't depend on
+                delay.set_delay(d as f32 + 0.5);
+                impulse_response(&mut delay, &mut [0.0; 12]);
+
+                delay.set_delay(d as f32);
+                let mut output = [0.0; 12];
+                impulse_response(&mut delay, &mut output);
+                for (i, sample) in output.iter().enumerate() {
+                    let expected = if i == d { 1.0 } else { 0.0 };
+                    assert!(
+                        (sample - expected).abs() < 1e-6,
+                        "{interpolation:?} {d} {i}"
+                    );
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_fractional_delays() {
+        let mut delay = DelayLine::<16>::new(Interpolation::Linear);
+        delay.set_delay(2.25);
+        let mut output = [0.0; 6];
+        impulse_response(&mut delay, &mut output);
+        assert_eq!(output, [0.0, 0.0, 0.75, 0.25, 0.0, 0.0]);
+
+        // the cubic response is symmetric around halfway and sums to one
+        let mut delay = DelayLine::<16>::new(Interpolation::Cubic);
+        delay.set_delay(3.5);
+        let mut output = [0.0; 8];
+        impulse_response(&mut delay, &mut output);
+        assert_eq!(
+            output,
+            [0.0, 0.0, -0.0625, 0.5625, 0.5625, -0.0625, 0.0, 0.0]
+        );
+
+        // the allpass delays low frequencies by exactly the delay, which is the centroid of its
+        // impulse response
+        let mut delay = DelayLine::<64>::new(Interpolation::Allpass);
+        delay.set_delay(4.3);
+        let mut output = [0.0; 64];
+        impulse_response(&mut delay, &mut output);
+        let sum: f32 = output.iter().sum();
+        let centroid: f32 = output.iter().enumerate().map(|(i, h)| i as f32 * h).sum();
+        assert!((sum - 1.0).abs() < 1e-4);
+        assert!((centroid / sum - 4.3).abs() < 1e-3);
+    }
+
+    #[test]
+    fn test_allpass_decays_after_delay_change() {
+        for to in [3.0, 3.001, 2.999, 0.0, 0.5] {
+            let mut delay = DelayLine::<16>::new(Interpolation::Allpass);
+            delay.set_delay(2.5);
+            let mut output = [0.0; 4];
+            impulse_response(&mut delay, &mut output);
+
+            delay.set_delay(to);
+            let mut output = [0.0; 2000];
+            delay.process(&[0.0; 2000], &mut output);
+            assert!(
+                output[100..].iter().all(|sample| sample.abs() < 1e-6),
+                "{to}"
+            );
+        }
+    }
+
+    #[test]
+    fn test_blocks_wrap() {
+        // blocks which wrap around the end of the buffer give the same result as ticking
+        let mut block = DelayLine::<8>::new(Interpolation::Cubic);
+        let mut ticked = DelayLine::<8>::new(Interpolation::Cubic);
+        block.set_delay(3.7);
+        ticked.set_delay(3.7);
+
+        let input: [f32; 23] = core::array::from_fn(|i| (i * i % 7) as f32);
+        let mut output = [0.0; 23];
+        for (input, output) in input.chunks(5).zip(output.chunks_mut(5)) {
+            block.process(input, output);
+        }
+        for (input, output) in input.iter().zip(output) {
+            assert_eq!(ticked.tick(*input), output);
+        }
+    }
+
+    #[test]
+    fn test_modulated_and_clamped() {
+        let mut delay = DelayLine::<8>::new(Interpolation::Linear);
+        assert_eq!(DelayLine::<8>::max_delay(), 5.0);
+        delay.set_delay(100.0);
+        assert_eq!(delay.delay(), 5.0);
+        delay.set_delay(f32::NAN);
+        assert_eq!(delay.delay(), 0.0);
+
+        let input = [1.0, 2.0, 3.0, 4.0, 5.0];
+        let mut output = [0.0; 5];
+        delay.process_modulated(&input, &[0.0, 1.0, 0.5, 2.0, -1.0], &mut output);
+        assert_eq!(output, [1.0, 1.0, 2.5, 2.0, 5.0]);
+        assert_eq!(delay.read_linear(1.5), 3.5);
+    }
+}
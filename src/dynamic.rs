@@ -0,0 +1,429 @@
+//! Using ringbuffers of different kinds through the same trait object, with [`DynRingBuffer`].
+//!
+//! [`DynRingBuffer`] has methods of the same names as [`RingBuffer`] and is implemented for every
+//! [`RingBuffer`], so it lives in this module rather than the crate root: a glob import of the
+//! crate root only brings in [`RingBuffer`], and method calls on concrete ringbuffers stay
+//! unambiguous.
+//!
+//! A function which takes a `&mut dyn DynRingBuffer<T>` works with every kind of ringbuffer,
+//! without being generic over it:
+//!
+//! ```
+//! use ringbuffer::dynamic::DynRingBuffer;
+//! use ringbuffer::{AllocRingBuffer, ConstGenericRingBuffer};
+//!
+//! fn record(history: &mut dyn DynRingBuffer<u32>, sample: u32) -> Option<u32> {
+//!     history.enqueue(sample)
+//! }
+//!
+//! let mut short = ConstGenericRingBuffer::<u32, 1>::new();
+//! let mut long = AllocRingBuffer::new(16);
+//! assert_eq!(record(&mut short, 1), None);
+//! assert_eq!(record(&mut short, 2), Some(1));
+//! assert_eq!(record(&mut long, 2), None);
+//! ```
+
+use crate::RingBuffer;
+
+#[cfg(feature = "alloc")]
+extern crate alloc;
+#[cfg(feature = "alloc")]
+use alloc::boxed::Box;
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
+
+/// `DynRingBuffer` is the object safe counterpart of [`RingBuffer`], so that different kinds of
+/// ringbuffers can be used through the same `&mut dyn DynRingBuffer<T>` or
+/// `Box<dyn DynRingBuffer<T>>`, for example const generic ringbuffers of different capacities
+/// next to heap allocated ones.
+///
+/// It is implemented for every [`RingBuffer`]. The methods behave like the ones of the same name
+/// on [`RingBuffer`], but take closures and iterators as trait objects, and return boxed
+/// iterators. Since the names are the same, calling them on a concrete ringbuffer is ambiguous
+/// when both traits are in scope, so only import this trait where trait objects are used.
+///
+/// # Example
+/// ```
+/// use ringbuffer::dynamic::DynRingBuffer;
+/// use ringbuffer::{AllocRingBuffer, ConstGenericRingBuffer};
+///
+/// let mut buffers: Vec<Box<dyn DynRingBuffer<i32>>> = vec![
+///     Box::new(ConstGenericRingBuffer::<i32, 2>::new()),
+///     Box::new(ConstGenericRingBuffer::<i32, 8>::new()),
+///     Box::new(AllocRingBuffer::new(4)),
+/// ];
+///
+/// for buffer in &mut buffers {
+///     buffer.extend_from_iter(&mut (0..5));
+/// }
+///
+/// let lens: Vec<_> = buffers.iter().map(|b| b.len()).collect();
+/// assert_eq!(lens, [2, 5, 4]);
+/// assert_eq!(buffers[0].to_vec(), [3, 4]);
+/// ```
+pub trait DynRingBuffer<T> {
+    /// Returns the number of elements in the buffer.
+    fn len(&self) -> usize;
+
+    /// Returns true if the buffer holds no elements.
+    fn is_empty(&self) -> bool;
+
+    /// Returns true when the length of the buffer equals its capacity.
+    fn is_full(&self) -> bool;
+
+    /// Returns the capacity of the buffer.
+    fn capacity(&self) -> usize;
+
+    /// Adds a value onto the buffer, and returns the element it overwrote, if any.
+    fn enqueue(&mut self, value: T) -> Option<T>;
+
+    /// Removes the element at the front of the buffer and returns it.
+    fn dequeue(&mut self) -> Option<T>;
+
+    /// Enqueues every element `iter` yields.
+    fn extend_from_iter(&mut self, iter: &mut dyn Iterator<Item = T>);
+
+    /// Empties the buffer.
+    fn clear(&mut self);
+
+    /// Sets every element in the buffer to the value returned by `f`.
+    fn fill_with(&mut self, f: &mut dyn FnMut() -> T);
+
+    /// Sets every element in the buffer to `value`.
+    fn fill(&mut self, value: T)
+    where
+        T: Clone;
+
+    /// Gets the element at `index`, wrapping around the length like [`RingBuffer::get`].
+    fn get(&self, index: usize) -> Option<&T>;
+
+    /// Gets the element at `index`, wrapping around the length like [`RingBuffer::get_mut`].
+    fn get_mut(&mut self, index: usize) -> Option<&mut T>;
+
+    /// Gets the element at `index` relative to the back when it is negative, wrapping around the
+    /// length like [`RingBuffer::get_signed`].
+    fn get_signed(&self, index: isize) -> Option<&T>;
+
+    /// Gets the element at `index` relative to the back when it is negative, wrapping around the
+    /// length like [`RingBuffer::get_mut_signed`].
+    fn get_mut_signed(&mut self, index: isize) -> Option<&mut T>;
+
+    /// Gets the element at `index`, or `None` if it is out of bounds.
+    fn get_checked(&self, index: usize) -> Option<&T>;
+
+    /// Gets the element at `index` mutably, or `None` if it is out of bounds.
+    fn get_checked_mut(&mut self, index: usize) -> Option<&mut T>;
+
+    /// Returns the element at the front of the buffer.
+    fn front(&self) -> Option<&T>;
+
+    /// Returns the element at the front of the buffer mutably.
+    fn front_mut(&mut self) -> Option<&mut T>;
+
+    /// Returns the element at the back of the buffer.
+    fn back(&self) -> Option<&T>;
+
+    /// Returns the element at the back of the buffer mutably.
+    fn back_mut(&mut self) -> Option<&mut T>;
+
+    /// Returns true if the buffer contains `elem`.
+    fn contains(&self, elem: &T) -> bool
+    where
+        T: PartialEq;
+
+    /// Calls `f` on every element, from front to back.
+    fn for_each(&self, f: &mut dyn FnMut(&T));
+
+    /// Calls `f` on every element mutably, from front to back.
+    fn for_each_mut(&mut self, f: &mut dyn FnMut(&mut T));
+
+    /// Returns an iterator over the elements, from front to back.
+    #[cfg(feature = "alloc")]
+    fn iter(&self) -> Box<dyn DoubleEndedIterator<Item = &T> + '_>;
+
+    /// Returns an iterator over the elements mutably, from front to back.
+    #[cfg(feature = "alloc")]
+    fn iter_mut(&mut self) -> Box<dyn DoubleEndedIterator<Item = &mut T> + '_>;
+
+    /// Returns an iterator which dequeues the elements as it is iterated over.
+    #[cfg(feature = "alloc")]
+    fn drain<'a>(&'a mut self) -> Box<dyn Iterator<Item = T> + 'a>
+    where
+        T: 'a;
+
+    /// Copies the elements into a `Vec`, from front to back.
+    #[cfg(feature = "alloc")]
+    fn to_vec(&self) -> Vec<T>
+    where
+        T: Clone;
+
+    /// Copies the elements from `offset` on into `dst`, like [`RingBuffer::copy_to_slice`].
+    ///
+    /// # Panics
+    /// Panics if the buffer length minus the offset is NOT equal to `dst.len()`.
+    fn copy_to_slice(&self, offset: usize, dst: &mut [T])
+    where
+        T: Copy;
+
+    /// Copies `src` over the elements from `offset` on, like [`RingBuffer::copy_from_slice`].
+    ///
+    /// # Panics
+    /// Panics if the buffer length minus the offset is NOT equal to `src.len()`.
+    fn copy_from_slice(&mut self, offset: usize, src: &[T])
+    where
+        T: Copy;
+}
+
+impl<T, B: RingBuffer<T>> DynRingBuffer<T> for B {
+    #[inline]
+    fn len(&self) -> usize {
+        RingBuffer::len(self)
+    }
+
+    #[inline]
+    fn is_empty(&self) -> bool {
+        RingBuffer::is_empty(self)
+    }
+
+    #[inline]
+    fn is_full(&self) -> bool {
+        RingBuffer::is_full(self)
+    }
+
+    #[inline]
+    fn capacity(&self) -> usize {
+        RingBuffer::capacity(self)
+    }
+
+    #[inline]
+    fn enqueue(&mut self, value: T) -> Option<T> {
+        RingBuffer::enqueue(self, value)
+    }
+
+    #[inline]
+    fn dequeue(&mut self) -> Option<T> {
+        RingBuffer::dequeue(self)
+    }
+
+    fn extend_from_iter(&mut self, iter: &mut dyn Iterator<Item = T>) {
+        self.extend(iter);
+    }
+
+    #[inline]
+    fn clear(&mut self) {
+        RingBuffer::clear(self);
+    }
+
+    fn fill_with(&mut self, f: &mut dyn FnMut() -> T) {
+        RingBuffer::fill_with(self, f);
+    }
+
+    fn fill(&mut self, value: T)
+    where
+        T: Clone,
+    {
+        RingBuffer::fill(self, value);
+    }
+
+    #[inline]
+    fn get(&self, index: usize) -> Option<&T> {
+        RingBuffer::get(self, index)
+    }
+
+    #[inline]
+    fn get_mut(&mut self, index: usize) -> Option<&mut T> {
+        RingBuffer::get_mut(self, index)
+    }
+
+    #[inline]
+    fn get_signed(&self, index: isize) -> Option<&T> {
+        RingBuffer::get_signed(self, index)
+    }
+
+    #[inline]
+    fn get_mut_signed(&mut self, index: isize) -> Option<&mut T> {
+        RingBuffer::get_mut_signed(self, index)
+    }
+
+    #[inline]
+    fn get_checked(&self, index: usize) -> Option<&T> {
+        RingBuffer::get_checked(self, index)
+    }
+
+    #[inline]
+    fn get_checked_mut(&mut self, index: usize) -> Option<&mut T> {
+        RingBuffer::get_checked_mut(self, index)
+    }
+
+    #[inline]
+    fn front(&self) -> Option<&T> {
+        RingBuffer::front(self)
+    }
+
+    #[inline]
+    fn front_mut(&mut self) -> Option<&mut T> {
+        RingBuffer::front_mut(self)
+    }
+
+    #[inline]
+    fn back(&self) -> Option<&T> {
+        RingBuffer::back(self)
+    }
+
+    #[inline]
+    fn back_mut(&mut self) -> Option<&mut T> {
+        RingBuffer::back_mut(self)
+    }
+
+    fn contains(&self, elem: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        RingBuffer::contains(self, elem)
+    }
+
+    fn for_each(&self, f: &mut dyn FnMut(&T)) {
+        RingBuffer::iter(self).for_each(f);
+    }
+
+    fn for_each_mut(&mut self, f: &mut dyn FnMut(&mut T)) {
+        RingBuffer::iter_mut(self).for_each(f);
+    }
+
+    #[cfg(feature = "alloc")]
+    fn iter(&self) -> Box<dyn DoubleEndedIterator<Item = &T> + '_> {
+        Box::new(RingBuffer::iter(self))
+    }
+
+    #[cfg(feature = "alloc")]
+    fn iter_mut(&mut self) -> Box<dyn DoubleEndedIterator<Item = &mut T> + '_> {
+        Box::new(RingBuffer::iter_mut(self))
+    }
+
+    #[cfg(feature = "alloc")]
+    fn drain<'a>(&'a mut self) -> Box<dyn Iterator<Item = T> + 'a>
+    where
+        T: 'a,
+    {
+        Box::new(RingBuffer::drain(self))
+    }
+
+    #[cfg(feature = "alloc")]
+    fn to_vec(&self) -> Vec<T>
+    where
+        T: Clone,
+    {
+        RingBuffer::to_vec(self)
+    }
+
+    fn copy_to_slice(&self, offset: usize, dst: &mut [T])
+    where
+        T: Copy,
+    {
+        RingBuffer::copy_to_slice(self, offset, dst);
+    }
+
+    fn copy_from_slice(&mut self, offset: usize, src: &[T])
+    where
+        T: Copy,
+    {
+        RingBuffer::copy_from_slice(self, offset, src);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::DynRingBuffer;
+    use crate::{AllocRingBuffer, ConstGenericRingBuffer, GrowableAllocRingBuffer};
+    use std::boxed::Box;
+    use std::vec;
+    use std::vec::Vec;
+
+    fn buffers() -> Vec<Box<dyn DynRingBuffer<i32>>> {
+        vec![
+            Box::new(ConstGenericRingBuffer::<i32, 3>::new()),
+            Box::new(ConstGenericRingBuffer::<i32, 8>::new()),
+            Box::new(AllocRingBuffer::new(3)),
+            Box::new(GrowableAllocRingBuffer::with_capacity(3)),
+        ]
+    }
+
+    /// The number of elements `fill` sets for each of [`buffers`], after enqueueing three: the
+    /// whole capacity, except for the growable buffer, which only has its length.
+    const FILLED_LENS: [usize; 4] = [3, 8, 3, 3];
+
+    #[test]
+    fn test_read_write() {
+        for mut rb in buffers() {
+            assert!(rb.is_empty());
+            assert_eq!(rb.dequeue(), None);
+            assert_eq!(rb.front(), None);
+
+            rb.extend_from_iter(&mut (1..=3));
+            assert_eq!(rb.len(), 3);
+            assert!(rb.capacity() >= 3);
+            assert_eq!(rb.get(0), Some(&1));
+            assert_eq!(rb.get_signed(-1), Some(&3));
+            assert_eq!(rb.get_checked(3), None);
+            assert!(rb.contains(&2));
+
+            *rb.get_mut(0).unwrap() += 10;
+            *rb.get_mut_signed(-1).unwrap() += 10;
+            *rb.get_checked_mut(1).unwrap() += 10;
+            assert_eq!((rb.front(), rb.back()), (Some(&11), Some(&13)));
+            *rb.front_mut().unwrap() -= 10;
+            *rb.back_mut().unwrap() -= 10;
+            assert_eq!(rb.to_vec(), [1, 12, 3]);
+
+            assert_eq!(rb.dequeue(), Some(1));
+            assert_eq!(rb.enqueue(4), None);
+
+            let mut dst = [0; 3];
+            rb.copy_to_slice(0, &mut dst);
+            assert_eq!(dst, [12, 3, 4]);
+            rb.copy_from_slice(1, &[5, 6]);
+            assert_eq!(rb.to_vec(), [12, 5, 6]);
+
+            rb.clear();
+            assert!(rb.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_iteration() {
+        for mut rb in buffers() {
+            rb.extend_from_iter(&mut (1..=3));
+
+            let mut sum = 0;
+            rb.for_each(&mut |v| sum += v);
+            assert_eq!(sum, 6);
+            rb.for_each_mut(&mut |v| *v *= 2);
+
+            assert!(rb.iter().eq([2, 4, 6].iter()));
+            assert!(rb.iter().rev().eq([6, 4, 2].iter()));
+            for v in rb.iter_mut() {
+                *v += 1;
+            }
+            assert_eq!(rb.drain().collect::<Vec<_>>(), [3, 5, 7]);
+            assert!(rb.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_fill() {
+        for (mut rb, len) in buffers().into_iter().zip(FILLED_LENS) {
+            rb.extend_from_iter(&mut (1..=3));
+            rb.fill(7);
+            assert_eq!(rb.to_vec(), vec![7; len]);
+
+            let mut next = 0;
+            rb.fill_with(&mut || {
+                next += 1;
+                next
+            });
+            assert_eq!(rb.to_vec(), (1..=len as i32).collect::<Vec<_>>());
+        }
+    }
+}
@@ -0,0 +1,161 @@
+use core::ops::{Add, Mul};
+
+/// The `FirFilter` is a streaming finite impulse response filter with `N` taps, which keeps the
+/// last `N` input samples in a ringbuffer.
+///
+/// Every output is the sum of the last `N` inputs, each multiplied with its tap: the oldest input
+/// with `kernel[0]`, the next one with `kernel[1]`, and so on up to the newest input with
+/// `kernel[N - 1]`, just like [`RingBufferSlices::dot`](crate::RingBufferSlices::dot). The
+/// kernel is in the same order as the [`window`](FirFilter::window). The inputs are stored twice,
+/// back to back, so the last `N` of them are always one contiguous slice, the window. That makes every output a single dot product which the
+/// compiler can vectorize, at the cost of writing each input twice.
+///
+/// The buffers are stored inline, like a [`ConstGenericRingBuffer`](crate::ConstGenericRingBuffer),
+/// so this doesn't need `alloc`. For a filter over a [`RingBuffer`](crate::RingBuffer) which is
+/// already there, see [`RingBufferSlices::dot`](crate::RingBufferSlices::dot).
+///
+/// # Example
+/// ```
+/// use ringbuffer::FirFilter;
+///
+/// // a moving average over 4 samples
+/// let mut filter = FirFilter::new([0.25; 4]);
+///
+/// let mut output = [0.0; 6];
+/// filter.process(&[4.0, 4.0, 4.0, 4.0, 8.0, 8.0], &mut output);
+/// assert_eq!(output, [1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+/// assert_eq!(filter.window(), &[4.0, 4.0, 8.0, 8.0]);
+/// ```
+#[derive(Debug, Clone)]
+pub struct FirFilter<T, const N: usize> {
+    // the taps, oldest input first, like the window
+    kernel: [T; N],
+    // the inputs, written to both halves
+    history: [[T; N]; 2],
+    // the slot the next input is written to, always below N
+    writeptr: usize,
+}
+
+impl<T, const N: usize> FirFilter<T, N>
+where
+    T: Copy + Default + Add<Output = T> + Mul<Output = T>,
+{
+    const ERROR_TAPS_ARE_NOT_ALLOWED_TO_BE_ZERO: () =
+        assert!(N != 0, "Number of taps is not allowed to be zero");
+
+    /// Creates a filter with the given taps, where `kernel[0]` is multiplied with the oldest
+    /// input and `kernel[N - 1]` with the newest. The inputs start out as `T::default()`.
+    #[must_use]
+    pub fn new(kernel: [T; N]) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::ERROR_TAPS_ARE_NOT_ALLOWED_TO_BE_ZERO;
+
+        Self {
+            kernel,
+            history: [[T::default(); N]; 2],
+            writeptr: 0,
+        }
+    }
+
+    /// Returns the last `N` inputs as one slice, oldest first.
+    #[inline]
+    #[must_use]
+    pub fn window(&self) -> &[T] {
+        // Safety: the two halves are arrays next to each other in an array, so together they
+        // are 2 * N contiguous elements, of which these are N
+        unsafe {
+            core::slice::from_raw_parts(self.history.as_ptr().cast::<T>().add(self.writeptr), N)
+        }
+    }
+
+    /// Adds an input to the filter, and returns the output for it.
+    #[inline]
+    pub fn tick(&mut self, input: T) -> T {
+        self.history[0][self.writeptr] = input;
+        self.history[1][self.writeptr] = input;
+        self.writeptr = crate::mask_any(N, self.writeptr + 1);
+        crate::dot(self.window(), &self.kernel)
+    }
+
+    /// Runs every sample of `input` through the filter, like [`tick`](Self::tick), writing the
+    /// results to `output`.
+    ///
+    /// # Panics
+    /// Panics if `input` and `output` differ in length.
+    pub fn process(&mut self, input: &[T], output: &mut [T]) {
+        assert_eq!(
+            input.len(),
+            output.len(),
+            "input and output differ in length"
+        );
+        for (input, output) in input.iter().zip(output) {
+            *output = self.tick(*input);
+        }
+    }
+
+    /// Resets all inputs to `T::default()`.
+    pub fn reset(&mut self) {
+        self.history = [[T::default(); N]; 2];
+        self.writeptr = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::FirFilter;
+    use crate::{AllocRingBuffer, RingBuffer, RingBufferSlices};
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_impulse_response() {
+        let kernel = [1, 2, 3, 4, 5];
+        let mut filter = FirFilter::new(kernel);
+        let mut input = [0; 12];
+        input[0] = 1;
+        let mut output = [0; 12];
+        filter.process(&input, &mut output);
+        // the impulse starts out as the newest input, so it meets the last tap first
+        assert_eq!(output, [5, 4, 3, 2, 1, 0, 0, 0, 0, 0, 0, 0]);
+
+        filter.reset();
+        assert_eq!(filter.window(), &[0; 5]);
+        assert_eq!(filter.tick(2), 10);
+    }
+
+    #[test]
+    fn test_same_as_dot_on_ringbuffer() {
+        // 19 taps, so the dot product has both full lanes and a tail
+        let kernel: [i64; 19] = core::array::from_fn(|i| i as i64 * 3 - 20);
+
+        let mut filter = FirFilter::new(kernel);
+        let mut rb = AllocRingBuffer::new(19);
+        rb.fill(0);
+        for i in 0..100 {
+            let input = (i * i % 37) - 18;
+            let _ = rb.enqueue(input);
+            assert_eq!(filter.tick(input), rb.dot(&kernel));
+            assert_eq!(filter.window(), rb.to_vec());
+        }
+    }
+
+    #[test]
+    fn test_dot_across_wrap() {
+        let mut rb = AllocRingBuffer::new(4);
+        rb.extend([1.0, 2.0, 3.0, 4.0, 5.0, 6.0]);
+        let (first, second) = rb.as_slices();
+        assert_eq!((first, second), (&[3.0, 4.0][..], &[5.0, 6.0][..]));
+        assert_eq!(rb.dot(&[1.0, 0.5, 0.25, 0.0]), 6.25);
+
+        let _ = rb.dequeue();
+        assert_eq!(rb.dot(&[1.0, 1.0, 1.0]), 15.0);
+        assert_eq!(rb.iter().copied().collect::<Vec<_>>(), [4.0, 5.0, 6.0]);
+    }
+
+    #[test]
+    #[should_panic(expected = "doesn't match buffer length")]
+    fn test_dot_wrong_kernel_length() {
+        let mut rb = AllocRingBuffer::new(4);
+        rb.extend([1, 2]);
+        let _ = rb.dot(&[1, 2, 3]);
+    }
+}
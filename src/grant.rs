@@ -0,0 +1,573 @@
+use core::mem::MaybeUninit;
+use core::ptr;
+#[cfg(all(feature = "mmap", target_os = "linux"))]
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use crate::{advance_readptr, Pod};
+
+/// Where a grant publishes the slots it hands back to the ringbuffer.
+pub(crate) enum Counter<'a> {
+    /// The pointers of a ringbuffer which is borrowed mutably, of the given size.
+    Local {
+        size: usize,
+        readptr: &'a mut usize,
+        writeptr: &'a mut usize,
+    },
+    /// A counter in shared memory, which only we write, and its current value.
+    #[cfg(all(feature = "mmap", target_os = "linux"))]
+    Shared { counter: &'a AtomicU32, value: u32 },
+}
+
+/// Splits the `len` slots from `start` in a buffer of `size` slots into the part before the end
+/// of the buffer and the part which wraps around to the start.
+///
+/// # Safety
+/// `buf` must be valid for `size` slots during `'a`, `start` must be below `size`, `len` must
+/// not exceed `size`, and nothing else may access those slots during `'a`.
+unsafe fn regions<'a, S>(
+    buf: *mut S,
+    size: usize,
+    start: usize,
+    len: usize,
+) -> (&'a mut [S], &'a mut [S]) {
+    let first = len.min(size - start);
+    (
+        core::slice::from_raw_parts_mut(buf.add(start), first),
+        core::slice::from_raw_parts_mut(buf, len - first),
+    )
+}
+
+/// A reservation of free slots at the back of a ringbuffer, made with `reserve`.
+///
+/// Write into the slots, then [`commit`](WriteGrant::commit) the first `k` of them to add them
+/// to the buffer without copying. Slots can be written with [`push`](WriteGrant::push), through
+/// [`as_mut_slices`](WriteGrant::as_mut_slices) when the elements are [`Pod`], or through
+/// [`as_uninit_slices`](WriteGrant::as_uninit_slices) followed by the unsafe
+/// [`assume_init`](WriteGrant::assume_init). Only slots written in one of those ways can be
+/// committed. The free space is split in two slices where it wraps around the end of the buffer.
+///
+/// Values which were written but not committed are dropped together with the grant.
+///
+/// # Example
+/// ```
+/// use ringbuffer::{ConstGenericRingBuffer, RingBuffer};
+///
+/// let mut rb = ConstGenericRingBuffer::<u8, 8>::new();
+/// rb.extend([1, 2]);
+///
+/// let mut grant = rb.reserve(4).unwrap();
+/// let (first, _) = grant.as_mut_slices();
+/// // for example, read from a socket into this slice
+/// first[..3].copy_from_slice(&[3, 4, 5]);
+/// grant.commit(3);
+///
+/// assert_eq!(rb.to_vec(), vec![1, 2, 3, 4, 5]);
+/// ```
+pub struct WriteGrant<'a, T> {
+    first: &'a mut [MaybeUninit<T>],
+    second: &'a mut [MaybeUninit<T>],
+    // the number of slots from the start which hold a value
+    init: usize,
+    counter: Counter<'a>,
+}
+
+impl<'a, T> WriteGrant<'a, T> {
+    /// Creates a grant over the `len` slots from `start` in `buf`, of which `init` are
+    /// initialized already.
+    ///
+    /// # Safety
+    /// Like [`regions`]. Additionally, none of the slots may hold an element of the ringbuffer,
+    /// the first `init` of them must hold a valid `T`, and `counter` must be the write pointer
+    /// pointing at `start`.
+    pub(crate) unsafe fn new(
+        buf: *mut MaybeUninit<T>,
+        size: usize,
+        start: usize,
+        len: usize,
+        init: usize,
+        counter: Counter<'a>,
+    ) -> Self {
+        let (first, second) = regions(buf, size, start, len);
+        Self {
+            first,
+            second,
+            init,
+            counter,
+        }
+    }
+
+    /// The number of reserved slots.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.first.len() + self.second.len()
+    }
+
+    /// Returns true if no slots were reserved.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of slots, from the start, which have been written and can be committed.
+    #[must_use]
+    pub fn initialized(&self) -> usize {
+        self.init
+    }
+
+    /// Writes `value` into the first slot which wasn't written yet, or hands it back when every
+    /// reserved slot was written.
+    ///
+    /// # Errors
+    /// Returns the value when every reserved slot was written already.
+    pub fn push(&mut self, value: T) -> Result<(), T> {
+        let Some(slot) = self.slot(self.init) else {
+            return Err(value);
+        };
+        let _ = slot.write(value);
+        self.init += 1;
+        Ok(())
+    }
+
+    /// Returns the reserved slots, without initializing them. Writing into them doesn't make them
+    /// committable, see [`assume_init`](WriteGrant::assume_init).
+    ///
+    /// Values already written into the slots are dropped first, since safe code could overwrite
+    /// them with uninitialized memory through the returned slices. Nothing can be committed
+    /// afterwards until slots are written again.
+    pub fn as_uninit_slices(&mut self) -> (&mut [MaybeUninit<T>], &mut [MaybeUninit<T>]) {
+        self.drop_slots(0);
+        (self.first, self.second)
+    }
+
+    /// Marks the first `n` reserved slots as written.
+    ///
+    /// # Safety
+    /// The first `n` slots must hold valid values of `T`.
+    ///
+    /// # Panics
+    /// Panics if `n` is greater than the number of reserved slots.
+    pub unsafe fn assume_init(&mut self, n: usize) {
+        let len = self.len();
+        assert!(
+            n <= len,
+            "cannot initialize {n} of the {len} reserved slots"
+        );
+        self.init = n;
+    }
+
+    /// Commits the first `k` reserved slots, adding them to the back of the buffer.
+    /// Anything written after them is dropped.
+    ///
+    /// # Panics
+    /// Panics if fewer than `k` slots were written.
+    pub fn commit(mut self, k: usize) {
+        let init = self.init;
+        assert!(
+            k <= init,
+            "cannot commit {k} slots when only {init} were written"
+        );
+
+        self.drop_slots(k);
+        // the buffer owns the first k values from now on
+        self.init = 0;
+        match &mut self.counter {
+            Counter::Local { writeptr, .. } => **writeptr = writeptr.wrapping_add(k),
+            #[cfg(all(feature = "mmap", target_os = "linux"))]
+            Counter::Shared { counter, value } => {
+                // Release, so the consumer sees the elements once it sees the new writeptr
+                counter.store(value.wrapping_add(k as u32), Ordering::Release);
+            }
+        }
+    }
+
+    fn slot(&mut self, index: usize) -> Option<&mut MaybeUninit<T>> {
+        let first_len = self.first.len();
+        if index < first_len {
+            Some(&mut self.first[index])
+        } else {
+            self.second.get_mut(index - first_len)
+        }
+    }
+
+    /// Drops the written values from `from` up to `init`, and forgets about them.
+    fn drop_slots(&mut self, from: usize) {
+        let to = self.init;
+        // forget the values first, so they are leaked rather than dropped twice when a drop
+        // panics
+        self.init = from;
+        for index in from..to {
+            if let Some(slot) = self.slot(index) {
+                // Safety: every slot below init holds a value
+                unsafe { slot.assume_init_drop() };
+            }
+        }
+    }
+}
+
+impl<T: Pod> WriteGrant<'_, T> {
+    /// Returns the reserved slots, as slices of `T` which can be written to directly, like a
+    /// `&mut [u8]` passed to [`Read::read`](https://doc.rust-lang.org/std/io/trait.Read.html).
+    /// After writing, any number of slots can be committed.
+    ///
+    /// Slots which weren't written yet are zeroed first.
+    pub fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+        let len = self.len();
+        for index in self.init..len {
+            if let Some(slot) = self.slot(index) {
+                // Safety: zero is a valid bit pattern for Pod types
+                unsafe { ptr::write_bytes(slot.as_mut_ptr(), 0, 1) };
+            }
+        }
+        self.init = len;
+
+        // Safety: every slot holds a value now, and MaybeUninit<T> has the same layout as T
+        unsafe {
+            (
+                &mut *(ptr::from_mut(self.first) as *mut [T]),
+                &mut *(ptr::from_mut(self.second) as *mut [T]),
+            )
+        }
+    }
+}
+
+impl<T> Drop for WriteGrant<'_, T> {
+    fn drop(&mut self) {
+        self.drop_slots(0);
+    }
+}
+
+/// A view of the elements at the front of a ringbuffer, made with `read_grant`.
+///
+/// Read the elements through [`as_slices`](ReadGrant::as_slices), then
+/// [`release`](ReadGrant::release) the first `k` of them to remove them from the buffer, without
+/// copying them out. The elements are split in two slices where they wrap around the end of the
+/// buffer. Dropping the grant leaves every element in the buffer.
+///
+/// # Example
+/// ```
+/// use ringbuffer::{AllocRingBuffer, RingBuffer};
+///
+/// let mut rb = AllocRingBuffer::new(8);
+/// rb.extend([1, 2, 3, 4]);
+///
+/// let grant = rb.read_grant(3).unwrap();
+/// let (first, _) = grant.as_slices();
+/// // for example, write this slice to a socket
+/// assert_eq!(first, &[1, 2, 3]);
+/// grant.release(2);
+///
+/// assert_eq!(rb.to_vec(), vec![3, 4]);
+/// ```
+pub struct ReadGrant<'a, T> {
+    first: &'a mut [T],
+    second: &'a mut [T],
+    counter: Counter<'a>,
+}
+
+impl<'a, T> ReadGrant<'a, T> {
+    /// Creates a grant over the `len` elements from `start` in `buf`.
+    ///
+    /// # Safety
+    /// Like [`regions`]. Additionally, every slot must hold an element of the ringbuffer, and
+    /// `counter` must be the read pointer pointing at `start`.
+    pub(crate) unsafe fn new(
+        buf: *mut T,
+        size: usize,
+        start: usize,
+        len: usize,
+        counter: Counter<'a>,
+    ) -> Self {
+        let (first, second) = regions(buf, size, start, len);
+        Self {
+            first,
+            second,
+            counter,
+        }
+    }
+
+    /// The number of granted elements.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.first.len() + self.second.len()
+    }
+
+    /// Returns true if no elements were granted.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the granted elements, from the item pushed the longest ago.
+    #[must_use]
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        (self.first, self.second)
+    }
+
+    /// Returns the granted elements mutably, from the item pushed the longest ago.
+    pub fn as_mut_slices(&mut self) -> (&mut [T], &mut [T]) {
+        (self.first, self.second)
+    }
+
+    /// Removes the first `k` granted elements from the buffer, dropping them.
+    ///
+    /// # Panics
+    /// Panics if `k` is greater than the number of granted elements.
+    pub fn release(self, k: usize) {
+        let len = self.len();
+        assert!(
+            k <= len,
+            "cannot release {k} elements when only {len} were granted"
+        );
+
+        let first = k.min(self.first.len());
+        let released = (
+            ptr::from_mut(&mut self.first[..first]),
+            ptr::from_mut(&mut self.second[..k - first]),
+        );
+
+        // remove the elements before dropping them, so they are leaked rather than dropped
+        // twice when a drop panics
+        match self.counter {
+            Counter::Local {
+                size,
+                readptr,
+                writeptr,
+            } => advance_readptr(size, k, readptr, writeptr),
+            #[cfg(all(feature = "mmap", target_os = "linux"))]
+            Counter::Shared { counter, value } => {
+                // Release, so the producer only overwrites the slots after we read them
+                counter.store(value.wrapping_add(k as u32), Ordering::Release);
+            }
+        }
+
+        // Safety: the elements are no longer part of the buffer, and we still have exclusive
+        // access to the memory
+        unsafe {
+            ptr::drop_in_place(released.0);
+            ptr::drop_in_place(released.1);
+        }
+    }
+}
+
+/// Implements `reserve` and `read_grant` on a ringbuffer, given its pointers and how to find and
+/// mask into its storage, like `impl_ringbuffer_ext!` does.
+macro_rules! impl_grants {
+    ($get_base_mut_ptr: ident, $readptr: ident, $writeptr: ident, $mask: expr) => {
+        /// Reserves `n` free slots at the back of the buffer, which can be written and then
+        /// committed without copying. See [`WriteGrant`](crate::WriteGrant).
+        ///
+        /// Returns `None` when fewer than `n` slots are free. Unlike
+        /// [`enqueue`](crate::RingBuffer::enqueue), this never overwrites elements.
+        pub fn reserve(&mut self, n: usize) -> Option<$crate::WriteGrant<'_, T>> {
+            if n > self.capacity() - self.len() {
+                return None;
+            }
+            let size = self.buffer_size();
+            let start = unsafe { $mask(self, self.$writeptr) };
+            let buf = unsafe { $get_base_mut_ptr(self) }.cast();
+
+            // Safety: the n slots from the write pointer hold no elements, since that's at most
+            // the free space. The grant borrows self mutably, so nothing else touches them.
+            Some(unsafe {
+                $crate::WriteGrant::new(
+                    buf,
+                    size,
+                    start,
+                    n,
+                    0,
+                    $crate::grant::Counter::Local {
+                        size,
+                        readptr: &mut self.$readptr,
+                        writeptr: &mut self.$writeptr,
+                    },
+                )
+            })
+        }
+
+        /// Grants access to the first `n` elements of the buffer, which can be read and then
+        /// released without copying. See [`ReadGrant`](crate::ReadGrant).
+        ///
+        /// Returns `None` when the buffer holds fewer than `n` elements.
+        pub fn read_grant(&mut self, n: usize) -> Option<$crate::ReadGrant<'_, T>> {
+            if n > self.len() {
+                return None;
+            }
+            let size = self.buffer_size();
+            let start = unsafe { $mask(self, self.$readptr) };
+            let buf = unsafe { $get_base_mut_ptr(self) };
+
+            // Safety: the n slots from the read pointer hold elements, since that's at most the
+            // length. The grant borrows self mutably, so nothing else touches them.
+            Some(unsafe {
+                $crate::ReadGrant::new(
+                    buf,
+                    size,
+                    start,
+                    n,
+                    $crate::grant::Counter::Local {
+                        size,
+                        readptr: &mut self.$readptr,
+                        writeptr: &mut self.$writeptr,
+                    },
+                )
+            })
+        }
+    };
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{AllocRingBuffer, ConstGenericRingBuffer, RingBuffer, SliceRingBuffer};
+    use alloc::collections::VecDeque;
+    use alloc::vec::Vec;
+    use core::cell::Cell;
+    use core::mem::MaybeUninit;
+
+    /// Writes and reads through grants while going around the buffer a few times, comparing
+    /// against a `VecDeque`.
+    macro_rules! check_grants {
+        ($rb: expr) => {{
+            let mut rb = $rb;
+            let capacity = rb.capacity();
+            let mut model = VecDeque::new();
+            let mut next = 0;
+
+            for round in 0..4 * capacity {
+                let free = capacity - rb.len();
+                assert!(rb.reserve(free + 1).is_none());
+                let mut grant = rb.reserve(free.min(round % 3 + 1)).unwrap();
+                let n = grant.len();
+                for _ in 0..n {
+                    assert_eq!(grant.push(next), Ok(()));
+                    model.push_back(next);
+                    next += 1;
+                }
+                assert_eq!(grant.push(next), Err(next));
+                grant.commit(n);
+                assert_eq!(rb.to_vec(), Vec::from(model.clone()));
+
+                assert!(rb.read_grant(rb.len() + 1).is_none());
+                let grant = rb.read_grant(rb.len().min(round % 2 + 1)).unwrap();
+                let (first, second) = grant.as_slices();
+                let read: Vec<_> = first.iter().chain(second).copied().collect();
+                let k = read.len();
+                assert_eq!(read, model.iter().take(k).copied().collect::<Vec<_>>());
+                grant.release(k);
+                model.drain(..k).for_each(drop);
+                assert_eq!(rb.to_vec(), Vec::from(model.clone()));
+            }
+        }};
+    }
+
+    #[test]
+    fn test_grants() {
+        check_grants!(AllocRingBuffer::<usize>::new(8));
+        check_grants!(AllocRingBuffer::<usize>::new(5));
+        check_grants!(AllocRingBuffer::<usize>::new_exact(5));
+        check_grants!(ConstGenericRingBuffer::<usize, 8>::new());
+        check_grants!(ConstGenericRingBuffer::<usize, 5>::new());
+
+        let mut storage = [MaybeUninit::uninit(); 7];
+        check_grants!(SliceRingBuffer::<usize>::new(&mut storage));
+    }
+
+    #[test]
+    fn test_grants_split_at_wrap() {
+        let mut rb = ConstGenericRingBuffer::<u8, 4>::new();
+        rb.extend([1, 2, 3]);
+        let _ = rb.dequeue();
+        let _ = rb.dequeue();
+
+        let mut grant = rb.reserve(3).unwrap();
+        let (first, second) = grant.as_mut_slices();
+        assert_eq!((first.len(), second.len()), (1, 2));
+        // Pod slots which weren't written are zeroed
+        assert_eq!(second, [0, 0]);
+        first.copy_from_slice(&[4]);
+        second.copy_from_slice(&[5, 6]);
+        grant.commit(3);
+        assert_eq!(rb.to_vec(), [3, 4, 5, 6]);
+
+        let mut grant = rb.read_grant(4).unwrap();
+        assert_eq!(grant.as_slices(), (&[3, 4][..], &[5, 6][..]));
+        grant.as_mut_slices().1[0] = 7;
+        drop(grant);
+        assert_eq!(rb.to_vec(), [3, 4, 7, 6]);
+    }
+
+    struct Droppable<'a>(&'a Cell<usize>);
+
+    impl Drop for Droppable<'_> {
+        fn drop(&mut self) {
+            self.0.set(self.0.get() + 1);
+        }
+    }
+
+    #[test]
+    fn test_grants_drop() {
+        let dropped = Cell::new(0);
+        let mut rb = AllocRingBuffer::new(4);
+
+        // written but not committed values are dropped with the grant
+        let mut grant = rb.reserve(3).unwrap();
+        for _ in 0..3 {
+            assert!(grant.push(Droppable(&dropped)).is_ok());
+        }
+        grant.commit(2);
+        assert_eq!(dropped.get(), 1);
+        assert_eq!(rb.len(), 2);
+
+        let mut grant = rb.reserve(2).unwrap();
+        assert!(grant.push(Droppable(&dropped)).is_ok());
+        drop(grant);
+        assert_eq!(dropped.get(), 2);
+        assert_eq!(rb.len(), 2);
+
+        // released values are dropped, the rest stays in the buffer
+        rb.read_grant(2).unwrap().release(1);
+        assert_eq!(dropped.get(), 3);
+        assert_eq!(rb.len(), 1);
+        drop(rb);
+        assert_eq!(dropped.get(), 4);
+    }
+
+    #[test]
+    fn test_write_grant_assume_init() {
+        let mut rb = AllocRingBuffer::new(4);
+        let mut grant = rb.reserve(2).unwrap();
+        let (first, _) = grant.as_uninit_slices();
+        let _ = first[0].write(1);
+        let _ = first[1].write(2);
+        unsafe { grant.assume_init(2) };
+        assert_eq!(grant.initialized(), 2);
+        grant.commit(2);
+        assert_eq!(rb.to_vec(), [1, 2]);
+    }
+
+    #[test]
+    fn test_write_grant_uninit_slices_forget_written() {
+        let dropped = Cell::new(0);
+        let mut rb = AllocRingBuffer::new(4);
+        let mut grant = rb.reserve(2).unwrap();
+        assert!(grant.push(Droppable(&dropped)).is_ok());
+
+        // the slices could be used to overwrite the pushed value with uninitialized memory, so
+        // it can't be committed anymore
+        let (first, _) = grant.as_uninit_slices();
+        first[0] = MaybeUninit::uninit();
+        assert_eq!(dropped.get(), 1);
+        assert_eq!(grant.initialized(), 0);
+        assert!(grant.push(Droppable(&dropped)).is_ok());
+        grant.commit(1);
+        assert_eq!(rb.len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "cannot commit 2 slots when only 1 were written")]
+    fn test_commit_unwritten() {
+        let mut rb = AllocRingBuffer::new(4);
+        let mut grant = rb.reserve(2).unwrap();
+        assert_eq!(grant.push(1), Ok(()));
+        grant.commit(2);
+    }
+}
@@ -15,28 +15,95 @@
 
 #[cfg(feature = "alloc")]
 extern crate alloc;
+#[cfg(all(feature = "mmap", target_os = "linux"))]
+extern crate std;
 
 #[macro_use]
 pub(crate) mod ringbuffer_trait;
 
 pub use ringbuffer_trait::RingBuffer;
 
+pub mod dynamic;
+
 mod set_len_trait;
 pub use set_len_trait::SetLen;
 
+#[macro_use]
+mod slices_trait;
+pub use slices_trait::RingBufferSlices;
+
+mod pod;
+pub use pod::Pod;
+
+mod capacity_error;
+pub use capacity_error::CapacityError;
+
+mod checked;
+pub use checked::Checked;
+
+#[macro_use]
+mod grant;
+pub use grant::{ReadGrant, WriteGrant};
+
+mod sequence;
+pub use sequence::{SeqError, Sequenced};
+
+mod broadcast;
+pub use broadcast::{Broadcast, Reader, RecvError};
+
 #[cfg(feature = "alloc")]
 mod with_alloc;
 #[cfg(feature = "alloc")]
 pub use with_alloc::alloc_ringbuffer::AllocRingBuffer;
 #[cfg(feature = "alloc")]
+pub use with_alloc::bip_buffer::BipBuffer;
+#[cfg(feature = "alloc")]
+pub use with_alloc::mirrored_ringbuffer::MirroredRingBuffer;
+#[cfg(feature = "alloc")]
+pub use with_alloc::mpmc::MpmcRingBuffer;
+#[cfg(feature = "alloc")]
+pub use with_alloc::row_ringbuffer::RowRingBuffer;
+#[cfg(feature = "alloc")]
+pub use with_alloc::seqlock::{SeqlockRingBuffer, SeqlockWriter};
+#[cfg(feature = "alloc")]
 pub use with_alloc::vecdeque::GrowableAllocRingBuffer;
 
 mod with_const_generics;
 pub use with_const_generics::ConstGenericRingBuffer;
 
+mod compact;
+pub use compact::{CompactRingBuffer, RingIndex};
+
+mod delay_line;
+pub use delay_line::{DelayLine, Interpolation};
+
+mod fir_filter;
+pub use fir_filter::FirFilter;
+
+mod bucket_ring;
+pub use bucket_ring::BucketRing;
+
+mod with_slice;
+pub use with_slice::SliceRingBuffer;
+
+mod storage;
+pub use storage::{Masking, RingBufferCore, RingStorage};
+
+mod bip_buffer;
+pub use bip_buffer::ConstGenericBipBuffer;
+
+#[cfg(feature = "conformance")]
+pub mod conformance;
+
+#[cfg(all(feature = "mmap", target_os = "linux"))]
+mod with_mmap;
+#[cfg(all(feature = "mmap", target_os = "linux"))]
+pub use with_mmap::mmap_ringbuffer::MmapRingBuffer;
+#[cfg(all(feature = "mmap", target_os = "linux"))]
+pub use with_mmap::shared_ringbuffer::SharedRingBuffer;
+
 /// Used internally. Computes the bitmask used to properly wrap the ringbuffers.
 #[inline]
-#[cfg(feature = "alloc")]
 const fn mask_and(cap: usize, index: usize) -> usize {
     debug_assert!(cap.is_power_of_two());
     index & (cap - 1)
@@ -48,6 +115,99 @@ const fn mask_modulo(cap: usize, index: usize) -> usize {
     index % cap
 }
 
+/// Used internally. Wraps the ringbuffers with [`mask_and`] when their size is a power of two,
+/// and with [`mask_modulo`] otherwise.
+#[inline]
+const fn mask_any(size: usize, index: usize) -> usize {
+    if size.is_power_of_two() {
+        mask_and(size, index)
+    } else {
+        mask_modulo(size, index)
+    }
+}
+
+/// Used internally. Moves the read pointer of a ringbuffer `n` elements ahead, where `n` is at
+/// most the length.
+///
+/// The pointers count up with wrapping arithmetic, so a long running ringbuffer never overflows
+/// them. Masking a wrapped pointer only stays correct when the size is a power of two, so for
+/// other sizes both pointers are moved back by the size once the read pointer reaches it.
+#[inline]
+fn advance_readptr(size: usize, n: usize, readptr: &mut usize, writeptr: &mut usize) {
+    *readptr = readptr.wrapping_add(n);
+    if !size.is_power_of_two() && *readptr >= size {
+        *readptr -= size;
+        *writeptr = writeptr.wrapping_sub(size);
+    }
+}
+
+/// Used internally. Splits the `len` slots from slot `from_idx` of `size` slots, wrapping around
+/// to the first slot at the end, into the two contiguous regions they cover: the one up to the
+/// end of the slots and the one from their start. `from_idx` must be less than `size`, and `len`
+/// at most `size`.
+#[inline]
+fn wrapping_regions(
+    size: usize,
+    from_idx: usize,
+    len: usize,
+) -> (core::ops::Range<usize>, core::ops::Range<usize>) {
+    let first = len.min(size - from_idx);
+    (from_idx..from_idx + first, 0..len - first)
+}
+
+/// Used internally. Copies `dst.len()` elements from the `size` slots at `base` into `dst`,
+/// starting at slot `from_idx` and wrapping around to the first slot at the end. This is split
+/// into the two regions of [`wrapping_regions`].
+///
+/// # Safety
+/// `from_idx` must be less than `size`, `dst.len()` at most `size`, and the slots which are read
+/// must be initialized.
+#[inline]
+unsafe fn copy_wrapping_to_slice<T: Copy>(
+    base: *const T,
+    size: usize,
+    from_idx: usize,
+    dst: &mut [T],
+) {
+    let (first, second) = wrapping_regions(size, from_idx, dst.len());
+    let dst_ptr = dst.as_mut_ptr();
+    unsafe {
+        core::ptr::copy_nonoverlapping(base.add(first.start), dst_ptr, first.len());
+        core::ptr::copy_nonoverlapping(base, dst_ptr.add(first.len()), second.len());
+    }
+}
+
+/// Used internally. Multiplies two slices of the same length elementwise and sums the products.
+/// The products are summed in 8 separate lanes, which the compiler can vectorize even for floats,
+/// where it may not reorder the additions of a single sum.
+#[inline]
+fn dot<T>(a: &[T], b: &[T]) -> T
+where
+    T: Copy + Default + core::ops::Add<Output = T> + core::ops::Mul<Output = T>,
+{
+    debug_assert_eq!(a.len(), b.len());
+    let (a_chunks, b_chunks) = (a.chunks_exact(8), b.chunks_exact(8));
+    let tail = a_chunks
+        .remainder()
+        .iter()
+        .zip(b_chunks.remainder())
+        .fold(T::default(), |sum, (x, y)| sum + *x * *y);
+
+    let mut lanes = [T::default(); 8];
+    for (a, b) in a_chunks.zip(b_chunks) {
+        for ((lane, x), y) in lanes.iter_mut().zip(a).zip(b) {
+            *lane = *lane + *x * *y;
+        }
+    }
+    lanes.into_iter().fold(tail, |sum, lane| sum + lane)
+}
+
+/// Used internally. Keeps counters which different threads or processes update on separate cache
+/// lines, so they don't slow each other down with false sharing.
+#[cfg(feature = "alloc")]
+#[repr(C, align(64))]
+struct CacheAligned<T>(T);
+
 #[cfg(test)]
 #[allow(non_upper_case_globals)]
 mod tests {
@@ -58,7 +218,32 @@ mod tests {
     use std::vec::Vec;
 
     use crate::ringbuffer_trait::{RingBufferIterator, RingBufferMutIterator};
-    use crate::{AllocRingBuffer, ConstGenericRingBuffer, GrowableAllocRingBuffer, RingBuffer};
+    use crate::{
+        AllocRingBuffer, Checked, ConstGenericRingBuffer, GrowableAllocRingBuffer, RingBuffer,
+    };
+
+    /// Runs `rb` through its pointers wrapping around. The caller sets the pointers of the empty
+    /// `rb` to just before `usize::MAX`, or to wherever they wrap for its implementation.
+    pub(crate) fn check_counters_wrap(rb: &mut impl RingBuffer<usize>) {
+        for i in 0..3 * rb.capacity() + 5 {
+            let _ = rb.enqueue(i);
+            if i % 3 == 0 && rb.len() > 1 {
+                let _ = rb.dequeue();
+            }
+
+            let expected: Vec<_> = (i + 1 - rb.len()..=i).collect();
+            assert_eq!(rb.len(), expected.len());
+            assert_eq!(rb.to_vec(), expected);
+            assert_eq!(rb.get(0), expected.first());
+            assert_eq!(rb.get_signed(-1), Some(&i));
+            assert_eq!(rb.iter().next_back(), Some(&i));
+
+            let mut dst = vec![0; rb.len()];
+            rb.copy_to_slice(0, &mut dst);
+            assert_eq!(dst, expected);
+            rb.copy_from_slice(0, &dst);
+        }
+    }
 
     #[test]
     fn run_test_neg_index() {
@@ -113,6 +298,15 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_try_with_capacity_growable() {
+        let b = GrowableAllocRingBuffer::<i32>::try_with_capacity(8).unwrap();
+        assert!(b.capacity() >= 8);
+        assert_eq!(b.len(), 0);
+
+        assert!(GrowableAllocRingBuffer::<i32>::try_with_capacity(usize::MAX).is_err());
+    }
+
     #[test]
     fn run_test_len() {
         fn test_len(mut b: impl RingBuffer<i32>) {
@@ -556,6 +750,36 @@ mod tests {
         assert_eq!(vec![1, 2, 3], b.to_vec());
     }
 
+    #[test]
+    fn run_test_into_array() {
+        fn test_into_array<B: RingBuffer<i32> + Debug>(mut b: B) {
+            for i in 0..5 {
+                let _ = b.enqueue(i);
+            }
+            // wraps, except for the growable buffer
+            let len = b.len();
+            let b = b.try_into_array::<2>().unwrap_err();
+            assert_eq!(b.len(), len);
+
+            let mut b = b;
+            while b.len() > 3 {
+                let _ = b.dequeue();
+            }
+            assert_eq!(b.into_array(), [2, 3, 4]);
+        }
+
+        test_into_array(AllocRingBuffer::new(4));
+        test_into_array(GrowableAllocRingBuffer::with_capacity(4));
+        test_into_array(ConstGenericRingBuffer::<i32, 4>::new());
+    }
+
+    #[test]
+    #[should_panic]
+    fn run_test_into_array_wrong_length() {
+        let b = ConstGenericRingBuffer::<i32, 4>::from([1, 2, 3, 4]);
+        let _: [i32; 3] = b.into_array();
+    }
+
     #[test]
     fn run_test_index() {
         fn test_index(mut b: impl RingBuffer<i32>) {
@@ -595,6 +819,71 @@ mod tests {
         test_index(ConstGenericRingBuffer::<i32, 8>::new());
     }
 
+    #[test]
+    fn run_test_get_checked() {
+        fn test_get_checked(mut b: impl RingBuffer<i32>) {
+            assert_eq!(b.get_checked(0), None);
+            assert_eq!(b.get_checked_signed(-1), None);
+
+            b.extend([1, 2, 3]);
+
+            assert_eq!(b.get_checked(0), Some(&1));
+            assert_eq!(b.get_checked(2), Some(&3));
+            assert_eq!(b.get_checked(3), None);
+            assert_eq!(b.get_checked(7), None);
+            // get wraps around instead
+            assert_eq!(b.get(7), Some(&2));
+
+            assert_eq!(b.get_checked_signed(-1), Some(&3));
+            assert_eq!(b.get_checked_signed(-3), Some(&1));
+            assert_eq!(b.get_checked_signed(-4), None);
+            assert_eq!(b.get_checked_signed(2), Some(&3));
+            assert_eq!(b.get_checked_signed(3), None);
+            assert_eq!(b.get_checked_signed(isize::MIN), None);
+            assert_eq!(b.get_checked_signed(isize::MAX), None);
+
+            *b.get_checked_mut(1).unwrap() = 5;
+            *b.get_checked_mut_signed(-1).unwrap() = 6;
+            assert_eq!(b.get_checked_mut(3), None);
+            assert_eq!(b.get_checked_mut_signed(-4), None);
+            assert_eq!(b.to_vec(), vec![1, 5, 6]);
+        }
+
+        test_get_checked(AllocRingBuffer::new(8));
+        test_get_checked(GrowableAllocRingBuffer::with_capacity(8));
+        test_get_checked(ConstGenericRingBuffer::<i32, 8>::new());
+    }
+
+    #[test]
+    fn run_test_checked_index() {
+        fn test_checked_index(b: impl RingBuffer<i32>) {
+            let mut b = Checked::new(b);
+            b.extend([1, 2, 3]);
+            assert_eq!(b[2], 3);
+            b[0] = 4;
+            assert_eq!(b[0], 4);
+            assert_eq!(b.into_inner().to_vec(), vec![4, 2, 3]);
+        }
+
+        test_checked_index(AllocRingBuffer::new(8));
+        test_checked_index(GrowableAllocRingBuffer::with_capacity(8));
+        test_checked_index(ConstGenericRingBuffer::<i32, 8>::new());
+    }
+
+    #[test]
+    #[should_panic(expected = "index 3 out of bounds for length 3")]
+    fn run_test_checked_index_out_of_bounds() {
+        let b = Checked::new(ConstGenericRingBuffer::<i32, 8>::from_iter([1, 2, 3]));
+        let _ = b[3];
+    }
+
+    #[test]
+    #[should_panic(expected = "index 3 out of bounds for length 3")]
+    fn run_test_checked_index_mut_out_of_bounds() {
+        let mut b = Checked::new(AllocRingBuffer::from(vec![1, 2, 3]));
+        b[3] = 4;
+    }
+
     #[test]
     fn run_test_index_mut() {
         fn test_index_mut(mut b: impl RingBuffer<i32>) {
@@ -1093,6 +1382,26 @@ mod tests {
         test_large_negative_index(GrowableAllocRingBuffer::<i32>::new());
     }
 
+    #[test]
+    fn run_test_get_mut_signed_matches_get_signed() {
+        // with a length of 2, an index off by two still finds the right element, so this uses 3.
+        // GrowableAllocRingBuffer::get_mut_signed used to read -1 as len - 3, and returned None
+        // instead of wrapping around for indices past the length.
+        fn test_get_mut_signed(mut b: impl RingBuffer<i32>) {
+            b.extend([1, 2, 3]);
+            for index in -10..10 {
+                let expected = b.get_signed(index).copied();
+                assert_eq!(b.get_mut_signed(index).copied(), expected, "{index}");
+            }
+            assert_eq!(b.get_mut_signed(-1), Some(&mut 3));
+            assert_eq!(b.get_mut_signed(3), Some(&mut 1));
+        }
+
+        test_get_mut_signed(AllocRingBuffer::new(3));
+        test_get_mut_signed(ConstGenericRingBuffer::<i32, 3>::new());
+        test_get_mut_signed(GrowableAllocRingBuffer::<i32>::new());
+    }
+
     #[test]
     fn run_test_enqueue_dequeue_enqueue_full() {
         fn test_enqueue_dequeue_enqueue_full(mut b: impl RingBuffer<i32>) {
@@ -1430,6 +1739,78 @@ mod tests {
         }
     }
 
+    #[test]
+    fn run_test_zero_sized_types() {
+        use core::cell::Cell;
+
+        std::thread_local! {
+            static DROPS: Cell<usize> = const { Cell::new(0) };
+        }
+
+        struct Zst;
+
+        impl Drop for Zst {
+            fn drop(&mut self) {
+                DROPS.with(|d| d.set(d.get() + 1));
+            }
+        }
+
+        fn drops() -> usize {
+            DROPS.with(Cell::get)
+        }
+
+        fn test_zst(mut b: impl RingBuffer<Zst>) {
+            DROPS.with(|d| d.set(0));
+
+            for _ in 0..3 {
+                assert!(b.enqueue(Zst).is_none());
+            }
+            assert_eq!(b.len(), 3);
+            assert_eq!(drops(), 0);
+
+            assert!(b.dequeue().is_some());
+            assert_eq!(b.len(), 2);
+            assert_eq!(drops(), 1);
+
+            assert_eq!(b.iter().count(), 2);
+            for i in b.iter_mut() {
+                // drops the old value
+                *i = Zst;
+            }
+            assert_eq!(b.len(), 2);
+            assert_eq!(drops(), 3);
+
+            assert_eq!(b.drain().count(), 2);
+            assert!(b.is_empty());
+            assert!(b.dequeue().is_none());
+            assert_eq!(drops(), 5);
+
+            let _ = b.enqueue(Zst);
+            let _ = b.enqueue(Zst);
+            drop(b);
+            assert_eq!(drops(), 7);
+        }
+
+        test_zst(AllocRingBuffer::new(4));
+        test_zst(AllocRingBuffer::new(5));
+        test_zst(GrowableAllocRingBuffer::with_capacity(4));
+        test_zst(ConstGenericRingBuffer::<Zst, 4>::new());
+
+        fn test_zst_overwrite(mut b: impl RingBuffer<Zst>) {
+            DROPS.with(|d| d.set(0));
+
+            for _ in 0..10 {
+                drop(b.enqueue(Zst));
+            }
+            assert_eq!(b.len(), 4);
+            assert!(b.is_full());
+            assert_eq!(drops(), 6);
+        }
+
+        test_zst_overwrite(AllocRingBuffer::new(4));
+        test_zst_overwrite(ConstGenericRingBuffer::<Zst, 4>::new());
+    }
+
     #[test]
     fn test_clone() {
         macro_rules! test_clone {
@@ -1702,7 +2083,7 @@ mod tests {
             ($rb_init: expr) => {
                 let mut rb = $rb_init();
                 rb.copy_from_slice(0, &[0; 0]);
-                assert_eq!(rb.to_vec(), alloc::vec![]);
+                assert_eq!(rb.to_vec(), alloc::vec::Vec::<i32>::new());
             };
         }
 
@@ -1949,7 +2330,7 @@ mod tests {
     fn test_set_len_leak() {
         use crate::SetLen;
 
-        #[derive(Default, Clone)]
+        #[derive(Debug, Default, Clone)]
         struct Droppable {
             dropped: bool,
         }
@@ -1975,7 +2356,9 @@ mod tests {
             };
         }
 
-        test_concrete!(|| ConstGenericRingBuffer::<Droppable, 8>::from(values.clone()));
+        test_concrete!(
+            || ConstGenericRingBuffer::<Droppable, 8>::try_from(values.clone()).unwrap()
+        );
         test_concrete!(|| AllocRingBuffer::<Droppable>::from(values));
     }
 
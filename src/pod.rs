@@ -0,0 +1,46 @@
+/// `Pod` ("plain old data") marks types which can be reinterpreted straight from raw bytes.
+///
+/// Ringbuffers which hand out memory that was never explicitly written (like the free space of a
+/// `MirroredRingBuffer`), or which store their elements in memory that outlives the process (like
+/// the file behind a `MmapRingBuffer`), require their elements to be `Pod`. Such types can't
+/// contain pointers, references or anything which needs to be dropped.
+///
+/// # Safety
+/// Implementing this implies that:
+/// - every bit pattern of `size_of::<Self>()` bytes is a valid value of `Self`
+/// - `Self` has no padding bytes
+/// - `Self` contains no pointers or references (they wouldn't be valid after a restart)
+///
+/// In practice this means primitive integers and floats, arrays of them and `#[repr(C)]` structs
+/// built out of them without padding.
+pub unsafe trait Pod: Copy + 'static {}
+
+macro_rules! impl_pod {
+    ($($t: ty),* $(,)?) => {
+        $(
+            // Safety: primitive integers and floats are valid for any bit pattern
+            unsafe impl Pod for $t {}
+        )*
+    };
+}
+
+impl_pod!(
+    u8,
+    u16,
+    u32,
+    u64,
+    u128,
+    usize,
+    i8,
+    i16,
+    i32,
+    i64,
+    i128,
+    isize,
+    f32,
+    f64,
+    ()
+);
+
+// Safety: arrays have no padding between elements, so an array of pod is pod itself
+unsafe impl<T: Pod, const N: usize> Pod for [T; N] {}
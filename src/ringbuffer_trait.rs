@@ -9,7 +9,8 @@ use alloc::vec::Vec;
 /// implementations ([`AllocRingBuffer`](crate::AllocRingBuffer), [`ConstGenericRingBuffer`](crate::ConstGenericRingBuffer))
 ///
 /// This trait is not object safe, so can't be used dynamically. However it is possible to
-/// define a generic function over types implementing `RingBuffer`.
+/// define a generic function over types implementing `RingBuffer`, and every `RingBuffer` can
+/// be used as a [`DynRingBuffer`](crate::dynamic::DynRingBuffer) trait object.
 ///
 /// # Safety
 /// Implementing this implies that the ringbuffer upholds some safety
@@ -150,13 +151,23 @@ pub unsafe trait RingBuffer<T>:
 
     /// Gets a value relative to the current index. 0 is the next index to be written to with push.
     /// -1 and down are the last elements pushed and 0 and up are the items that were pushed the longest ago.
+    ///
+    /// The index wraps around the length of the buffer, so this only returns `None` when the
+    /// buffer is empty. See [`get_checked_signed`](RingBuffer::get_checked_signed) for a version
+    /// which doesn't wrap.
     fn get_signed(&self, index: isize) -> Option<&T>;
 
     /// Gets a value relative to the current index. 0 is the next index to be written to with push.
+    ///
+    /// The index wraps around the length of the buffer, so this only returns `None` when the
+    /// buffer is empty. See [`get_checked`](RingBuffer::get_checked) for a version which doesn't
+    /// wrap.
     fn get(&self, index: usize) -> Option<&T>;
 
     /// Gets a value relative to the current index mutably. 0 is the next index to be written to with push.
     /// -1 and down are the last elements pushed and 0 and up are the items that were pushed the longest ago.
+    ///
+    /// The index wraps around the length of the buffer, like [`get_signed`](RingBuffer::get_signed).
     #[inline]
     fn get_mut_signed(&mut self, index: isize) -> Option<&mut T> {
         // Safety: self is a RingBuffer
@@ -164,12 +175,72 @@ pub unsafe trait RingBuffer<T>:
     }
 
     /// Gets a value relative to the current index mutably. 0 is the next index to be written to with push.
+    ///
+    /// The index wraps around the length of the buffer, like [`get`](RingBuffer::get).
     #[inline]
     fn get_mut(&mut self, index: usize) -> Option<&mut T> {
         // Safety: self is a RingBuffer
         unsafe { Self::ptr_get_mut(self, index).map(|i| &mut *i) }
     }
 
+    /// Gets the value at `index`, where 0 is the item pushed the longest ago.
+    /// Returns `None` when the index is not below the length, instead of wrapping around like
+    /// [`get`](RingBuffer::get).
+    #[inline]
+    fn get_checked(&self, index: usize) -> Option<&T> {
+        if index < self.len() {
+            self.get(index)
+        } else {
+            None
+        }
+    }
+
+    /// Gets the value at `index` mutably, where 0 is the item pushed the longest ago.
+    /// Returns `None` when the index is not below the length, instead of wrapping around like
+    /// [`get_mut`](RingBuffer::get_mut).
+    #[inline]
+    fn get_checked_mut(&mut self, index: usize) -> Option<&mut T> {
+        if index < self.len() {
+            self.get_mut(index)
+        } else {
+            None
+        }
+    }
+
+    /// Gets the value at `index`, where 0 and up are the items pushed the longest ago and -1 and
+    /// down are the last items pushed. Returns `None` when the index is outside of `-len..len`,
+    /// instead of wrapping around like [`get_signed`](RingBuffer::get_signed).
+    #[inline]
+    fn get_checked_signed(&self, index: isize) -> Option<&T> {
+        let in_bounds = if index >= 0 {
+            index.unsigned_abs() < self.len()
+        } else {
+            index.unsigned_abs() <= self.len()
+        };
+        if in_bounds {
+            self.get_signed(index)
+        } else {
+            None
+        }
+    }
+
+    /// Gets the value at `index` mutably, where 0 and up are the items pushed the longest ago and
+    /// -1 and down are the last items pushed. Returns `None` when the index is outside of
+    /// `-len..len`, instead of wrapping around like [`get_mut_signed`](RingBuffer::get_mut_signed).
+    #[inline]
+    fn get_checked_mut_signed(&mut self, index: isize) -> Option<&mut T> {
+        let in_bounds = if index >= 0 {
+            index.unsigned_abs() < self.len()
+        } else {
+            index.unsigned_abs() <= self.len()
+        };
+        if in_bounds {
+            self.get_mut_signed(index)
+        } else {
+            None
+        }
+    }
+
     /// same as [`get_mut`](RingBuffer::get_mut) but on raw pointers.
     ///
     /// # Safety
@@ -246,6 +317,35 @@ pub unsafe trait RingBuffer<T>:
         self.iter().cloned().collect()
     }
 
+    /// Converts the buffer into an array holding its elements in order, from the item pushed the
+    /// longest ago to the one most recently pushed.
+    ///
+    /// # Panics
+    /// Panics if the buffer does not hold exactly `N` elements.
+    /// See [`try_into_array`](RingBuffer::try_into_array) for a non-panicking version.
+    fn into_array<const N: usize>(self) -> [T; N] {
+        match self.try_into_array() {
+            Ok(array) => array,
+            Err(rb) => panic!(
+                "can't convert a ringbuffer of length {} into an array of length {}",
+                rb.len(),
+                N
+            ),
+        }
+    }
+
+    /// Converts the buffer into an array holding its elements in order, from the item pushed the
+    /// longest ago to the one most recently pushed.
+    ///
+    /// Gives the buffer back unchanged if it does not hold exactly `N` elements.
+    fn try_into_array<const N: usize>(mut self) -> Result<[T; N], Self> {
+        if self.len() != N {
+            return Err(self);
+        }
+        // dequeue can't fail, the buffer holds exactly N elements
+        Ok(core::array::from_fn(|_| self.dequeue().unwrap()))
+    }
+
     /// Returns true if elem is in the ringbuffer.
     fn contains(&self, elem: &T) -> bool
     where
@@ -510,13 +610,18 @@ macro_rules! impl_ringbuffer {
     ($readptr: ident, $writeptr: ident) => {
         #[inline]
         unsafe fn ptr_len(rb: *const Self) -> usize {
-            (*rb).$writeptr - (*rb).$readptr
+            // the pointers wrap around at usize::MAX, their distance is still the length
+            (*rb).$writeptr.wrapping_sub((*rb).$readptr)
         }
     };
 }
 
 /// Implement various functions on implementors of [`RingBuffer`].
 /// This is to avoid duplicate code.
+///
+/// `$mask` wraps a pointer, or a pointer plus an offset within the buffer, into the buffer. It is
+/// an unsafe function which takes a pointer to the ringbuffer, like `$get_base_ptr`, so it can
+/// use masking chosen per ringbuffer.
 macro_rules! impl_ringbuffer_ext {
     ($get_base_ptr: ident, $get_base_mut_ptr: ident, $get_unchecked: ident, $get_unchecked_mut: ident, $readptr: ident, $writeptr: ident, $mask: expr) => {
         #[inline]
@@ -529,13 +634,14 @@ macro_rules! impl_ringbuffer_ext {
                     self.len() as isize + index
                 };
 
-                let normalized_index =
-                    self.$readptr as isize + index_from_readptr.rem_euclid(self.len() as isize);
+                let normalized_index = self
+                    .$readptr
+                    .wrapping_add(index_from_readptr.rem_euclid(self.len() as isize) as usize);
 
                 unsafe {
                     // SAFETY: index has been modulo-ed to be within range
                     // to be within bounds
-                    $get_unchecked(self, $mask(self.buffer_size(), normalized_index as usize))
+                    $get_unchecked(self, $mask(self, normalized_index))
                 }
             })
         }
@@ -544,11 +650,11 @@ macro_rules! impl_ringbuffer_ext {
         fn get(&self, index: usize) -> Option<&T> {
             use core::ops::Not;
             self.is_empty().not().then(move || {
-                let normalized_index = self.$readptr + index.rem_euclid(self.len());
+                let normalized_index = self.$readptr.wrapping_add(index.rem_euclid(self.len()));
                 unsafe {
                     // SAFETY: index has been modulo-ed to be within range
                     // to be within bounds
-                    $get_unchecked(self, $mask(self.buffer_size(), normalized_index))
+                    $get_unchecked(self, $mask(self, normalized_index))
                 }
             })
         }
@@ -563,16 +669,14 @@ macro_rules! impl_ringbuffer_ext {
                     Self::ptr_len(rb) as isize + index
                 };
 
-                let normalized_index = (*rb).$readptr as isize
-                    + index_from_readptr.rem_euclid(Self::ptr_len(rb) as isize);
+                let normalized_index = (*rb).$readptr.wrapping_add(
+                    index_from_readptr.rem_euclid(Self::ptr_len(rb) as isize) as usize,
+                );
 
                 unsafe {
                     // SAFETY: index has been modulo-ed to be within range
                     // to be within bounds
-                    $get_unchecked_mut(
-                        rb,
-                        $mask(Self::ptr_buffer_size(rb), normalized_index as usize),
-                    )
+                    $get_unchecked_mut(rb, $mask(rb, normalized_index))
                 }
             })
         }
@@ -581,21 +685,20 @@ macro_rules! impl_ringbuffer_ext {
         #[doc(hidden)]
         unsafe fn ptr_get_mut(rb: *mut Self, index: usize) -> Option<*mut T> {
             (Self::ptr_len(rb) != 0).then(move || {
-                let normalized_index = (*rb).$readptr + index.rem_euclid(Self::ptr_len(rb));
+                let normalized_index =
+                    (*rb).$readptr.wrapping_add(index.rem_euclid(Self::ptr_len(rb)));
 
                 unsafe {
                     // SAFETY: index has been modulo-ed to be within range
                     // to be within bounds
-                    $get_unchecked_mut(rb, $mask(Self::ptr_buffer_size(rb), normalized_index))
+                    $get_unchecked_mut(rb, $mask(rb, normalized_index))
                 }
             })
         }
 
         #[inline]
         fn clear(&mut self) {
-            for i in self.drain() {
-                drop(i);
-            }
+            self.drain().for_each(drop);
 
             self.$readptr = 0;
             self.$writeptr = 0;
@@ -619,29 +722,13 @@ macro_rules! impl_ringbuffer_ext {
 
             let base: *const T = $get_base_ptr(rb);
             let size = Self::ptr_buffer_size(rb);
-            let offset_readptr = (*rb).$readptr + offset;
+            let offset_readptr = (*rb).$readptr.wrapping_add(offset);
 
-            let from_idx = $mask(size, offset_readptr);
-            let to_idx = $mask(size, offset_readptr + dst_len);
+            let from_idx = $mask(rb, offset_readptr);
 
-            if from_idx < to_idx {
-                dst.copy_from_slice(unsafe {
-                    // SAFETY: index has been modulo-ed to be within range
-                    // to be within bounds
-                    core::slice::from_raw_parts(base.add(from_idx), dst_len)
-                });
-            } else {
-                dst[..size - from_idx].copy_from_slice(unsafe {
-                    // SAFETY: index has been modulo-ed to be within range
-                    // to be within bounds
-                    core::slice::from_raw_parts(base.add(from_idx), size - from_idx)
-                });
-                dst[size - from_idx..].copy_from_slice(unsafe {
-                    // SAFETY: index has been modulo-ed to be within range
-                    // to be within bounds
-                    core::slice::from_raw_parts(base, to_idx)
-                });
-            }
+            // SAFETY: index has been modulo-ed to be within range, and the length was checked
+            // against the buffer length
+            unsafe { $crate::copy_wrapping_to_slice(base, size, from_idx, dst) };
         }
 
         unsafe fn ptr_copy_from_slice(rb: *mut Self, offset: usize, src: &[T])
@@ -662,10 +749,10 @@ macro_rules! impl_ringbuffer_ext {
 
             let base: *mut T = $get_base_mut_ptr(rb);
             let size = Self::ptr_buffer_size(rb);
-            let offset_readptr = (*rb).$readptr + offset;
+            let offset_readptr = (*rb).$readptr.wrapping_add(offset);
 
-            let from_idx = $mask(size, offset_readptr);
-            let to_idx = $mask(size, offset_readptr + src_len);
+            let from_idx = $mask(rb, offset_readptr);
+            let to_idx = $mask(rb, offset_readptr.wrapping_add(src_len));
 
             if from_idx < to_idx {
                 unsafe {
@@ -0,0 +1,228 @@
+use crate::ringbuffer_trait::RingBufferIterator;
+use crate::RingBuffer;
+use core::fmt;
+use core::iter::Skip;
+use core::marker::PhantomData;
+
+/// The error returned when looking up an element by its sequence number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SeqError {
+    /// The element was dequeued or overwritten already.
+    Evicted,
+    /// No element was enqueued with this sequence number yet.
+    NotYetWritten,
+}
+
+impl fmt::Display for SeqError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Evicted => write!(f, "the element was evicted from the ringbuffer"),
+            Self::NotYetWritten => write!(f, "the element was not written to the ringbuffer yet"),
+        }
+    }
+}
+
+/// Gives every element enqueued onto the wrapped ringbuffer a sequence number, so elements can
+/// be looked up by it, and reading can resume where a previous reader left off.
+///
+/// Every element gets the next sequence number when it is enqueued, starting at 0 for the first
+/// element in the buffer. Sequence numbers are never reused, not even after
+/// [`clear`](Self::clear). Only the sequence number of the next element is stored, so the wrapped
+/// buffer doesn't grow, and dequeueing costs nothing extra. Elements are therefore only added and
+/// removed through the wrapper, which hands out just a shared reference to the buffer.
+///
+/// # Example
+/// ```
+/// use ringbuffer::{ConstGenericRingBuffer, SeqError, Sequenced};
+///
+/// let mut log = Sequenced::new(ConstGenericRingBuffer::<_, 4>::new());
+/// for line in ["a", "b", "c", "d", "e"] {
+///     let _ = log.enqueue(line);
+/// }
+///
+/// // the first line was overwritten
+/// assert_eq!((log.first_seq(), log.next_seq()), (1, 5));
+/// assert_eq!(log.get_by_seq(0), Err(SeqError::Evicted));
+/// assert_eq!(log.get_by_seq(3), Ok(&"d"));
+/// assert!(log.iter_from_seq(3).unwrap().eq(&["d", "e"]));
+/// ```
+#[derive(Debug, Clone)]
+pub struct Sequenced<B, T> {
+    rb: B,
+    // the sequence number of the next element to be enqueued
+    next: u64,
+    _marker: PhantomData<fn() -> T>,
+}
+
+impl<B: RingBuffer<T>, T> Sequenced<B, T> {
+    /// Wraps `rb`. Elements already in `rb` get the first sequence numbers, oldest first.
+    #[inline]
+    pub fn new(rb: B) -> Self {
+        Self {
+            next: rb.len() as u64,
+            rb,
+            _marker: PhantomData,
+        }
+    }
+
+    /// Gives back the wrapped ringbuffer.
+    #[inline]
+    pub fn into_inner(self) -> B {
+        self.rb
+    }
+
+    /// Returns the wrapped ringbuffer.
+    #[inline]
+    pub const fn inner(&self) -> &B {
+        &self.rb
+    }
+
+    /// Returns the sequence number of the element at the front of the buffer, or
+    /// [`next_seq`](Self::next_seq) when the buffer is empty.
+    #[inline]
+    #[must_use]
+    pub fn first_seq(&self) -> u64 {
+        self.next - self.rb.len() as u64
+    }
+
+    /// Returns the sequence number the next enqueued element will get.
+    #[inline]
+    #[must_use]
+    pub const fn next_seq(&self) -> u64 {
+        self.next
+    }
+
+    /// Gets the element with sequence number `seq`.
+    ///
+    /// # Errors
+    /// Fails with [`SeqError::Evicted`] when the element was dequeued or overwritten already, and
+    /// with [`SeqError::NotYetWritten`] when it wasn't enqueued yet.
+    #[inline]
+    pub fn get_by_seq(&self, seq: u64) -> Result<&T, SeqError> {
+        let first = self.first_seq();
+        if seq < first {
+            return Err(SeqError::Evicted);
+        }
+        usize::try_from(seq - first)
+            .ok()
+            .and_then(|index| self.rb.get_checked(index))
+            .ok_or(SeqError::NotYetWritten)
+    }
+
+    /// Adds a value onto the buffer like [`RingBuffer::enqueue`], and returns the element it
+    /// overwrote, if any.
+    #[inline]
+    pub fn enqueue(&mut self, value: T) -> Option<T> {
+        self.next += 1;
+        self.rb.enqueue(value)
+    }
+
+    /// Adds a value onto the buffer like [`enqueue`](Self::enqueue), and returns the sequence
+    /// number it got, together with the element it overwrote, if any.
+    #[inline]
+    pub fn enqueue_with_seq(&mut self, value: T) -> (u64, Option<T>) {
+        let seq = self.next;
+        (seq, self.enqueue(value))
+    }
+
+    /// Removes the element at the front of the buffer, like [`RingBuffer::dequeue`].
+    #[inline]
+    pub fn dequeue(&mut self) -> Option<T> {
+        self.rb.dequeue()
+    }
+
+    /// Empties the buffer. The sequence numbers continue where they were.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.rb.clear();
+    }
+
+    /// Returns an iterator over the elements from the one with sequence number `seq` up to
+    /// the one most recently enqueued. Starting at [`next_seq`](Self::next_seq) gives an empty
+    /// iterator.
+    ///
+    /// # Errors
+    /// Fails with [`SeqError::Evicted`] when the element with sequence number `seq` was dequeued
+    /// or overwritten already, so some elements were missed, and with
+    /// [`SeqError::NotYetWritten`] when `seq` is past [`next_seq`](Self::next_seq).
+    #[inline]
+    pub fn iter_from_seq(&self, seq: u64) -> Result<Skip<RingBufferIterator<'_, T, B>>, SeqError> {
+        let first = self.first_seq();
+        if seq < first {
+            Err(SeqError::Evicted)
+        } else if seq > self.next {
+            Err(SeqError::NotYetWritten)
+        } else {
+            // at most the length, so this fits
+            Ok(self.rb.iter().skip((seq - first) as usize))
+        }
+    }
+}
+
+impl<B: RingBuffer<T>, T> Extend<T> for Sequenced<B, T> {
+    fn extend<A: IntoIterator<Item = T>>(&mut self, iter: A) {
+        for value in iter {
+            let _ = self.enqueue(value);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Sequenced;
+    use crate::{AllocRingBuffer, ConstGenericRingBuffer, RingBuffer, SeqError};
+    use alloc::vec::Vec;
+
+    macro_rules! check_sequence {
+        ($rb: expr) => {{
+            let mut rb = Sequenced::new($rb);
+            assert_eq!((rb.first_seq(), rb.next_seq()), (0, 0));
+            assert_eq!(rb.get_by_seq(0), Err(SeqError::NotYetWritten));
+            assert_eq!(rb.iter_from_seq(0).unwrap().count(), 0);
+
+            for i in 0..10 {
+                assert_eq!(rb.enqueue_with_seq(i * 10).0, i);
+            }
+            // the first 6 were overwritten
+            assert_eq!((rb.first_seq(), rb.next_seq()), (6, 10));
+            assert_eq!(rb.get_by_seq(5), Err(SeqError::Evicted));
+            assert_eq!(rb.get_by_seq(6), Ok(&60));
+            assert_eq!(rb.get_by_seq(9), Ok(&90));
+            assert_eq!(rb.get_by_seq(10), Err(SeqError::NotYetWritten));
+
+            let replay: Vec<_> = rb.iter_from_seq(8).unwrap().copied().collect();
+            assert_eq!(replay, [80, 90]);
+            assert_eq!(rb.iter_from_seq(10).unwrap().count(), 0);
+            assert!(matches!(rb.iter_from_seq(5), Err(SeqError::Evicted)));
+            assert!(matches!(rb.iter_from_seq(11), Err(SeqError::NotYetWritten)));
+
+            assert_eq!(rb.dequeue(), Some(60));
+            assert_eq!(rb.first_seq(), 7);
+
+            // sequence numbers continue after clearing, extending and cloning
+            rb.clear();
+            assert_eq!((rb.first_seq(), rb.next_seq()), (10, 10));
+            rb.extend([1, 1, 1]);
+            assert_eq!((rb.first_seq(), rb.next_seq()), (10, 13));
+            assert_eq!(rb.clone().first_seq(), 10);
+            assert_eq!(rb.into_inner().len(), 3);
+        }};
+    }
+
+    #[test]
+    fn test_sequence() {
+        check_sequence!(AllocRingBuffer::<u64>::new(4));
+        check_sequence!(AllocRingBuffer::<u64>::new_exact(4));
+        check_sequence!(ConstGenericRingBuffer::<u64, 4>::new());
+    }
+
+    #[test]
+    fn test_existing_elements() {
+        let rb: ConstGenericRingBuffer<_, 4> = [1, 2, 3].into_iter().collect();
+        let mut rb = Sequenced::new(rb);
+        assert_eq!((rb.first_seq(), rb.next_seq()), (0, 3));
+        assert_eq!(rb.enqueue_with_seq(4), (3, None));
+        assert_eq!(rb.get_by_seq(0), Ok(&1));
+        assert_eq!(rb.inner().to_vec(), [1, 2, 3, 4]);
+    }
+}
@@ -23,7 +23,7 @@ macro_rules! impl_ring_buffer_set_len {
         unsafe fn set_len(&mut self, new_len: usize) {
             let cap = self.capacity();
             assert!(new_len <= cap, "Cannot set the a length of {new_len} on a ringbuffer with capacity for {cap} items");
-            self.$writeptr = self.$readptr + new_len;
+            self.$writeptr = self.$readptr.wrapping_add(new_len);
         }
     };
 }
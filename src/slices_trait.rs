@@ -0,0 +1,66 @@
+use core::ops::{Add, Mul};
+
+use crate::RingBuffer;
+
+/// `RingBufferSlices` is implemented by ringbuffers which keep their elements in one contiguous
+/// storage, so they can hand them out as (at most) two slices.
+///
+/// This is a separate trait from [`RingBuffer`], so ringbuffers which don't store their elements
+/// like that can still implement [`RingBuffer`].
+pub trait RingBufferSlices<T>: RingBuffer<T> {
+    /// Returns the elements as two slices, oldest first, split where they wrap around the end of
+    /// the storage. The second slice is empty when they don't.
+    fn as_slices(&self) -> (&[T], &[T]);
+
+    /// Multiplies every element with the one at the same index in `kernel`, and returns the sum
+    /// of the products. The oldest element is multiplied with `kernel[0]`.
+    ///
+    /// This works on the two slices of [`as_slices`](RingBufferSlices::as_slices) directly,
+    /// summing the products in several lanes so the compiler can vectorize it, which makes it a
+    /// lot faster than going through [`iter`](RingBuffer::iter).
+    ///
+    /// # Panics
+    /// Panics if `kernel` isn't exactly as long as the buffer.
+    fn dot(&self, kernel: &[T]) -> T
+    where
+        T: Copy + Default + Add<Output = T> + Mul<Output = T>,
+    {
+        let len = self.len();
+        assert_eq!(
+            kernel.len(),
+            len,
+            "kernel length ({}) doesn't match buffer length ({len})",
+            kernel.len()
+        );
+
+        let (first, second) = self.as_slices();
+        let (kernel_first, kernel_second) = kernel.split_at(first.len());
+        crate::dot(first, kernel_first) + crate::dot(second, kernel_second)
+    }
+}
+
+/// Implement `as_slices` given a function to get a pointer to the storage, the `readptr`, and a
+/// function to mask it into the storage, like `impl_ringbuffer_ext!` takes.
+macro_rules! impl_ring_buffer_slices {
+    ($get_base_ptr: ident, $readptr: ident, $mask: expr) => {
+        fn as_slices(&self) -> (&[T], &[T]) {
+            let len = self.len();
+            if len == 0 {
+                return (&[], &[]);
+            }
+
+            let size = self.buffer_size();
+            let start = unsafe { $mask(self, self.$readptr) };
+            let first = len.min(size - start);
+            unsafe {
+                // SAFETY: index has been modulo-ed to be within range, and the len slots from
+                // the read pointer, wrapping around, are initialized
+                let base: *const T = $get_base_ptr(self);
+                (
+                    core::slice::from_raw_parts(base.add(start), first),
+                    core::slice::from_raw_parts(base, len - first),
+                )
+            }
+        }
+    };
+}
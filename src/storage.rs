@@ -0,0 +1,467 @@
+use crate::ringbuffer_trait::{RingBufferIntoIterator, RingBufferIterator, RingBufferMutIterator};
+use crate::{
+    advance_readptr, impl_ring_buffer_set_len, mask_and, mask_any, RingBuffer, RingBufferSlices,
+    SetLen,
+};
+use core::mem::{ManuallyDrop, MaybeUninit};
+use core::ops::{Index, IndexMut};
+use core::ptr;
+
+/// How a [`RingBufferCore`] wraps its pointers around the slots of its [`RingStorage`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Masking {
+    /// Wrap with a bitmask. This is the fastest, but the number of slots must be a power of two.
+    PowerOfTwo,
+    /// Wrap with a bitmask when the number of slots is a power of two, and with a modulo
+    /// otherwise.
+    Any,
+}
+
+/// The memory a [`RingBufferCore`] keeps its elements in.
+///
+/// Implement this to give a ringbuffer custom storage, like pinned memory or an arena, and get
+/// the whole [`RingBuffer`] API for it from [`RingBufferCore`]. It is implemented for arrays,
+/// mutable slices and (with the `alloc` feature) boxed slices of [`MaybeUninit`].
+///
+/// # Safety
+/// [`as_ptr`](RingStorage::as_ptr) and [`as_mut_ptr`](RingStorage::as_mut_ptr) must point to
+/// [`slots`](RingStorage::slots) consecutive, properly aligned slots of `Item`, which stay valid
+/// for reads and writes as long as the storage lives. Every call must return the same slots, and
+/// the number of slots must not change. The storage must not read, write or drop the slots
+/// itself: they may be uninitialized, and the [`RingBufferCore`] owns the elements in them.
+pub unsafe trait RingStorage {
+    /// The type of the elements stored in the slots.
+    type Item;
+
+    /// How the ringbuffer wraps around the slots. When this is [`Masking::PowerOfTwo`], the
+    /// number of slots must be a power of two.
+    const MASKING: Masking = Masking::Any;
+
+    /// Returns a pointer to the first slot.
+    fn as_ptr(&self) -> *const Self::Item;
+
+    /// Returns a mutable pointer to the first slot.
+    fn as_mut_ptr(&mut self) -> *mut Self::Item;
+
+    /// Returns the number of slots, which is the capacity of the ringbuffer.
+    fn slots(&self) -> usize;
+}
+
+unsafe impl<T, const N: usize> RingStorage for [MaybeUninit<T>; N] {
+    type Item = T;
+
+    fn as_ptr(&self) -> *const T {
+        self.as_slice().as_ptr().cast()
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut T {
+        self.as_mut_slice().as_mut_ptr().cast()
+    }
+
+    fn slots(&self) -> usize {
+        N
+    }
+}
+
+unsafe impl<T> RingStorage for &mut [MaybeUninit<T>] {
+    type Item = T;
+
+    fn as_ptr(&self) -> *const T {
+        (**self).as_ptr().cast()
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut T {
+        (**self).as_mut_ptr().cast()
+    }
+
+    fn slots(&self) -> usize {
+        self.len()
+    }
+}
+
+#[cfg(feature = "alloc")]
+unsafe impl<T> RingStorage for alloc::boxed::Box<[MaybeUninit<T>]> {
+    type Item = T;
+
+    fn as_ptr(&self) -> *const T {
+        (**self).as_ptr().cast()
+    }
+
+    fn as_mut_ptr(&mut self) -> *mut T {
+        (**self).as_mut_ptr().cast()
+    }
+
+    fn slots(&self) -> usize {
+        self.len()
+    }
+}
+
+/// The `RingBufferCore` struct is a `RingBuffer` implementation which keeps its elements in any
+/// [`RingStorage`]. It does all the bookkeeping, so custom storage only has to say where its
+/// slots are.
+///
+/// The capacity is the number of slots. The elements left in the buffer are dropped when it is
+/// dropped.
+///
+/// # Example
+/// ```
+/// use core::mem::MaybeUninit;
+/// use ringbuffer::{RingBuffer, RingBufferCore};
+///
+/// let mut buffer = RingBufferCore::new([const { MaybeUninit::uninit() }; 2]);
+///
+/// buffer.enqueue(5);
+/// buffer.enqueue(42);
+/// assert!(buffer.is_full());
+///
+/// // Because capacity is reached the next enqueue will be the first item of the buffer.
+/// buffer.enqueue(1);
+/// assert_eq!(buffer.to_vec(), vec![42, 1]);
+/// ```
+#[derive(Debug)]
+pub struct RingBufferCore<S: RingStorage> {
+    storage: S,
+    readptr: usize,
+    writeptr: usize,
+}
+
+impl<S: RingStorage> RingBufferCore<S> {
+    /// Creates an empty ringbuffer which keeps its elements in `storage`.
+    ///
+    /// # Panics
+    /// Panics if `storage` has no slots, or if its masking is [`Masking::PowerOfTwo`] but the
+    /// number of slots isn't a power of two.
+    #[must_use]
+    pub fn new(storage: S) -> Self {
+        let slots = storage.slots();
+        assert_ne!(slots, 0, "Capacity must be greater than zero");
+        assert!(
+            S::MASKING == Masking::Any || slots.is_power_of_two(),
+            "storage with power of two masking has {slots} slots"
+        );
+
+        Self {
+            storage,
+            readptr: 0,
+            writeptr: 0,
+        }
+    }
+
+    /// Drops the elements in the buffer and gives the, now uninitialized, storage back.
+    #[must_use]
+    pub fn into_storage(mut self) -> S {
+        self.clear();
+        let this = ManuallyDrop::new(self);
+        // Safety: the buffer is forgotten, so the storage is only owned by the caller
+        unsafe { ptr::read(&this.storage) }
+    }
+
+    #[inline]
+    fn mask(size: usize, index: usize) -> usize {
+        match S::MASKING {
+            Masking::PowerOfTwo => mask_and(size, index),
+            Masking::Any => mask_any(size, index),
+        }
+    }
+}
+
+impl<S: RingStorage<Item = T>, T> RingBufferCore<S> {
+    impl_grants!(get_base_mut_ptr, readptr, writeptr, mask_ptr);
+}
+
+impl<S: RingStorage> Drop for RingBufferCore<S> {
+    fn drop(&mut self) {
+        self.drain().for_each(drop);
+    }
+}
+
+impl<S: RingStorage> PartialEq for RingBufferCore<S>
+where
+    S::Item: PartialEq,
+{
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().zip(other.iter()).all(|(a, b)| a == b)
+    }
+}
+
+impl<S: RingStorage> Eq for RingBufferCore<S> where S::Item: Eq {}
+
+/// Get a const pointer to the buffer
+unsafe fn get_base_ptr<S: RingStorage>(rb: *const RingBufferCore<S>) -> *const S::Item {
+    (*rb).storage.as_ptr()
+}
+
+/// Get a mut pointer to the buffer
+unsafe fn get_base_mut_ptr<S: RingStorage>(rb: *mut RingBufferCore<S>) -> *mut S::Item {
+    (*rb).storage.as_mut_ptr()
+}
+
+/// Wrap a pointer, or a pointer plus an offset within the buffer, into the buffer
+#[inline]
+unsafe fn mask_ptr<S: RingStorage>(rb: *const RingBufferCore<S>, ptr: usize) -> usize {
+    RingBufferCore::<S>::mask((*rb).storage.slots(), ptr)
+}
+
+/// Get a reference from the buffer without checking it is initialized
+/// Caller MUST be sure this index is initialized, or undefined behavior will happen
+unsafe fn get_unchecked<'a, S: RingStorage>(
+    rb: *const RingBufferCore<S>,
+    index: usize,
+) -> &'a S::Item {
+    &*get_base_ptr(rb).add(index)
+}
+
+/// Get a mutable reference from the buffer without checking it is initialized
+/// Caller MUST be sure this index is initialized, or undefined behavior will happen
+unsafe fn get_unchecked_mut<S: RingStorage>(
+    rb: *mut RingBufferCore<S>,
+    index: usize,
+) -> *mut S::Item {
+    get_base_mut_ptr(rb).add(index)
+}
+
+impl<S: RingStorage> IntoIterator for RingBufferCore<S> {
+    type Item = S::Item;
+    type IntoIter = RingBufferIntoIterator<S::Item, Self>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        RingBufferIntoIterator::new(self)
+    }
+}
+
+#[allow(clippy::into_iter_without_iter)]
+// iter() is implemented on the trait
+impl<'a, S: RingStorage> IntoIterator for &'a RingBufferCore<S> {
+    type Item = &'a S::Item;
+    type IntoIter = RingBufferIterator<'a, S::Item, RingBufferCore<S>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[allow(clippy::into_iter_without_iter)]
+// iter_mut() is implemented on the trait
+impl<'a, S: RingStorage> IntoIterator for &'a mut RingBufferCore<S> {
+    type Item = &'a mut S::Item;
+    type IntoIter = RingBufferMutIterator<'a, S::Item, RingBufferCore<S>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<S: RingStorage> Extend<S::Item> for RingBufferCore<S> {
+    fn extend<A: IntoIterator<Item = S::Item>>(&mut self, iter: A) {
+        for i in iter {
+            let _ = self.enqueue(i);
+        }
+    }
+}
+
+unsafe impl<S: RingStorage<Item = T>, T> RingBuffer<T> for RingBufferCore<S> {
+    #[inline]
+    unsafe fn ptr_capacity(rb: *const Self) -> usize {
+        (*rb).storage.slots()
+    }
+
+    #[inline]
+    unsafe fn ptr_buffer_size(rb: *const Self) -> usize {
+        (*rb).storage.slots()
+    }
+
+    impl_ringbuffer!(readptr, writeptr);
+
+    #[inline]
+    fn enqueue(&mut self, value: T) -> Option<T> {
+        let mut ret = None;
+        let size = self.storage.slots();
+
+        if self.is_full() {
+            // make sure we drop whatever is being overwritten
+            // SAFETY: the buffer is full, so this must be initialized
+            //       : also, index has been masked
+            ret = Some(unsafe {
+                get_base_ptr(self)
+                    .add(Self::mask(size, self.readptr))
+                    .read()
+            });
+            advance_readptr(size, 1, &mut self.readptr, &mut self.writeptr);
+        }
+        // SAFETY: index has been masked, and the slot is not initialized
+        unsafe {
+            get_base_mut_ptr(self)
+                .add(Self::mask(size, self.writeptr))
+                .write(value);
+        }
+        self.writeptr = self.writeptr.wrapping_add(1);
+
+        ret
+    }
+
+    fn dequeue(&mut self) -> Option<T> {
+        if self.is_empty() {
+            None
+        } else {
+            let size = self.storage.slots();
+            let index = Self::mask(size, self.readptr);
+            // Safety: the buffer isn't empty, so the element at the read pointer is initialized.
+            // Moving the read pointer past it makes the slot uninitialized again.
+            let res = unsafe { get_base_ptr(self).add(index).read() };
+            advance_readptr(size, 1, &mut self.readptr, &mut self.writeptr);
+            Some(res)
+        }
+    }
+
+    impl_ringbuffer_ext!(
+        get_base_ptr,
+        get_base_mut_ptr,
+        get_unchecked,
+        get_unchecked_mut,
+        readptr,
+        writeptr,
+        mask_ptr
+    );
+
+    #[inline]
+    fn fill_with<F: FnMut() -> T>(&mut self, mut f: F) {
+        self.clear();
+        for index in 0..self.storage.slots() {
+            // SAFETY: the buffer was cleared, so the slot is not initialized
+            unsafe { get_base_mut_ptr(self).add(index).write(f()) };
+            // count every element as soon as it is written, so a panicking f doesn't leak
+            self.writeptr += 1;
+        }
+    }
+}
+
+impl<S: RingStorage> Index<usize> for RingBufferCore<S> {
+    type Output = S::Item;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get(index).expect("index out of bounds")
+    }
+}
+
+impl<S: RingStorage> IndexMut<usize> for RingBufferCore<S> {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        self.get_mut(index).expect("index out of bounds")
+    }
+}
+
+impl<S: RingStorage<Item = T>, T> RingBufferSlices<T> for RingBufferCore<S> {
+    impl_ring_buffer_slices!(get_base_ptr, readptr, mask_ptr);
+}
+
+impl<S: RingStorage> SetLen for RingBufferCore<S> {
+    impl_ring_buffer_set_len!(readptr, writeptr);
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::{Masking, RingBufferCore, RingStorage};
+    use crate::{RingBuffer, SetLen};
+    use core::mem::MaybeUninit;
+    use std::boxed::Box;
+    use std::vec;
+
+    /// Storage which only supports power of two masking, like a driver's descriptor ring.
+    struct PowerOfTwo([MaybeUninit<u32>; 4]);
+
+    unsafe impl RingStorage for PowerOfTwo {
+        type Item = u32;
+        const MASKING: Masking = Masking::PowerOfTwo;
+
+        fn as_ptr(&self) -> *const u32 {
+            self.0.as_ptr().cast()
+        }
+
+        fn as_mut_ptr(&mut self) -> *mut u32 {
+            self.0.as_mut_ptr().cast()
+        }
+
+        fn slots(&self) -> usize {
+            self.0.len()
+        }
+    }
+
+    #[test]
+    fn test_storages() {
+        let mut array = RingBufferCore::new([const { MaybeUninit::<u32>::uninit() }; 3]);
+        let mut slots = [const { MaybeUninit::uninit() }; 3];
+        let mut slice = RingBufferCore::new(&mut slots[..]);
+        let mut boxed =
+            RingBufferCore::new((0..3).map(|_| MaybeUninit::uninit()).collect::<Box<_>>());
+        let mut power_of_two = RingBufferCore::new(PowerOfTwo([MaybeUninit::uninit(); 4]));
+
+        for i in 0..10 {
+            let _ = array.enqueue(i);
+            let _ = slice.enqueue(i);
+            let _ = boxed.enqueue(i);
+            let _ = power_of_two.enqueue(i);
+        }
+        assert_eq!(array.to_vec(), vec![7, 8, 9]);
+        assert_eq!(slice.to_vec(), vec![7, 8, 9]);
+        assert_eq!(boxed.to_vec(), vec![7, 8, 9]);
+        assert_eq!(power_of_two.to_vec(), vec![6, 7, 8, 9]);
+
+        assert_eq!(array.dequeue(), Some(7));
+        let mut grant = array.reserve(1).unwrap();
+        assert!(grant.push(10).is_ok());
+        grant.commit(1);
+        assert_eq!(array.to_vec(), vec![8, 9, 10]);
+
+        unsafe { power_of_two.set_len(2) };
+        assert_eq!(power_of_two.to_vec(), vec![6, 7]);
+    }
+
+    #[test]
+    fn test_into_storage_drops_elements() {
+        let value = std::rc::Rc::new(());
+        let mut rb = RingBufferCore::new([const { MaybeUninit::uninit() }; 2]);
+        let _ = rb.enqueue(std::rc::Rc::clone(&value));
+        let _ = rb.enqueue(std::rc::Rc::clone(&value));
+        assert_eq!(std::rc::Rc::strong_count(&value), 3);
+
+        let storage = rb.into_storage();
+        assert_eq!(std::rc::Rc::strong_count(&value), 1);
+
+        let mut rb = RingBufferCore::new(storage);
+        let _ = rb.enqueue(std::rc::Rc::clone(&value));
+        drop(rb);
+        assert_eq!(std::rc::Rc::strong_count(&value), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "storage with power of two masking has 3 slots")]
+    fn test_power_of_two_masking() {
+        struct Three([MaybeUninit<u32>; 3]);
+
+        unsafe impl RingStorage for Three {
+            type Item = u32;
+            const MASKING: Masking = Masking::PowerOfTwo;
+
+            fn as_ptr(&self) -> *const u32 {
+                self.0.as_ptr().cast()
+            }
+
+            fn as_mut_ptr(&mut self) -> *mut u32 {
+                self.0.as_mut_ptr().cast()
+            }
+
+            fn slots(&self) -> usize {
+                self.0.len()
+            }
+        }
+
+        let _ = RingBufferCore::new(Three([MaybeUninit::uninit(); 3]));
+    }
+
+    #[test]
+    #[should_panic(expected = "Capacity must be greater than zero")]
+    fn test_no_slots() {
+        let _ = RingBufferCore::new([const { MaybeUninit::<u32>::uninit() }; 0]);
+    }
+}
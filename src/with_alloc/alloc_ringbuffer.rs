@@ -7,13 +7,22 @@ use crate::ringbuffer_trait::{
 extern crate alloc;
 
 // We need boxes, so depend on alloc
-use crate::{impl_ring_buffer_set_len, mask_and, GrowableAllocRingBuffer, SetLen};
+use crate::{
+    impl_ring_buffer_set_len, mask_and, mask_modulo, GrowableAllocRingBuffer, RingBufferSlices,
+    SetLen,
+};
+use alloc::collections::TryReserveError;
+use core::mem::{size_of, ManuallyDrop};
 use core::ptr;
 
 /// The `AllocRingBuffer` is a `RingBuffer` which is based on a Vec. This means it allocates at runtime
 /// on the heap, and therefore needs the [`alloc`] crate. This struct and therefore the dependency on
 /// alloc can be disabled by disabling the `alloc` (default) feature.
 ///
+/// By default, room is allocated for a power of two number of elements, which is the fastest to
+/// index. Use [`new_exact`](AllocRingBuffer::new_exact) to allocate exactly as much as the
+/// capacity instead, when saving memory matters more.
+///
 /// # Example
 /// ```
 /// use ringbuffer::{AllocRingBuffer, RingBuffer};
@@ -40,8 +49,12 @@ use core::ptr;
 pub struct AllocRingBuffer<T> {
     pub(crate) buf: *mut T,
 
-    // the size of the allocation. Next power of 2 up from the capacity
+    // the size of the allocation. Next power of 2 up from the capacity,
+    // or equal to the capacity for buffers made with new_exact
     size: usize,
+    // whether the size is a power of two, so pointers are wrapped with a bit mask instead of a
+    // division. Decided once when allocating, so indexing doesn't have to check the size
+    power_of_two: bool,
     // maximum number of elements actually allowed in the ringbuffer.
     // Always less than or equal than the size
     capacity: usize,
@@ -80,8 +93,9 @@ impl<T: Clone> From<&[T]> for AllocRingBuffer<T> {
 }
 
 impl<T> From<GrowableAllocRingBuffer<T>> for AllocRingBuffer<T> {
+    /// Keeps the capacity `v` has allocated, or uses a capacity of 1 if it hasn't allocated yet.
     fn from(mut v: GrowableAllocRingBuffer<T>) -> AllocRingBuffer<T> {
-        let mut rb = AllocRingBuffer::new(v.len());
+        let mut rb = AllocRingBuffer::new(v.capacity().max(1));
         rb.extend(v.drain());
         rb
     }
@@ -140,8 +154,9 @@ impl From<&str> for AllocRingBuffer<char> {
 }
 
 impl<T, const CAP: usize> From<crate::ConstGenericRingBuffer<T, CAP>> for AllocRingBuffer<T> {
+    /// Keeps the capacity of `value`.
     fn from(mut value: crate::ConstGenericRingBuffer<T, CAP>) -> Self {
-        let mut res = AllocRingBuffer::new(value.len());
+        let mut res = AllocRingBuffer::new(CAP);
         res.extend(value.drain());
         res
     }
@@ -152,8 +167,11 @@ impl<T> Drop for AllocRingBuffer<T> {
         self.drain().for_each(drop);
 
         let layout = alloc::alloc::Layout::array::<T>(self.size).unwrap();
-        unsafe {
-            alloc::alloc::dealloc(self.buf.cast(), layout);
+        // zero sized layouts (like buffers of ZSTs) were never allocated
+        if layout.size() != 0 {
+            unsafe {
+                alloc::alloc::dealloc(self.buf.cast(), layout);
+            }
         }
     }
 }
@@ -162,7 +180,7 @@ impl<T: Clone> Clone for AllocRingBuffer<T> {
     fn clone(&self) -> Self {
         debug_assert_ne!(self.capacity, 0);
 
-        let mut new = Self::new(self.capacity);
+        let mut new = Self::allocate(self.capacity, self.size);
         new.extend(self.iter().cloned());
         new
     }
@@ -237,22 +255,20 @@ unsafe impl<T> RingBuffer<T> for AllocRingBuffer<T> {
         let mut ret = None;
 
         if self.is_full() {
-            // mask with and is allowed here because size is always a power of two
             let previous_value =
-                unsafe { ptr::read(get_unchecked_mut(self, mask_and(self.size, self.readptr))) };
+                unsafe { ptr::read(get_unchecked_mut(self, mask_ptr(self, self.readptr))) };
 
             ret = Some(previous_value);
-            self.readptr += 1;
+            self.advance_readptr();
         }
 
-        // mask with and is allowed here because size is always a power of two
-        let index = mask_and(self.size, self.writeptr);
+        let index = unsafe { mask_ptr(self, self.writeptr) };
 
         unsafe {
             ptr::write(get_unchecked_mut(self, index), value);
         }
 
-        self.writeptr += 1;
+        self.writeptr = self.writeptr.wrapping_add(1);
 
         ret
     }
@@ -261,10 +277,9 @@ unsafe impl<T> RingBuffer<T> for AllocRingBuffer<T> {
         if self.is_empty() {
             None
         } else {
-            // mask with and is allowed here because size is always a power of two
-            let index = mask_and(self.size, self.readptr);
+            let index = unsafe { mask_ptr(self, self.readptr) };
             let res = unsafe { get_unchecked_mut(self, index) };
-            self.readptr += 1;
+            self.advance_readptr();
 
             // Safety: the fact that we got this maybeuninit from the buffer (with mask) means that
             // it's initialized. If it wasn't the is_empty call would have caught it. Values
@@ -280,7 +295,7 @@ unsafe impl<T> RingBuffer<T> for AllocRingBuffer<T> {
         get_unchecked_mut,
         readptr,
         writeptr,
-        mask_and
+        mask_ptr
     );
 
     #[inline]
@@ -297,6 +312,8 @@ unsafe impl<T> RingBuffer<T> for AllocRingBuffer<T> {
 }
 
 impl<T> AllocRingBuffer<T> {
+    impl_grants!(get_base_mut_ptr, readptr, writeptr, mask_ptr);
+
     /// Creates a `AllocRingBuffer` with a certain capacity. The actual capacity is the input to the
     /// function raised to the power of two (effectively the input is the log2 of the actual capacity)
     #[inline]
@@ -315,23 +332,136 @@ impl<T> AllocRingBuffer<T> {
 
     /// Creates a `AllocRingBuffer` with a certain capacity. The capacity must not be zero.
     ///
+    /// Room is allocated for the next power of two number of elements. See
+    /// [`new_exact`](AllocRingBuffer::new_exact) to allocate exactly `capacity` elements.
+    ///
     /// # Panics
-    /// Panics when capacity is zero
+    /// Panics when capacity is zero, or when the allocation would exceed `isize::MAX` bytes.
+    /// Aborts through [`handle_alloc_error`](alloc::alloc::handle_alloc_error) when the allocator
+    /// is out of memory.
     #[inline]
     #[must_use]
     pub fn new(capacity: usize) -> Self {
         assert_ne!(capacity, 0, "Capacity must be greater than 0");
-        let size = capacity.next_power_of_two();
-        let layout = alloc::alloc::Layout::array::<T>(size).unwrap();
-        let buf = unsafe { alloc::alloc::alloc(layout).cast() };
+        let size = capacity
+            .checked_next_power_of_two()
+            .expect("capacity overflow");
+        Self::allocate(capacity, size)
+    }
+
+    /// Creates a `AllocRingBuffer` with a certain capacity, allocating room for exactly
+    /// `capacity` elements, so [`buffer_size`](RingBuffer::buffer_size) equals
+    /// [`capacity`](RingBuffer::capacity).
+    ///
+    /// Unless the capacity is a power of two, this uses less memory than
+    /// [`new`](AllocRingBuffer::new), but indexing has to divide instead of masking.
+    ///
+    /// # Panics
+    /// Panics when capacity is zero, or when the allocation would exceed `isize::MAX` bytes.
+    /// Aborts through [`handle_alloc_error`](alloc::alloc::handle_alloc_error) when the allocator
+    /// is out of memory.
+    #[inline]
+    #[must_use]
+    pub fn new_exact(capacity: usize) -> Self {
+        assert_ne!(capacity, 0, "Capacity must be greater than 0");
+        Self::allocate(capacity, capacity)
+    }
+
+    /// Creates a `AllocRingBuffer` with a certain capacity, like [`new`](AllocRingBuffer::new),
+    /// but returns an error instead of panicking or aborting when the memory can't be allocated.
+    ///
+    /// # Errors
+    /// Fails when the allocation would exceed `isize::MAX` bytes, or when the allocator is out
+    /// of memory.
+    ///
+    /// # Panics
+    /// Panics when capacity is zero
+    #[inline]
+    pub fn try_new(capacity: usize) -> Result<Self, TryReserveError> {
+        assert_ne!(capacity, 0, "Capacity must be greater than 0");
+        // past the largest power of two, the exact capacity already needs more than isize::MAX
+        // bytes, unless the elements are zero sized, and then the size doesn't matter
+        let size = capacity.checked_next_power_of_two().unwrap_or(capacity);
+        Self::try_allocate(capacity, size)
+    }
+
+    /// Creates a `AllocRingBuffer` with room for exactly `capacity` elements, like
+    /// [`new_exact`](AllocRingBuffer::new_exact), but returns an error instead of panicking or
+    /// aborting when the memory can't be allocated.
+    ///
+    /// # Errors
+    /// Fails when the allocation would exceed `isize::MAX` bytes, or when the allocator is out
+    /// of memory.
+    ///
+    /// # Panics
+    /// Panics when capacity is zero
+    #[inline]
+    pub fn try_new_exact(capacity: usize) -> Result<Self, TryReserveError> {
+        assert_ne!(capacity, 0, "Capacity must be greater than 0");
+        Self::try_allocate(capacity, capacity)
+    }
+
+    /// Moves the read pointer forward by one. Like `advance_readptr`, this wraps both pointers
+    /// back once the read pointer reaches the end of the buffer, unless the size is a power of
+    /// two.
+    #[inline]
+    fn advance_readptr(&mut self) {
+        self.readptr = self.readptr.wrapping_add(1);
+        if !self.power_of_two && self.readptr >= self.size {
+            self.readptr -= self.size;
+            self.writeptr = self.writeptr.wrapping_sub(self.size);
+        }
+    }
+
+    /// Allocates an empty buffer with room for `size` elements, of which `capacity` are used.
+    fn allocate(capacity: usize, size: usize) -> Self {
+        let layout = alloc::alloc::Layout::array::<T>(size).expect("capacity overflow");
+        let buf = if layout.size() == 0 {
+            // allocating zero bytes is undefined behaviour, and ZSTs don't need any memory anyway.
+            // A dangling pointer is well aligned and valid for zero sized reads and writes.
+            ptr::NonNull::dangling().as_ptr()
+        } else {
+            let buf = unsafe { alloc::alloc::alloc(layout) };
+            if buf.is_null() {
+                alloc::alloc::handle_alloc_error(layout);
+            }
+            buf.cast()
+        };
         Self {
             buf,
             size,
+            power_of_two: size.is_power_of_two(),
             capacity,
             readptr: 0,
             writeptr: 0,
         }
     }
+
+    /// Like [`allocate`](AllocRingBuffer::allocate), but fails instead of panicking or aborting.
+    fn try_allocate(capacity: usize, size: usize) -> Result<Self, TryReserveError> {
+        // TryReserveError can't be constructed outside of alloc, so let Vec compute the layout
+        // and allocate, and take over its allocation. For ZSTs it doesn't allocate, and the
+        // pointer is dangling.
+        let mut buf = alloc::vec::Vec::<T>::new();
+        buf.try_reserve_exact(size)?;
+        // drop frees `Layout::array::<T>(size)`, so that has to be exactly what was allocated.
+        // Vec only promises at least `size` elements, though the global allocator gives exactly
+        // that.
+        assert!(
+            size_of::<T>() == 0 || buf.capacity() == size,
+            "Vec allocated room for {} elements instead of {size}",
+            buf.capacity()
+        );
+        let buf = ManuallyDrop::new(buf).as_mut_ptr();
+        Ok(Self {
+            buf,
+            size,
+            power_of_two: size.is_power_of_two(),
+            capacity,
+            readptr: 0,
+            writeptr: 0,
+        })
+    }
 }
 
 /// Get a const pointer to the buffer
@@ -344,6 +474,17 @@ unsafe fn get_base_mut_ptr<T>(rb: *mut AllocRingBuffer<T>) -> *mut T {
     (*rb).buf
 }
 
+/// Wrap a pointer, or a pointer plus an offset within the buffer, into the buffer, with the
+/// masking chosen when it was allocated
+#[inline]
+unsafe fn mask_ptr<T>(rb: *const AllocRingBuffer<T>, ptr: usize) -> usize {
+    if (*rb).power_of_two {
+        mask_and((*rb).size, ptr)
+    } else {
+        mask_modulo((*rb).size, ptr)
+    }
+}
+
 /// Get a reference from the buffer without checking it is initialized.
 ///
 /// Caller must be sure the index is in bounds, or this will panic.
@@ -381,6 +522,10 @@ impl<T> IndexMut<usize> for AllocRingBuffer<T> {
     }
 }
 
+impl<T> RingBufferSlices<T> for AllocRingBuffer<T> {
+    impl_ring_buffer_slices!(get_base_ptr, readptr, mask_ptr);
+}
+
 impl<T> SetLen for AllocRingBuffer<T> {
     impl_ring_buffer_set_len!(readptr, writeptr);
 }
@@ -421,6 +566,58 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_counters_wrap_at_usize_max() {
+        use crate::tests::check_counters_wrap;
+        use crate::SetLen;
+
+        let mut rb = AllocRingBuffer::new(10);
+        rb.readptr = usize::MAX - 3;
+        rb.writeptr = usize::MAX - 3;
+        check_counters_wrap(&mut rb);
+        unsafe { rb.set_len(10) };
+        assert_eq!(rb.len(), 10);
+
+        // exact buffers that aren't a power of two keep their pointers within twice the size
+        let mut rb = AllocRingBuffer::new_exact(10);
+        rb.readptr = 8;
+        rb.writeptr = 8;
+        check_counters_wrap(&mut rb);
+        assert!(rb.readptr < 10);
+    }
+
+    #[test]
+    fn test_new_exact() {
+        let mut rb = AllocRingBuffer::new_exact(10);
+        assert_eq!(rb.capacity(), 10);
+        assert_eq!(rb.buffer_size(), 10);
+
+        for i in 0..1000_u32 {
+            let _ = rb.enqueue(i);
+            let expected: alloc::vec::Vec<_> = (i.saturating_sub(9)..=i).collect();
+            assert_eq!(rb.to_vec(), expected);
+            assert_eq!(rb[0], expected[0]);
+            assert_eq!(rb.get_signed(-1), Some(&i));
+
+            let mut dst = alloc::vec![0; rb.len()];
+            rb.copy_to_slice(0, &mut dst);
+            assert_eq!(dst, expected);
+        }
+        for i in 990..1000 {
+            assert_eq!(rb.dequeue(), Some(i));
+        }
+
+        // clones keep the exact size
+        rb.fill(1);
+        let clone = rb.clone();
+        assert_eq!(clone.buffer_size(), 10);
+        assert_eq!(clone, rb);
+
+        let rb = AllocRingBuffer::<u64>::try_new_exact(10).unwrap();
+        assert_eq!(rb.buffer_size(), 10);
+        assert!(AllocRingBuffer::<u64>::try_new_exact(1 << (usize::BITS - 2)).is_err());
+    }
+
     #[test]
     fn test_with_capacity_power_of_two() {
         let b = AllocRingBuffer::<i32>::with_capacity_power_of_2(2);
@@ -472,6 +669,37 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_try_new() {
+        let rb = AllocRingBuffer::<u64>::try_new(10).unwrap();
+        assert_eq!(rb.capacity(), 10);
+        assert_eq!(rb.size, 16);
+
+        // next_power_of_two overflows
+        assert!(AllocRingBuffer::<u8>::try_new(usize::MAX).is_err());
+        // more than isize::MAX bytes
+        assert!(AllocRingBuffer::<u64>::try_new(1 << (usize::BITS - 2)).is_err());
+        // but zero sized types never need any memory
+        assert!(AllocRingBuffer::<()>::try_new(1 << (usize::BITS - 2)).is_ok());
+        let mut rb = AllocRingBuffer::<()>::try_new(usize::MAX).unwrap();
+        assert_eq!(rb.capacity(), usize::MAX);
+        rb.extend([(), ()]);
+        assert_eq!((rb.dequeue(), rb.len()), (Some(()), 1));
+    }
+
+    #[test]
+    #[cfg_attr(miri, ignore)]
+    fn test_try_new_out_of_memory() {
+        // a valid layout, but way more memory than any machine has
+        assert!(AllocRingBuffer::<u8>::try_new(1 << (usize::BITS - 2)).is_err());
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_new_capacity_overflow() {
+        let _ = AllocRingBuffer::<u64>::new(1 << (usize::BITS - 2));
+    }
+
     #[test]
     fn test_conversions() {
         // from &[T]
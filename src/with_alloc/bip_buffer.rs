@@ -0,0 +1,165 @@
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::vec;
+
+use crate::bip_buffer::BipState;
+use crate::Pod;
+
+/// The `BipBuffer` is a bip buffer (bipartite buffer) which allocates its storage on the heap.
+/// See [`ConstGenericBipBuffer`](crate::ConstGenericBipBuffer) for one that doesn't need `alloc`.
+///
+/// Unlike a ringbuffer, a bip buffer never splits a region at the end of its storage. Instead
+/// [`writable`](BipBuffer::writable) always hands out the largest contiguous run of free slots,
+/// either after the last element or at the start of the storage, and
+/// [`readable`](BipBuffer::readable) hands out the elements up to where the writer wrapped
+/// around. This is useful for APIs which need a single slice, like some codecs. The price is that
+/// the free space at the end of the storage is unused while the writer is at its start.
+///
+/// Elements are never overwritten. Because [`writable`](BipBuffer::writable) exposes slots which
+/// were never written, elements must be [`Pod`].
+///
+/// # Example
+/// ```
+/// use ringbuffer::BipBuffer;
+///
+/// let mut buffer = BipBuffer::<u8>::new(8);
+///
+/// buffer.writable()[..6].copy_from_slice(b"hello ");
+/// buffer.advance_write(6);
+/// buffer.advance_read(4);
+///
+/// // there are only 2 free slots at the end, so writing continues at the start
+/// assert_eq!(buffer.writable().len(), 4);
+/// buffer.writable()[..3].copy_from_slice(b"bip");
+/// buffer.advance_write(3);
+///
+/// assert_eq!(buffer.readable(), b"o ");
+/// buffer.advance_read(2);
+/// assert_eq!(buffer.readable(), b"bip");
+/// ```
+#[derive(Debug, Clone)]
+pub struct BipBuffer<T: Pod> {
+    buf: Box<[T]>,
+    state: BipState,
+}
+
+impl<T: Pod> BipBuffer<T> {
+    /// Creates an empty `BipBuffer` with a certain capacity. The capacity must not be zero.
+    ///
+    /// # Panics
+    /// Panics when capacity is zero
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        assert_ne!(capacity, 0, "Capacity must be greater than 0");
+
+        // Safety: zero is a valid bit pattern for Pod types
+        let zero = unsafe { core::mem::zeroed() };
+        Self {
+            buf: vec![zero; capacity].into_boxed_slice(),
+            state: BipState::default(),
+        }
+    }
+
+    /// Returns the number of elements in the buffer, in both regions.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.state.len()
+    }
+
+    /// Returns true if the buffer holds no elements.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns the capacity of the buffer.
+    #[inline]
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.buf.len()
+    }
+
+    /// Returns the elements at the front of the buffer as one slice, up to where the writer
+    /// wrapped around. The rest is available after [`advance_read`](Self::advance_read) consumed
+    /// this slice.
+    #[inline]
+    #[must_use]
+    pub fn readable(&self) -> &[T] {
+        &self.buf[self.state.readable()]
+    }
+
+    /// Returns the largest contiguous run of free slots. Write to the start of it, and then call
+    /// [`advance_write`](Self::advance_write) to add what was written to the buffer.
+    #[inline]
+    pub fn writable(&mut self) -> &mut [T] {
+        let writable = self.state.writable(self.buf.len());
+        &mut self.buf[writable]
+    }
+
+    /// Adds the first `n` elements of [`writable`](Self::writable) to the back of the buffer.
+    ///
+    /// # Panics
+    /// Panics if [`writable`](Self::writable) is shorter than `n`.
+    #[inline]
+    pub fn advance_write(&mut self, n: usize) {
+        self.state.advance_write(self.buf.len(), n);
+    }
+
+    /// Removes the first `n` elements of [`readable`](Self::readable) from the front of the
+    /// buffer.
+    ///
+    /// # Panics
+    /// Panics if [`readable`](Self::readable) is shorter than `n`.
+    #[inline]
+    pub fn advance_read(&mut self, n: usize) {
+        self.state.advance_read(n);
+    }
+
+    /// Empties the buffer.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.state = BipState::default();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::BipBuffer;
+
+    #[test]
+    fn test_largest_region() {
+        let mut buffer = BipBuffer::<u16>::new(10);
+        assert_eq!(buffer.capacity(), 10);
+        assert_eq!(buffer.writable().len(), 10);
+
+        buffer.writable()[..7].copy_from_slice(&[1, 2, 3, 4, 5, 6, 7]);
+        buffer.advance_write(7);
+        buffer.advance_read(5);
+        assert_eq!(buffer.readable(), &[6, 7]);
+
+        // 3 slots at the end, 5 at the start
+        assert_eq!(buffer.writable().len(), 5);
+        buffer.writable()[..5].copy_from_slice(&[8, 9, 10, 11, 12]);
+        buffer.advance_write(5);
+        assert_eq!(buffer.len(), 7);
+        assert!(buffer.writable().is_empty());
+
+        assert_eq!(buffer.readable(), &[6, 7]);
+        buffer.advance_read(2);
+        assert_eq!(buffer.readable(), &[8, 9, 10, 11, 12]);
+        assert_eq!(buffer.writable().len(), 5);
+
+        buffer.clear();
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.writable().len(), 10);
+    }
+
+    #[test]
+    #[should_panic(expected = "Capacity must be greater than 0")]
+    fn test_zero_capacity() {
+        let _ = BipBuffer::<u8>::new(0);
+    }
+}
@@ -0,0 +1,553 @@
+use core::marker::PhantomData;
+use core::mem::size_of;
+use core::ops::{Index, IndexMut};
+use core::ptr::{self, NonNull};
+
+use crate::ringbuffer_trait::{
+    RingBuffer, RingBufferIntoIterator, RingBufferIterator, RingBufferMutIterator,
+};
+
+extern crate alloc;
+
+use crate::{mask_and, Pod, RingBufferSlices};
+
+/// The `MirroredRingBuffer` is a `RingBuffer` of which the readable and writable regions are
+/// always available as one contiguous slice, through [`readable`](MirroredRingBuffer::readable)
+/// and [`writable`](MirroredRingBuffer::writable). This makes it convenient to hand the buffer
+/// straight to APIs which read or write slices, like `std::io::Read` and `std::io::Write`,
+/// without splitting the work at the wrap point.
+///
+/// On linux with the `mmap` feature, this is done by mapping the same physical memory twice,
+/// back to back, so that accessing past the end of the buffer wraps around to the start.
+/// Where that isn't possible (other platforms, or when the mapping fails), it falls back to a
+/// plain heap allocation like [`AllocRingBuffer`](crate::AllocRingBuffer). Then the slices stop at
+/// the wrap point, and you'll need a second call to get the rest.
+/// [`is_mirrored`](MirroredRingBuffer::is_mirrored) tells you which one you got.
+///
+/// Because [`writable`](MirroredRingBuffer::writable) exposes slots which were never written,
+/// elements must be [`Pod`].
+///
+/// # Example
+/// ```
+/// use ringbuffer::{MirroredRingBuffer, RingBuffer};
+///
+/// let mut buffer = MirroredRingBuffer::<u8>::new(4096);
+///
+/// let data = b"hello";
+/// buffer.writable()[..data.len()].copy_from_slice(data);
+/// buffer.advance_write(data.len());
+///
+/// assert_eq!(buffer.readable(), b"hello");
+/// buffer.advance_read(2);
+/// assert_eq!(buffer.readable(), b"llo");
+/// ```
+#[derive(Debug)]
+pub struct MirroredRingBuffer<T: Pod> {
+    buf: *mut T,
+
+    // the number of slots. Next power of 2 up from the capacity, and when mirrored, large
+    // enough to be a whole number of pages
+    size: usize,
+    // maximum number of elements actually allowed in the ringbuffer.
+    // Always less than or equal than the size
+    capacity: usize,
+    // whether the slots are mapped a second time directly after the first mapping
+    mirrored: bool,
+
+    readptr: usize,
+    writeptr: usize,
+
+    phantom: PhantomData<T>,
+}
+
+// SAFETY: all methods that require mutable access take &mut,
+// and we exclusively own the memory behind buf
+unsafe impl<T: Pod + Sync> Sync for MirroredRingBuffer<T> {}
+unsafe impl<T: Pod + Send> Send for MirroredRingBuffer<T> {}
+
+impl<T: Pod> MirroredRingBuffer<T> {
+    /// Creates a `MirroredRingBuffer` with a certain capacity. The capacity must not be zero.
+    ///
+    /// Falls back to a regular heap allocation if the memory can't be mirrored.
+    ///
+    /// # Panics
+    /// Panics when capacity is zero
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        assert_ne!(capacity, 0, "Capacity must be greater than 0");
+
+        #[cfg(all(feature = "mmap", target_os = "linux"))]
+        if let Some(rb) = Self::new_mirrored(capacity) {
+            return rb;
+        }
+
+        Self::new_unmirrored(capacity)
+    }
+
+    /// Returns true if the memory of this buffer is mirrored, meaning that
+    /// [`readable`](MirroredRingBuffer::readable) and [`writable`](MirroredRingBuffer::writable)
+    /// always cover everything there is to read or write.
+    #[inline]
+    #[must_use]
+    pub fn is_mirrored(&self) -> bool {
+        self.mirrored
+    }
+
+    /// Returns the elements in the buffer as one slice, starting at the front.
+    ///
+    /// If the buffer [`is_mirrored`](MirroredRingBuffer::is_mirrored), this is every element.
+    /// Otherwise this stops at the wrap point, and the rest is available after
+    /// [`advance_read`](MirroredRingBuffer::advance_read).
+    #[must_use]
+    pub fn readable(&self) -> &[T] {
+        let start = mask_and(self.size, self.readptr);
+        let len = self.contiguous(start, self.len());
+
+        // Safety: start + len is in bounds of the (possibly mirrored) memory,
+        // and every value in it is a valid T
+        unsafe { core::slice::from_raw_parts(self.buf.add(start), len) }
+    }
+
+    /// Returns the free space after the back of the buffer as one slice. Write to the start of it,
+    /// and then call [`advance_write`](MirroredRingBuffer::advance_write) to add what was written
+    /// to the buffer.
+    ///
+    /// If the buffer [`is_mirrored`](MirroredRingBuffer::is_mirrored), this is all free space.
+    /// Otherwise this stops at the wrap point, and the rest is available after
+    /// [`advance_write`](MirroredRingBuffer::advance_write).
+    #[must_use]
+    pub fn writable(&mut self) -> &mut [T] {
+        let start = mask_and(self.size, self.writeptr);
+        let len = self.contiguous(start, self.capacity - self.len());
+
+        // Safety: start + len is in bounds of the (possibly mirrored) memory, every value in it
+        // is a valid T since T is pod, and none of it is part of the readable region
+        unsafe { core::slice::from_raw_parts_mut(self.buf.add(start), len) }
+    }
+
+    /// Adds the first `n` elements of [`writable`](MirroredRingBuffer::writable) to the back of
+    /// the buffer.
+    ///
+    /// # Panics
+    /// Panics if there is less than `n` free space in the buffer.
+    pub fn advance_write(&mut self, n: usize) {
+        let free = self.capacity - self.len();
+        assert!(
+            n <= free,
+            "cannot advance the write pointer by {n} with only {free} free space"
+        );
+        self.writeptr = self.writeptr.wrapping_add(n);
+    }
+
+    /// Removes the first `n` elements of [`readable`](MirroredRingBuffer::readable) from the
+    /// front of the buffer.
+    ///
+    /// # Panics
+    /// Panics if there are less than `n` elements in the buffer.
+    pub fn advance_read(&mut self, n: usize) {
+        let len = self.len();
+        assert!(
+            n <= len,
+            "cannot advance the read pointer by {n} with only {len} elements in the buffer"
+        );
+        self.readptr = self.readptr.wrapping_add(n);
+    }
+
+    /// How much of `len` elements from `start` can be accessed in one slice
+    fn contiguous(&self, start: usize, len: usize) -> usize {
+        if self.mirrored {
+            len
+        } else {
+            len.min(self.size - start)
+        }
+    }
+
+    #[cfg(all(feature = "mmap", target_os = "linux"))]
+    fn new_mirrored(capacity: usize) -> Option<Self> {
+        use crate::with_mmap::sys;
+
+        if size_of::<T>() == 0 {
+            return None;
+        }
+
+        // both halves have to be whole pages for the mirroring to line up
+        let page_size = sys::page_size();
+        let mut size = capacity.checked_next_power_of_two()?;
+        while size.checked_mul(size_of::<T>())? % page_size != 0 {
+            size = size.checked_mul(2)?;
+        }
+
+        let buf = sys::map_mirrored(size * size_of::<T>()).ok()?;
+        Some(Self {
+            buf: buf.cast(),
+            size,
+            capacity,
+            mirrored: true,
+            readptr: 0,
+            writeptr: 0,
+            phantom: PhantomData,
+        })
+    }
+
+    fn new_unmirrored(capacity: usize) -> Self {
+        let size = capacity.next_power_of_two();
+        let buf = if size_of::<T>() == 0 {
+            NonNull::dangling().as_ptr()
+        } else {
+            let layout = alloc::alloc::Layout::array::<T>(size).unwrap();
+            // zeroed, since writable() hands out slots which were never written
+            // Safety: layout has a non-zero size
+            let buf = unsafe { alloc::alloc::alloc_zeroed(layout) };
+            if buf.is_null() {
+                alloc::alloc::handle_alloc_error(layout);
+            }
+            buf.cast()
+        };
+
+        Self {
+            buf,
+            size,
+            capacity,
+            mirrored: false,
+            readptr: 0,
+            writeptr: 0,
+            phantom: PhantomData,
+        }
+    }
+}
+
+impl<T: Pod> Drop for MirroredRingBuffer<T> {
+    fn drop(&mut self) {
+        if self.mirrored {
+            #[cfg(all(feature = "mmap", target_os = "linux"))]
+            // Safety: buf is the start of both halves of the mirrored mapping we own
+            unsafe {
+                crate::with_mmap::sys::unmap(self.buf.cast(), 2 * self.size * size_of::<T>());
+            }
+        } else if size_of::<T>() != 0 {
+            let layout = alloc::alloc::Layout::array::<T>(self.size).unwrap();
+            unsafe {
+                alloc::alloc::dealloc(self.buf.cast(), layout);
+            }
+        }
+    }
+}
+
+impl<T: Pod> Clone for MirroredRingBuffer<T> {
+    fn clone(&self) -> Self {
+        let mut new = Self::new(self.capacity);
+        new.extend(self.iter().copied());
+        new
+    }
+}
+
+impl<T: Pod + PartialEq> PartialEq for MirroredRingBuffer<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.capacity == other.capacity
+            && self.len() == other.len()
+            && self.iter().zip(other.iter()).all(|(a, b)| a == b)
+    }
+}
+
+impl<T: Pod + Eq> Eq for MirroredRingBuffer<T> {}
+
+impl<T: Pod> IntoIterator for MirroredRingBuffer<T> {
+    type Item = T;
+    type IntoIter = RingBufferIntoIterator<T, Self>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        RingBufferIntoIterator::new(self)
+    }
+}
+
+#[allow(clippy::into_iter_without_iter)]
+// iter() is implemented on the trait
+impl<'a, T: Pod> IntoIterator for &'a MirroredRingBuffer<T> {
+    type Item = &'a T;
+    type IntoIter = RingBufferIterator<'a, T, MirroredRingBuffer<T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[allow(clippy::into_iter_without_iter)]
+// iter_mut() is implemented on the trait
+impl<'a, T: Pod> IntoIterator for &'a mut MirroredRingBuffer<T> {
+    type Item = &'a mut T;
+    type IntoIter = RingBufferMutIterator<'a, T, MirroredRingBuffer<T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T: Pod> Extend<T> for MirroredRingBuffer<T> {
+    fn extend<A: IntoIterator<Item = T>>(&mut self, iter: A) {
+        let iter = iter.into_iter();
+
+        for i in iter {
+            let _ = self.enqueue(i);
+        }
+    }
+}
+
+unsafe impl<T: Pod> RingBuffer<T> for MirroredRingBuffer<T> {
+    #[inline]
+    unsafe fn ptr_capacity(rb: *const Self) -> usize {
+        (*rb).capacity
+    }
+
+    #[inline]
+    unsafe fn ptr_buffer_size(rb: *const Self) -> usize {
+        (*rb).size
+    }
+
+    impl_ringbuffer!(readptr, writeptr);
+
+    #[inline]
+    fn enqueue(&mut self, value: T) -> Option<T> {
+        let mut ret = None;
+
+        if self.is_full() {
+            // mask with and is allowed here because size is always a power of two
+            let previous_value =
+                unsafe { ptr::read(get_unchecked_mut(self, mask_and(self.size, self.readptr))) };
+
+            ret = Some(previous_value);
+            self.readptr = self.readptr.wrapping_add(1);
+        }
+
+        // mask with and is allowed here because size is always a power of two
+        let index = mask_and(self.size, self.writeptr);
+
+        unsafe {
+            ptr::write(get_unchecked_mut(self, index), value);
+        }
+
+        self.writeptr = self.writeptr.wrapping_add(1);
+
+        ret
+    }
+
+    fn dequeue(&mut self) -> Option<T> {
+        if self.is_empty() {
+            None
+        } else {
+            // mask with and is allowed here because size is always a power of two
+            let index = mask_and(self.size, self.readptr);
+            let res = unsafe { get_unchecked_mut(self, index) };
+            self.readptr = self.readptr.wrapping_add(1);
+
+            // Safety: every bit pattern is a valid T
+            unsafe { Some(ptr::read(res)) }
+        }
+    }
+
+    impl_ringbuffer_ext!(
+        get_base_ptr,
+        get_base_mut_ptr,
+        get_unchecked,
+        get_unchecked_mut,
+        readptr,
+        writeptr,
+        mask_ptr
+    );
+
+    #[inline]
+    fn fill_with<F: FnMut() -> T>(&mut self, mut f: F) {
+        self.clear();
+
+        self.readptr = 0;
+        self.writeptr = self.capacity;
+
+        for i in 0..self.capacity {
+            unsafe { ptr::write(get_unchecked_mut(self, i), f()) };
+        }
+    }
+}
+
+impl<T: Pod> RingBufferSlices<T> for MirroredRingBuffer<T> {
+    impl_ring_buffer_slices!(get_base_ptr, readptr, mask_ptr);
+}
+
+/// Get a const pointer to the buffer
+unsafe fn get_base_ptr<T: Pod>(rb: *const MirroredRingBuffer<T>) -> *const T {
+    (*rb).buf.cast()
+}
+
+/// Get a mut pointer to the buffer
+unsafe fn get_base_mut_ptr<T: Pod>(rb: *mut MirroredRingBuffer<T>) -> *mut T {
+    (*rb).buf
+}
+
+/// Wrap a pointer, or a pointer plus an offset within the buffer, into the buffer
+#[inline]
+unsafe fn mask_ptr<T: Pod>(rb: *const MirroredRingBuffer<T>, ptr: usize) -> usize {
+    mask_and((*rb).size, ptr)
+}
+
+/// Get a reference from the buffer without checking it is initialized.
+///
+/// Caller must be sure the index is in bounds, or this will panic.
+#[inline]
+unsafe fn get_unchecked<'a, T: Pod>(rb: *const MirroredRingBuffer<T>, index: usize) -> &'a T {
+    let p = (*rb).buf.add(index);
+    // Safety: caller makes sure the index is in bounds for the ringbuffer.
+    // Every bit pattern is a valid T, so all in bounds values are initialized
+    &*p
+}
+
+/// Get a mut reference from the buffer without checking it is initialized.
+///
+/// Caller must be sure the index is in bounds, or this will panic.
+#[inline]
+unsafe fn get_unchecked_mut<T: Pod>(rb: *mut MirroredRingBuffer<T>, index: usize) -> *mut T {
+    // Safety: caller makes sure the index is in bounds for the ringbuffer.
+    (*rb).buf.add(index)
+}
+
+impl<T: Pod> Index<usize> for MirroredRingBuffer<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get(index).expect("index out of bounds")
+    }
+}
+
+impl<T: Pod> IndexMut<usize> for MirroredRingBuffer<T> {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        self.get_mut(index).expect("index out of bounds")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{MirroredRingBuffer, RingBuffer};
+    use alloc::vec;
+    use alloc::vec::Vec;
+
+    /// Write `data` through the writable slices, then read it back through the readable ones
+    fn write_then_read(rb: &mut MirroredRingBuffer<u32>, data: &[u32]) -> Vec<u32> {
+        let mut written = 0;
+        while written < data.len() {
+            let writable = rb.writable();
+            let n = writable.len().min(data.len() - written);
+            assert_ne!(n, 0);
+            writable[..n].copy_from_slice(&data[written..written + n]);
+            rb.advance_write(n);
+            written += n;
+        }
+
+        let mut read = Vec::new();
+        while !rb.is_empty() {
+            let readable = rb.readable();
+            read.extend_from_slice(readable);
+            let n = readable.len();
+            rb.advance_read(n);
+        }
+        read
+    }
+
+    fn test_wrap(mut rb: MirroredRingBuffer<u32>) {
+        let capacity = rb.capacity();
+        let data = (0..capacity as u32).collect::<Vec<_>>();
+
+        // move the pointers to just before the wrap point
+        rb.readptr = rb.size - 3;
+        rb.writeptr = rb.size - 3;
+
+        assert_eq!(write_then_read(&mut rb, &data), data);
+        assert_eq!(write_then_read(&mut rb, &data[..5]), data[..5]);
+    }
+
+    #[test]
+    fn test_counters_wrap_at_usize_max() {
+        let mut rb = MirroredRingBuffer::<usize>::new_unmirrored(8);
+        rb.readptr = usize::MAX - 3;
+        rb.writeptr = usize::MAX - 3;
+        crate::tests::check_counters_wrap(&mut rb);
+
+        let data = (0..8).collect::<Vec<_>>();
+        let mut rb = MirroredRingBuffer::<u32>::new_unmirrored(8);
+        rb.readptr = usize::MAX - 3;
+        rb.writeptr = usize::MAX - 3;
+        assert_eq!(write_then_read(&mut rb, &data), data);
+    }
+
+    #[test]
+    fn test_readable_writable_wrap() {
+        test_wrap(MirroredRingBuffer::new(1024));
+        test_wrap(MirroredRingBuffer::new(1000));
+        test_wrap(MirroredRingBuffer::new_unmirrored(1024));
+        test_wrap(MirroredRingBuffer::new_unmirrored(1000));
+    }
+
+    #[test]
+    #[cfg(all(feature = "mmap", target_os = "linux"))]
+    fn test_mirrored_is_contiguous() {
+        let mut rb = MirroredRingBuffer::<u8>::new(10);
+        assert!(rb.is_mirrored());
+        // rounded up to a whole page
+        assert_eq!(rb.buffer_size() % crate::with_mmap::sys::page_size(), 0);
+        assert_eq!(rb.capacity(), 10);
+
+        rb.readptr = rb.size - 2;
+        rb.writeptr = rb.size - 2;
+
+        assert_eq!(rb.writable().len(), 10);
+        rb.writable()[..4].copy_from_slice(&[1, 2, 3, 4]);
+        rb.advance_write(4);
+        assert_eq!(rb.readable(), &[1, 2, 3, 4]);
+        assert_eq!(rb.to_vec(), vec![1, 2, 3, 4]);
+
+        // elements which aren't a power of two in size still line up with the pages
+        let rb = MirroredRingBuffer::<[u8; 3]>::new(10);
+        assert!(rb.is_mirrored());
+        assert_eq!(rb.buffer_size() * 3 % crate::with_mmap::sys::page_size(), 0);
+    }
+
+    #[test]
+    fn test_unmirrored_stops_at_wrap() {
+        let mut rb = MirroredRingBuffer::<u8>::new_unmirrored(8);
+        assert!(!rb.is_mirrored());
+
+        rb.advance_write(6);
+        rb.advance_read(6);
+
+        assert_eq!(rb.writable().len(), 2);
+        rb.writable().copy_from_slice(&[1, 2]);
+        rb.advance_write(2);
+        assert_eq!(rb.writable().len(), 6);
+        rb.writable()[..2].copy_from_slice(&[3, 4]);
+        rb.advance_write(2);
+
+        assert_eq!(rb.readable(), &[1, 2]);
+        assert_eq!(rb.to_vec(), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_enqueue_overwrites() {
+        let mut rb = MirroredRingBuffer::<u64>::new(4);
+        rb.extend(0..10);
+        assert_eq!(rb.to_vec(), vec![6, 7, 8, 9]);
+        assert_eq!(rb.writable().len(), 0);
+        assert_eq!(rb.dequeue(), Some(6));
+        assert_eq!(rb.readable()[0], 7);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_advance_write_past_capacity() {
+        let mut rb = MirroredRingBuffer::<u8>::new(4);
+        rb.advance_write(5);
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_advance_read_past_len() {
+        let mut rb = MirroredRingBuffer::<u8>::new(4);
+        rb.advance_write(2);
+        rb.advance_read(3);
+    }
+}
@@ -1,2 +1,7 @@
 pub mod alloc_ringbuffer;
+pub mod bip_buffer;
+pub mod mirrored_ringbuffer;
+pub mod mpmc;
+pub mod row_ringbuffer;
+pub mod seqlock;
 pub mod vecdeque;
@@ -0,0 +1,445 @@
+extern crate alloc;
+
+use alloc::boxed::Box;
+use core::cell::UnsafeCell;
+use core::fmt;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::{mask_and, CacheAligned};
+
+/// A slot of the buffer. Its stamp tells which lap of the pointers may use it next: a producer
+/// at position `pos` may write it once the stamp is `pos`, and then sets it to `pos + 1`. A
+/// consumer at `pos` may read it once the stamp is `pos + 1`, and then sets it to `pos + size`,
+/// handing it to the producer of the next lap.
+struct Slot<T> {
+    stamp: AtomicUsize,
+    value: UnsafeCell<MaybeUninit<T>>,
+}
+
+/// The `MpmcRingBuffer` is a bounded lock-free queue which any number of threads can push to and
+/// pop from at the same time, through a shared reference.
+///
+/// Like the default [`AllocRingBuffer`](crate::AllocRingBuffer) it allocates a power of two
+/// number of slots, so the capacity is rounded up to the next power of two, and is at least 2.
+/// Unlike the other ringbuffers it doesn't overwrite elements once it is full: another thread may
+/// be reading the oldest element at that moment, so [`try_push`](MpmcRingBuffer::try_push) hands
+/// the value back instead.
+///
+/// Every slot carries a stamp, which producers and consumers use to claim it (the design of
+/// Dmitry Vyukov's bounded MPMC queue). The head and tail counters live on separate cache lines,
+/// so producers and consumers don't slow each other down with false sharing.
+///
+/// # Example
+/// ```
+/// use ringbuffer::MpmcRingBuffer;
+///
+/// let queue = MpmcRingBuffer::new(2);
+///
+/// std::thread::scope(|s| {
+///     s.spawn(|| queue.try_push(1).unwrap());
+///     s.spawn(|| queue.try_push(2).unwrap());
+/// });
+///
+/// // the buffer is full, so we get the value back
+/// assert_eq!(queue.try_push(3), Err(3));
+///
+/// let mut popped = [queue.try_pop().unwrap(), queue.try_pop().unwrap()];
+/// popped.sort_unstable();
+/// assert_eq!(popped, [1, 2]);
+/// assert_eq!(queue.try_pop(), None);
+/// ```
+pub struct MpmcRingBuffer<T> {
+    // the position the next element is popped from
+    head: CacheAligned<AtomicUsize>,
+    // the position the next element is pushed to
+    tail: CacheAligned<AtomicUsize>,
+    slots: Box<[Slot<T>]>,
+}
+
+// Safety: the stamps hand every value from exactly one producer to exactly one consumer, which
+// may be on different threads.
+unsafe impl<T: Send> Send for MpmcRingBuffer<T> {}
+unsafe impl<T: Send> Sync for MpmcRingBuffer<T> {}
+
+impl<T> MpmcRingBuffer<T> {
+    /// Creates an empty `MpmcRingBuffer` with room for at least `capacity` elements. The
+    /// capacity is rounded up to the next power of two, and to at least 2.
+    ///
+    /// # Panics
+    /// Panics when capacity is zero, or larger than the largest power of two a `usize` holds
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        assert_ne!(capacity, 0, "Capacity must be greater than 0");
+
+        // a single slot can't tell a full buffer from an empty one by its stamp
+        let size = capacity
+            .checked_next_power_of_two()
+            .expect("Capacity is too large to round up to a power of two")
+            .max(2);
+        Self {
+            head: CacheAligned(AtomicUsize::new(0)),
+            tail: CacheAligned(AtomicUsize::new(0)),
+            slots: (0..size)
+                .map(|i| Slot {
+                    stamp: AtomicUsize::new(i),
+                    value: UnsafeCell::new(MaybeUninit::uninit()),
+                })
+                .collect(),
+        }
+    }
+
+    /// Returns the number of elements the buffer can hold.
+    #[inline]
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.slots.len()
+    }
+
+    /// Returns the number of elements in the buffer. Other threads may push or pop at any moment,
+    /// so this is only an estimate by the time it returns.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        loop {
+            let tail = self.tail.0.load(Ordering::SeqCst);
+            let head = self.head.0.load(Ordering::SeqCst);
+            // only use a consistent pair of counters
+            if self.tail.0.load(Ordering::SeqCst) == tail {
+                return tail.wrapping_sub(head).min(self.capacity());
+            }
+        }
+    }
+
+    /// Returns true if the buffer holds no elements. Like [`len`](Self::len), this is only an
+    /// estimate.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns true if the buffer can't hold any more elements. Like [`len`](Self::len), this is
+    /// only an estimate.
+    #[inline]
+    #[must_use]
+    pub fn is_full(&self) -> bool {
+        self.len() == self.capacity()
+    }
+
+    /// Adds a value to the back of the buffer.
+    ///
+    /// # Errors
+    /// Hands `value` back when the buffer is full.
+    pub fn try_push(&self, value: T) -> Result<(), T> {
+        let mut pos = self.tail.0.load(Ordering::Relaxed);
+        loop {
+            let slot = &self.slots[mask_and(self.slots.len(), pos)];
+            let stamp = slot.stamp.load(Ordering::Acquire);
+
+            match (stamp.wrapping_sub(pos) as isize).cmp(&0) {
+                core::cmp::Ordering::Equal => {
+                    // the slot is free in this lap, try to claim it
+                    match self.tail.0.compare_exchange_weak(
+                        pos,
+                        pos.wrapping_add(1),
+                        Ordering::Relaxed,
+                        Ordering::Relaxed,
+                    ) {
+                        Ok(_) => {
+                            // Safety: claiming the position gave us exclusive access to the slot
+                            let _ = unsafe { (*slot.value.get()).write(value) };
+                            slot.stamp.store(pos.wrapping_add(1), Ordering::Release);
+                            return Ok(());
+                        }
+                        Err(current) => pos = current,
+                    }
+                }
+                // the slot still holds the element of the previous lap
+                core::cmp::Ordering::Less => return Err(value),
+                // another producer claimed the position already
+                core::cmp::Ordering::Greater => pos = self.tail.0.load(Ordering::Relaxed),
+            }
+        }
+    }
+
+    /// Removes the value at the front of the buffer, or returns `None` when it is empty.
+    pub fn try_pop(&self) -> Option<T> {
+        let mut pos = self.head.0.load(Ordering::Relaxed);
+        loop {
+            let slot = &self.slots[mask_and(self.slots.len(), pos)];
+            let stamp = slot.stamp.load(Ordering::Acquire);
+
+            match (stamp.wrapping_sub(pos.wrapping_add(1)) as isize).cmp(&0) {
+                core::cmp::Ordering::Equal => {
+                    // the slot was written in this lap, try to claim it
+                    match self.head.0.compare_exchange_weak(
+                        pos,
+                        pos.wrapping_add(1),
+                        Ordering::Relaxed,
+                        Ordering::Relaxed,
+                    ) {
+                        Ok(_) => {
+                            // Safety: claiming the position gave us exclusive access to the
+                            // slot, and the stamp says it was initialized
+                            let value = unsafe { (*slot.value.get()).assume_init_read() };
+                            slot.stamp
+                                .store(pos.wrapping_add(self.slots.len()), Ordering::Release);
+                            return Some(value);
+                        }
+                        Err(current) => pos = current,
+                    }
+                }
+                // the slot wasn't written in this lap yet
+                core::cmp::Ordering::Less => return None,
+                // another consumer claimed the position already
+                core::cmp::Ordering::Greater => pos = self.head.0.load(Ordering::Relaxed),
+            }
+        }
+    }
+}
+
+impl<T> Drop for MpmcRingBuffer<T> {
+    fn drop(&mut self) {
+        let head = *self.head.0.get_mut();
+        let tail = *self.tail.0.get_mut();
+        for i in 0..tail.wrapping_sub(head) {
+            let slot = &mut self.slots[mask_and(self.slots.len(), head.wrapping_add(i))];
+            // Safety: every slot between head and tail holds an element
+            unsafe { slot.value.get_mut().assume_init_drop() };
+        }
+    }
+}
+
+impl<T> fmt::Debug for MpmcRingBuffer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("MpmcRingBuffer")
+            .field("capacity", &self.capacity())
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::MpmcRingBuffer;
+    use core::sync::atomic::{AtomicUsize, Ordering};
+    use std::collections::VecDeque;
+    use std::rc::Rc;
+    use std::sync::Mutex;
+    use std::vec::Vec;
+
+    /// The operations shared by [`MpmcRingBuffer`] and the model it is checked against.
+    trait Queue: Sync {
+        fn try_push(&self, value: u64) -> Result<(), u64>;
+        fn try_pop(&self) -> Option<u64>;
+    }
+
+    impl Queue for MpmcRingBuffer<u64> {
+        fn try_push(&self, value: u64) -> Result<(), u64> {
+            self.try_push(value)
+        }
+
+        fn try_pop(&self) -> Option<u64> {
+            self.try_pop()
+        }
+    }
+
+    struct Model {
+        queue: Mutex<VecDeque<u64>>,
+        capacity: usize,
+    }
+
+    impl Queue for Model {
+        fn try_push(&self, value: u64) -> Result<(), u64> {
+            let mut queue = self.queue.lock().unwrap();
+            if queue.len() == self.capacity {
+                return Err(value);
+            }
+            queue.push_back(value);
+            Ok(())
+        }
+
+        fn try_pop(&self) -> Option<u64> {
+            self.queue.lock().unwrap().pop_front()
+        }
+    }
+
+    const PRODUCERS: u64 = 4;
+    const PER_PRODUCER: u64 = 5000;
+
+    /// Pushes from several producers while `consumers` threads pop, and returns what every
+    /// consumer popped, in the order it popped it.
+    fn stress(queue: &impl Queue, consumers: usize) -> Vec<Vec<u64>> {
+        let popped = AtomicUsize::new(0);
+        let total = (PRODUCERS * PER_PRODUCER) as usize;
+
+        let seen = std::thread::scope(|s| {
+            for producer in 0..PRODUCERS {
+                let _ = s.spawn(move || {
+                    for i in 0..PER_PRODUCER {
+                        let mut value = producer << 32 | i;
+                        while let Err(v) = queue.try_push(value) {
+                            value = v;
+                            std::thread::yield_now();
+                        }
+                    }
+                });
+            }
+
+            let consumers: Vec<_> = (0..consumers)
+                .map(|_| {
+                    s.spawn(|| {
+                        let mut seen = Vec::new();
+                        while popped.load(Ordering::Relaxed) < total {
+                            let Some(value) = queue.try_pop() else {
+                                std::thread::yield_now();
+                                continue;
+                            };
+                            let _ = popped.fetch_add(1, Ordering::Relaxed);
+                            seen.push(value);
+                        }
+                        seen
+                    })
+                })
+                .collect();
+
+            consumers.into_iter().map(|c| c.join().unwrap()).collect()
+        });
+
+        assert!(queue.try_pop().is_none());
+        seen
+    }
+
+    /// Checks what the consumers of [`stress`] popped: every element exactly once, and the
+    /// elements of each producer in the order they were pushed, by every consumer. A single
+    /// consumer sees all elements of each producer, so it must see exactly `0..PER_PRODUCER`.
+    fn check_stress(seen: &[Vec<u64>]) {
+        for popped in seen {
+            for producer in 0..PRODUCERS {
+                let from_producer = popped.iter().filter(|value| *value >> 32 == producer);
+                if seen.len() == 1 {
+                    assert!(
+                        from_producer
+                            .map(|value| value & 0xffff_ffff)
+                            .eq(0..PER_PRODUCER),
+                        "the elements of producer {producer} were reordered or lost"
+                    );
+                } else {
+                    let from_producer: Vec<_> = from_producer.collect();
+                    assert!(
+                        from_producer.windows(2).all(|pair| pair[0] < pair[1]),
+                        "the elements of producer {producer} were reordered"
+                    );
+                }
+            }
+        }
+
+        let mut all: Vec<u64> = seen.concat();
+        all.sort_unstable();
+        let expected: Vec<u64> = (0..PRODUCERS)
+            .flat_map(|producer| (0..PER_PRODUCER).map(move |i| producer << 32 | i))
+            .collect();
+        assert_eq!(all, expected, "elements were lost or duplicated");
+    }
+
+    #[test]
+    fn test_stress_against_model() {
+        for consumers in [1, 4] {
+            let queue = MpmcRingBuffer::new(16);
+            let model = Model {
+                queue: Mutex::new(VecDeque::new()),
+                capacity: 16,
+            };
+
+            check_stress(&stress(&queue, consumers));
+            check_stress(&stress(&model, consumers));
+            assert!(queue.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_sequential_against_model() {
+        let queue = MpmcRingBuffer::new(5);
+        let model = Model {
+            queue: Mutex::new(VecDeque::new()),
+            capacity: 8,
+        };
+        assert_eq!(queue.capacity(), 8);
+
+        // xorshift, so the sequence of pushes and pops is the same every run
+        let mut state = 0x2545_f491_u64;
+        for i in 0..10_000 {
+            state ^= state << 13;
+            state ^= state >> 7;
+            state ^= state << 17;
+
+            if state % 3 == 0 {
+                assert_eq!(Queue::try_pop(&queue), model.try_pop());
+            } else {
+                assert_eq!(Queue::try_push(&queue, i), model.try_push(i));
+            }
+            assert_eq!(queue.len(), model.queue.lock().unwrap().len());
+        }
+    }
+
+    #[test]
+    fn test_capacity() {
+        assert_eq!(MpmcRingBuffer::<u8>::new(1).capacity(), 2);
+        assert_eq!(MpmcRingBuffer::<u8>::new(2).capacity(), 2);
+        assert_eq!(MpmcRingBuffer::<u8>::new(9).capacity(), 16);
+
+        let queue = MpmcRingBuffer::new(1);
+        assert_eq!(queue.try_push(1), Ok(()));
+        assert_eq!(queue.try_push(2), Ok(()));
+        assert!(queue.is_full());
+        assert_eq!(queue.try_push(3), Err(3));
+    }
+
+    #[test]
+    #[should_panic(expected = "Capacity must be greater than 0")]
+    fn test_zero_capacity() {
+        let _ = MpmcRingBuffer::<u8>::new(0);
+    }
+
+    #[test]
+    #[should_panic(expected = "Capacity is too large to round up to a power of two")]
+    fn test_capacity_overflow() {
+        let _ = MpmcRingBuffer::<u8>::new(usize::MAX);
+    }
+
+    #[test]
+    fn test_counters_wrap_at_usize_max() {
+        let mut queue = MpmcRingBuffer::new(4);
+        let start = usize::MAX - 5;
+        *queue.head.0.get_mut() = start;
+        *queue.tail.0.get_mut() = start;
+        for (i, slot) in queue.slots.iter_mut().enumerate() {
+            // the stamps a buffer which was used up to `start` would have
+            *slot.stamp.get_mut() = start.wrapping_add((i.wrapping_sub(start)) & 3);
+        }
+
+        for i in 0..100 {
+            assert_eq!(queue.try_push(i), Ok(()));
+            assert_eq!(queue.try_push(i + 1), Ok(()));
+            assert_eq!(queue.len(), 2);
+            assert_eq!(queue.try_pop(), Some(i));
+            assert_eq!(queue.try_pop(), Some(i + 1));
+            assert_eq!(queue.try_pop(), None);
+        }
+    }
+
+    #[test]
+    fn test_drop() {
+        let value = Rc::new(());
+        let queue = MpmcRingBuffer::new(4);
+        for _ in 0..3 {
+            assert!(queue.try_push(Rc::clone(&value)).is_ok());
+        }
+        drop(queue.try_pop());
+        assert_eq!(Rc::strong_count(&value), 3);
+        drop(queue);
+        assert_eq!(Rc::strong_count(&value), 1);
+    }
+}
@@ -0,0 +1,334 @@
+extern crate alloc;
+
+use alloc::boxed::Box;
+use alloc::vec;
+
+/// The `RowRingBuffer` is an overwriting ringbuffer of rows which all have the same width, set
+/// at runtime. The rows are stored back to back in one allocation, like spectrogram rows or
+/// interleaved multi-channel audio frames, where each row holds one sample per channel.
+///
+/// Every row is a contiguous slice. The rows are also stored a second time, spread over the
+/// columns, so a column, holding the same position of every row, is two contiguous slices too:
+/// [`channel`](RowRingBuffer::channel) returns them for one column, and
+/// [`deinterleave`](RowRingBuffer::deinterleave) for every column, which turns interleaved
+/// frames into slices per channel without copying. This costs writing every element twice.
+///
+/// # Example
+/// ```
+/// use ringbuffer::RowRingBuffer;
+///
+/// // stereo frames, keeping the last 3
+/// let mut frames = RowRingBuffer::<f32>::new(2, 3);
+/// for i in 0..4 {
+///     frames.enqueue(&[i as f32, -(i as f32)]);
+/// }
+///
+/// assert_eq!(frames.row(0), &[1.0, -1.0]);
+/// assert!(frames.column(0).eq(&[1.0, 2.0, 3.0]));
+///
+/// let mut channels = frames.deinterleave();
+/// assert_eq!(channels.next(), Some((&[1.0, 2.0][..], &[3.0][..])));
+/// assert_eq!(channels.next(), Some((&[-1.0, -2.0][..], &[-3.0][..])));
+/// ```
+#[derive(Debug, Clone)]
+pub struct RowRingBuffer<T> {
+    // the rows, back to back
+    buf: Box<[T]>,
+    // the same elements by column: the element in row slot `r` and column `c` is at
+    // `c * capacity + r`
+    columns: Box<[T]>,
+    width: usize,
+    // the row of the oldest element, always below the capacity
+    readptr: usize,
+    len: usize,
+}
+
+impl<T: Copy + Default> RowRingBuffer<T> {
+    /// Creates an empty `RowRingBuffer` for `capacity` rows which are `width` elements wide.
+    /// Neither may be zero.
+    ///
+    /// # Panics
+    /// Panics when the width or the capacity is zero, or when they don't fit in memory together
+    #[must_use]
+    pub fn new(width: usize, capacity: usize) -> Self {
+        assert_ne!(width, 0, "Width must be greater than 0");
+        assert_ne!(capacity, 0, "Capacity must be greater than 0");
+        let size = width
+            .checked_mul(capacity)
+            .expect("width times capacity overflows usize");
+
+        Self {
+            buf: vec![T::default(); size].into_boxed_slice(),
+            columns: vec![T::default(); size].into_boxed_slice(),
+            width,
+            readptr: 0,
+            len: 0,
+        }
+    }
+
+    /// Returns the number of elements in every row.
+    #[inline]
+    #[must_use]
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    /// Returns the number of rows the buffer can hold.
+    #[inline]
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.buf.len() / self.width
+    }
+
+    /// Returns the number of rows in the buffer.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Returns true if the buffer holds no rows.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    /// Returns true if the next [`enqueue`](Self::enqueue) overwrites the oldest row.
+    #[inline]
+    #[must_use]
+    pub fn is_full(&self) -> bool {
+        self.len == self.capacity()
+    }
+
+    /// The slot of the row `index` rows after the read pointer.
+    #[inline]
+    fn slot(&self, index: usize) -> usize {
+        crate::mask_any(self.capacity(), self.readptr + index)
+    }
+
+    /// The index of the first element of the row `index` rows after the read pointer.
+    #[inline]
+    fn start(&self, index: usize) -> usize {
+        self.slot(index) * self.width
+    }
+
+    /// Copies `row` into the row slot `slot`, in both storages.
+    fn write_row(&mut self, slot: usize, row: &[T]) {
+        let capacity = self.capacity();
+        let start = slot * self.width;
+        self.buf[start..start + self.width].copy_from_slice(row);
+        for (column, element) in row.iter().enumerate() {
+            self.columns[column * capacity + slot] = *element;
+        }
+    }
+
+    /// Panics if `row` isn't exactly [`width`](Self::width) elements wide.
+    fn check_width(&self, row: &[T]) {
+        assert_eq!(
+            row.len(),
+            self.width,
+            "row of {} elements doesn't match the width of the buffer",
+            row.len()
+        );
+    }
+
+    /// Copies a row onto the back of the buffer, overwriting the oldest row once it is full.
+    ///
+    /// # Panics
+    /// Panics if the row isn't exactly [`width`](Self::width) elements wide.
+    pub fn enqueue(&mut self, row: &[T]) {
+        self.check_width(row);
+
+        self.write_row(self.slot(self.len), row);
+        if self.is_full() {
+            self.readptr = crate::mask_any(self.capacity(), self.readptr + 1);
+        } else {
+            self.len += 1;
+        }
+    }
+
+    /// Removes the oldest row from the buffer and returns it, or `None` if the buffer is empty.
+    pub fn dequeue(&mut self) -> Option<&[T]> {
+        if self.is_empty() {
+            return None;
+        }
+
+        let start = self.start(0);
+        self.readptr = crate::mask_any(self.capacity(), self.readptr + 1);
+        self.len -= 1;
+        Some(&self.buf[start..start + self.width])
+    }
+
+    /// Returns the row at `index`, where 0 is the oldest row, or `None` if there are fewer rows.
+    #[inline]
+    #[must_use]
+    pub fn get_row(&self, index: usize) -> Option<&[T]> {
+        (index < self.len).then(|| {
+            let start = self.start(index);
+            &self.buf[start..start + self.width]
+        })
+    }
+
+    /// Returns the row at `index`, where 0 is the oldest row.
+    ///
+    /// # Panics
+    /// Panics if the buffer holds `index` rows or fewer.
+    #[inline]
+    #[must_use]
+    pub fn row(&self, index: usize) -> &[T] {
+        self.get_row(index).expect("index out of bounds")
+    }
+
+    /// Overwrites the row at `index`, where 0 is the oldest row, with a copy of `row`.
+    ///
+    /// # Panics
+    /// Panics if the buffer holds `index` rows or fewer, or if the row isn't exactly
+    /// [`width`](Self::width) elements wide.
+    pub fn set_row(&mut self, index: usize, row: &[T]) {
+        assert!(index < self.len, "index out of bounds");
+        self.check_width(row);
+        self.write_row(self.slot(index), row);
+    }
+
+    /// Returns an iterator over the rows, oldest first.
+    #[must_use]
+    pub fn rows(&self) -> impl DoubleEndedIterator<Item = &[T]> + ExactSizeIterator + '_ {
+        (0..self.len).map(|index| self.row(index))
+    }
+
+    /// Returns the rows as two slices of whole rows, oldest first, split where the rows wrap
+    /// around the end of the storage. The second slice is empty when they don't.
+    #[must_use]
+    pub fn as_slices(&self) -> (&[T], &[T]) {
+        let start = self.start(0);
+        let end = start + self.len * self.width;
+        if end <= self.buf.len() {
+            (&self.buf[start..end], &[])
+        } else {
+            (&self.buf[start..], &self.buf[..end - self.buf.len()])
+        }
+    }
+
+    /// Returns an iterator over the elements at position `column` of every row, oldest first.
+    ///
+    /// # Panics
+    /// Panics if `column` isn't below the [`width`](Self::width).
+    #[must_use]
+    pub fn column(&self, column: usize) -> impl DoubleEndedIterator<Item = &T> + ExactSizeIterator {
+        assert!(column < self.width, "column out of bounds");
+        self.rows().map(move |row| &row[column])
+    }
+
+    /// Returns the elements at position `column` of every row as two slices, oldest first,
+    /// split where they wrap around the end of the storage. The second slice is empty when they
+    /// don't. For interleaved frames, this is a channel.
+    ///
+    /// # Panics
+    /// Panics if `column` isn't below the [`width`](Self::width).
+    #[must_use]
+    pub fn channel(&self, column: usize) -> (&[T], &[T]) {
+        assert!(column < self.width, "column out of bounds");
+        let capacity = self.capacity();
+        let channel = &self.columns[column * capacity..(column + 1) * capacity];
+        let first = self.len.min(capacity - self.readptr);
+        (
+            &channel[self.readptr..self.readptr + first],
+            &channel[..self.len - first],
+        )
+    }
+
+    /// Returns an iterator over every column as two slices, like [`channel`](Self::channel).
+    /// There are always [`width`](Self::width) of them, even when the buffer is empty.
+    #[must_use]
+    pub fn deinterleave(
+        &self,
+    ) -> impl DoubleEndedIterator<Item = (&[T], &[T])> + ExactSizeIterator + '_ {
+        (0..self.width).map(|column| self.channel(column))
+    }
+
+    /// Empties the buffer.
+    #[inline]
+    pub fn clear(&mut self) {
+        self.readptr = 0;
+        self.len = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::RowRingBuffer;
+    use alloc::vec::Vec;
+
+    #[test]
+    fn test_rows_wrap() {
+        let mut buffer = RowRingBuffer::<u16>::new(3, 4);
+        assert_eq!(buffer.width(), 3);
+        assert_eq!(buffer.capacity(), 4);
+        assert_eq!(buffer.get_row(0), None);
+
+        for i in 0..6 {
+            buffer.enqueue(&[i, 10 + i, 20 + i]);
+        }
+        assert!(buffer.is_full());
+        assert_eq!(buffer.row(0), &[2, 12, 22]);
+        assert_eq!(buffer.row(3), &[5, 15, 25]);
+        assert_eq!(buffer.get_row(4), None);
+        assert_eq!(buffer.rows().rev().next(), Some(&[5, 15, 25][..]));
+
+        let (first, second) = buffer.as_slices();
+        assert_eq!(first, &[2, 12, 22, 3, 13, 23]);
+        assert_eq!(second, &[4, 14, 24, 5, 15, 25]);
+
+        buffer.set_row(1, &[100, 13, 23]);
+        assert_eq!(buffer.dequeue(), Some(&[2, 12, 22][..]));
+        assert_eq!(buffer.dequeue(), Some(&[100, 13, 23][..]));
+        assert_eq!(buffer.len(), 2);
+        assert_eq!(buffer.as_slices().1, &[]);
+
+        buffer.clear();
+        assert!(buffer.is_empty());
+        assert_eq!(buffer.dequeue(), None);
+    }
+
+    #[test]
+    fn test_columns() {
+        let mut buffer = RowRingBuffer::<i32>::new(2, 3);
+        for i in 0..5 {
+            buffer.enqueue(&[i, -i]);
+        }
+        assert_eq!(buffer.column(1).copied().collect::<Vec<_>>(), [-2, -3, -4]);
+        assert_eq!(buffer.column(0).len(), 3);
+
+        // the oldest row is in the last slot, so the channels wrap
+        assert_eq!(buffer.channel(0), (&[2][..], &[3, 4][..]));
+        let channels: Vec<_> = buffer.deinterleave().collect();
+        assert_eq!(
+            channels,
+            [(&[2][..], &[3, 4][..]), (&[-2][..], &[-3, -4][..])]
+        );
+
+        buffer.set_row(0, &[7, -7]);
+        assert_eq!(buffer.dequeue(), Some(&[7, -7][..]));
+        assert_eq!(buffer.channel(1), (&[-3, -4][..], &[][..]));
+
+        let empty = RowRingBuffer::<i32>::new(2, 3);
+        let empty_channel: (&[i32], &[i32]) = (&[], &[]);
+        assert_eq!(
+            empty.deinterleave().collect::<Vec<_>>(),
+            [empty_channel, empty_channel]
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "doesn't match the width")]
+    fn test_wrong_width() {
+        RowRingBuffer::<u8>::new(2, 2).enqueue(&[1, 2, 3]);
+    }
+
+    #[test]
+    #[should_panic(expected = "Width must be greater than 0")]
+    fn test_zero_width() {
+        let _ = RowRingBuffer::<u8>::new(0, 2);
+    }
+}
@@ -0,0 +1,403 @@
+extern crate alloc;
+
+use alloc::boxed::Box;
+use core::fmt;
+use core::marker::PhantomData;
+use core::mem::{size_of, MaybeUninit};
+use core::ops::Range;
+use core::ptr;
+use core::sync::atomic::{fence, AtomicBool, AtomicUsize, Ordering};
+
+use crate::{wrapping_regions, CacheAligned, Pod};
+
+/// The size of the words the slots are stored in.
+const WORD: usize = size_of::<usize>();
+
+/// The `SeqlockRingBuffer` is an overwriting ringbuffer which one writer fills without ever
+/// waiting, while any number of threads take snapshots of the most recent elements at the same
+/// time.
+///
+/// The writer is claimed with [`writer`](SeqlockRingBuffer::writer). Readers don't need to claim
+/// anything: [`snapshot_into`](SeqlockRingBuffer::snapshot_into) and
+/// [`latest`](SeqlockRingBuffer::latest) work through a shared reference. They copy the elements
+/// without locking, and copy them again when the writer overwrote any of them in the meantime
+/// (a sequence lock). Because readers may copy an element while it is being written, elements
+/// are stored in atomic words, and copied a word at a time.
+///
+/// The elements must be [`Pod`] rather than just `Copy`, for two reasons. Copying an element
+/// through integers reads all of its bytes, which is undefined behaviour for the padding bytes a
+/// `Copy` type may have. And a torn copy is written to the destination before the reader notices
+/// and copies again, so every mix of bytes from two elements has to be a valid value.
+///
+/// Readers only retry when the writer laps the elements they copy, so taking a snapshot of a few
+/// elements from a large buffer rarely has to.
+///
+/// # Example
+/// ```
+/// use ringbuffer::SeqlockRingBuffer;
+///
+/// let samples = SeqlockRingBuffer::new(4);
+///
+/// std::thread::scope(|s| {
+///     s.spawn(|| {
+///         let mut writer = samples.writer().unwrap();
+///         for i in 0..10 {
+///             writer.enqueue(i);
+///         }
+///     });
+/// });
+///
+/// let mut last = [0; 3];
+/// assert_eq!(samples.snapshot_into(&mut last), 3);
+/// assert_eq!(last, [7, 8, 9]);
+/// assert_eq!(samples.latest(), Some(9));
+/// ```
+pub struct SeqlockRingBuffer<T: Pod> {
+    // twice the number of enqueued elements, plus one while the writer is enqueueing
+    seq: CacheAligned<AtomicUsize>,
+    // the slot the next element is written to
+    writeptr: AtomicUsize,
+    len: AtomicUsize,
+    writer: AtomicBool,
+    capacity: usize,
+    // the slots, one element after the other, each in as many words as its bytes need
+    words: Box<[AtomicUsize]>,
+    _marker: PhantomData<T>,
+}
+
+/// The writer of a [`SeqlockRingBuffer`], claimed with [`SeqlockRingBuffer::writer`]. Dropping
+/// it lets another writer be claimed.
+pub struct SeqlockWriter<'a, T: Pod> {
+    rb: &'a SeqlockRingBuffer<T>,
+}
+
+impl<T: Pod> SeqlockRingBuffer<T> {
+    /// Creates an empty `SeqlockRingBuffer` with a certain capacity. The capacity must not be
+    /// zero.
+    ///
+    /// # Panics
+    /// Panics when capacity is zero, or when the slots don't fit in memory
+    #[must_use]
+    pub fn new(capacity: usize) -> Self {
+        assert_ne!(capacity, 0, "Capacity must be greater than 0");
+        let words = capacity
+            .checked_mul(Self::slot_words())
+            .expect("capacity overflows usize");
+
+        Self {
+            seq: CacheAligned(AtomicUsize::new(0)),
+            writeptr: AtomicUsize::new(0),
+            len: AtomicUsize::new(0),
+            writer: AtomicBool::new(false),
+            capacity,
+            words: (0..words).map(|_| AtomicUsize::new(0)).collect(),
+            _marker: PhantomData,
+        }
+    }
+
+    /// Claims the writer, or returns `None` when another one is claimed already.
+    #[inline]
+    pub fn writer(&self) -> Option<SeqlockWriter<'_, T>> {
+        self.writer
+            .compare_exchange(false, true, Ordering::Acquire, Ordering::Relaxed)
+            .ok()
+            .map(|_| SeqlockWriter { rb: self })
+    }
+
+    /// Returns the number of elements the buffer can hold.
+    #[inline]
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of elements in the buffer. Once the buffer filled up, this is always
+    /// the capacity.
+    #[inline]
+    #[must_use]
+    pub fn len(&self) -> usize {
+        self.len.load(Ordering::Acquire)
+    }
+
+    /// Returns true if nothing was enqueued yet.
+    #[inline]
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The number of words every slot takes.
+    #[inline]
+    const fn slot_words() -> usize {
+        size_of::<T>().div_ceil(WORD)
+    }
+
+    /// The words of the contiguous `slots`.
+    #[inline]
+    fn words(&self, slots: Range<usize>) -> &[AtomicUsize] {
+        &self.words[slots.start * Self::slot_words()..slots.end * Self::slot_words()]
+    }
+
+    /// Copies the contiguous `slots` into `dst`, which is as long.
+    #[inline]
+    fn copy_slots(&self, slots: Range<usize>, dst: &mut [MaybeUninit<T>]) {
+        if Self::slot_words() == 0 {
+            // zero sized elements have no bytes to copy
+            return;
+        }
+        let words = self.words(slots).chunks_exact(Self::slot_words());
+        for (element, words) in dst.iter_mut().zip(words) {
+            let out = element.as_mut_ptr().cast::<u8>();
+            let mut copied = 0;
+            for word in words {
+                let bytes = word.load(Ordering::Relaxed).to_ne_bytes();
+                let n = WORD.min(size_of::<T>() - copied);
+                // Safety: this writes the bytes of the element from `copied` to at most its size
+                unsafe { ptr::copy_nonoverlapping(bytes.as_ptr(), out.add(copied), n) };
+                copied += n;
+            }
+        }
+    }
+
+    /// Copies the most recent elements into `dst`, oldest first, and returns how many were
+    /// copied. That is the length of `dst`, or the length of the buffer when it holds fewer
+    /// elements. The copied elements are consistent: they were all in the buffer at the same
+    /// moment.
+    pub fn snapshot_into(&self, dst: &mut [T]) -> usize {
+        // Safety: T and MaybeUninit<T> have the same layout, and only valid values of T are
+        // written through this
+        let dst = unsafe {
+            core::slice::from_raw_parts_mut(dst.as_mut_ptr().cast::<MaybeUninit<T>>(), dst.len())
+        };
+        self.snapshot_uninit(dst)
+    }
+
+    /// Like [`snapshot_into`](Self::snapshot_into), initializing the first elements of `dst` it
+    /// returns the number of.
+    fn snapshot_uninit(&self, dst: &mut [MaybeUninit<T>]) -> usize {
+        let size = self.capacity;
+
+        loop {
+            let before = self.seq.0.load(Ordering::Acquire);
+            if before % 2 == 1 {
+                // the writer is in the middle of enqueueing
+                core::hint::spin_loop();
+                continue;
+            }
+
+            // the writer publishes the length after the write pointer, so loading it first never
+            // counts a slot before the write pointer which wasn't written yet
+            let n = dst.len().min(self.len.load(Ordering::Acquire));
+            let writeptr = self.writeptr.load(Ordering::Acquire);
+            let from_idx = if writeptr >= n {
+                writeptr - n
+            } else {
+                writeptr + size - n
+            };
+            // The copy may race with the writer, which is checked below. Both sides only access
+            // the words atomically, so that race is no undefined behaviour.
+            let (first, second) = wrapping_regions(size, from_idx, n);
+            let (dst_first, dst_second) = dst[..n].split_at_mut(first.len());
+            self.copy_slots(first, dst_first);
+            self.copy_slots(second, dst_second);
+
+            fence(Ordering::Acquire);
+            let after = self.seq.0.load(Ordering::Relaxed);
+            // the writer overwrites the oldest slots first, so the copied ones are only touched
+            // once it enqueued `size - n` more elements
+            let enqueued = after.wrapping_sub(before).div_ceil(2);
+            if enqueued <= size - n {
+                // every byte of the copied elements was written, and any bit pattern is a valid
+                // Pod value
+                return n;
+            }
+        }
+    }
+
+    /// Returns the most recently enqueued element, or `None` if nothing was enqueued yet.
+    #[must_use]
+    pub fn latest(&self) -> Option<T> {
+        let mut latest = [MaybeUninit::uninit()];
+        if self.snapshot_uninit(&mut latest) == 1 {
+            // Safety: snapshot_uninit initialized the element
+            Some(unsafe { latest[0].assume_init() })
+        } else {
+            None
+        }
+    }
+}
+
+impl<T: Pod> SeqlockWriter<'_, T> {
+    /// Adds a value onto the buffer, overwriting the oldest element once it is full. This never
+    /// waits for readers.
+    pub fn enqueue(&mut self, value: T) {
+        let rb = self.rb;
+        let seq = rb.seq.0.load(Ordering::Relaxed);
+        rb.seq.0.store(seq.wrapping_add(1), Ordering::Relaxed);
+        fence(Ordering::Release);
+
+        let writeptr = rb.writeptr.load(Ordering::Relaxed);
+        // Safety: Pod types have no padding, so all their bytes are initialized
+        let bytes = unsafe {
+            core::slice::from_raw_parts(ptr::from_ref(&value).cast::<u8>(), size_of::<T>())
+        };
+        let slot = rb.words(writeptr..writeptr + 1);
+        for (word, bytes) in slot.iter().zip(bytes.chunks(WORD)) {
+            let mut padded = [0; WORD];
+            padded[..bytes.len()].copy_from_slice(bytes);
+            word.store(usize::from_ne_bytes(padded), Ordering::Relaxed);
+        }
+        let writeptr = if writeptr + 1 == rb.capacity {
+            0
+        } else {
+            writeptr + 1
+        };
+        rb.writeptr.store(writeptr, Ordering::Release);
+        let len = rb.len.load(Ordering::Relaxed);
+        if len < rb.capacity {
+            rb.len.store(len + 1, Ordering::Release);
+        }
+
+        rb.seq.0.store(seq.wrapping_add(2), Ordering::Release);
+    }
+}
+
+impl<T: Pod> Drop for SeqlockWriter<'_, T> {
+    fn drop(&mut self) {
+        self.rb.writer.store(false, Ordering::Release);
+    }
+}
+
+impl<T: Pod> fmt::Debug for SeqlockRingBuffer<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SeqlockRingBuffer")
+            .field("capacity", &self.capacity())
+            .field("len", &self.len())
+            .finish_non_exhaustive()
+    }
+}
+
+impl<T: Pod> fmt::Debug for SeqlockWriter<'_, T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SeqlockWriter").finish_non_exhaustive()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    extern crate std;
+
+    use super::SeqlockRingBuffer;
+    use core::sync::atomic::{AtomicBool, Ordering};
+
+    #[test]
+    fn test_snapshots() {
+        let rb = SeqlockRingBuffer::new(5);
+        let mut dst = [0; 3];
+        assert_eq!(rb.snapshot_into(&mut dst), 0);
+        assert_eq!(rb.latest(), None);
+
+        let mut writer = rb.writer().unwrap();
+        writer.enqueue(1);
+        writer.enqueue(2);
+        assert_eq!(rb.snapshot_into(&mut dst), 2);
+        assert_eq!(dst[..2], [1, 2]);
+
+        // wraps around the end of the slots
+        for i in 3..=8 {
+            writer.enqueue(i);
+        }
+        assert_eq!(rb.len(), 5);
+        assert_eq!(rb.snapshot_into(&mut dst), 3);
+        assert_eq!(dst, [6, 7, 8]);
+
+        let mut all = [0; 8];
+        assert_eq!(rb.snapshot_into(&mut all), 5);
+        assert_eq!(all[..5], [4, 5, 6, 7, 8]);
+        assert_eq!(rb.latest(), Some(8));
+    }
+
+    #[test]
+    fn test_single_writer() {
+        let rb = SeqlockRingBuffer::<u8>::new(2);
+        let writer = rb.writer();
+        assert!(writer.is_some());
+        assert!(rb.writer().is_none());
+        drop(writer);
+        assert!(rb.writer().is_some());
+    }
+
+    #[test]
+    fn test_concurrent_snapshots_are_consistent() {
+        // every element is a run of the same value, so a torn copy shows up as a mix
+        let rb = SeqlockRingBuffer::<[u64; 8]>::new(16);
+        let done = AtomicBool::new(false);
+
+        std::thread::scope(|s| {
+            let _ = s.spawn(|| {
+                let mut writer = rb.writer().unwrap();
+                for i in 0..100_000 {
+                    writer.enqueue([i; 8]);
+                }
+                done.store(true, Ordering::Release);
+            });
+
+            for n in [1, 4, 16] {
+                let (rb, done) = (&rb, &done);
+                let _ = s.spawn(move || {
+                    let mut dst = [[0; 8]; 16];
+                    while !done.load(Ordering::Acquire) {
+                        let copied = rb.snapshot_into(&mut dst[..n]);
+                        for (i, element) in dst[..copied].iter().enumerate() {
+                            assert!(element.iter().all(|v| *v == element[0]), "torn read");
+                            if i > 0 {
+                                // consecutive elements, all there at the same moment
+                                assert_eq!(element[0], dst[i - 1][0] + 1);
+                            }
+                        }
+                    }
+                });
+            }
+        });
+    }
+
+    #[test]
+    fn test_sizes_between_words() {
+        // elements which don't fill their last word, and ones smaller than a word
+        let rb = SeqlockRingBuffer::<[u8; 11]>::new(3);
+        let mut writer = rb.writer().unwrap();
+        for i in 0..5 {
+            writer.enqueue([i; 11]);
+        }
+        let mut dst = [[0; 11]; 3];
+        assert_eq!(rb.snapshot_into(&mut dst), 3);
+        assert_eq!(dst, [[2; 11], [3; 11], [4; 11]]);
+
+        let rb = SeqlockRingBuffer::<u16>::new(4);
+        let mut writer = rb.writer().unwrap();
+        for i in 0..6 {
+            writer.enqueue(i * 1000);
+        }
+        let mut dst = [0; 4];
+        assert_eq!(rb.snapshot_into(&mut dst), 4);
+        assert_eq!(dst, [2000, 3000, 4000, 5000]);
+    }
+
+    #[test]
+    fn test_zero_sized() {
+        let rb = SeqlockRingBuffer::<()>::new(3);
+        assert_eq!(rb.latest(), None);
+        let mut writer = rb.writer().unwrap();
+        for _ in 0..5 {
+            writer.enqueue(());
+        }
+        assert_eq!(rb.snapshot_into(&mut [(); 4]), 3);
+        assert_eq!(rb.latest(), Some(()));
+    }
+
+    #[test]
+    #[should_panic(expected = "Capacity must be greater than 0")]
+    fn test_zero_capacity() {
+        let _ = SeqlockRingBuffer::<u8>::new(0);
+    }
+}
@@ -1,6 +1,6 @@
 use crate::ringbuffer_trait::{RingBufferIntoIterator, RingBufferIterator, RingBufferMutIterator};
-use crate::{AllocRingBuffer, RingBuffer};
-use alloc::collections::VecDeque;
+use crate::{AllocRingBuffer, RingBuffer, RingBufferSlices};
+use alloc::collections::{TryReserveError, VecDeque};
 use core::ops::{Deref, DerefMut, Index, IndexMut};
 
 /// A growable ringbuffer. Once capacity is reached, the size is doubled.
@@ -40,8 +40,9 @@ impl<T: Clone> From<&[T]> for GrowableAllocRingBuffer<T> {
 }
 
 impl<T> From<AllocRingBuffer<T>> for GrowableAllocRingBuffer<T> {
+    /// Keeps the capacity of `v`.
     fn from(mut v: AllocRingBuffer<T>) -> GrowableAllocRingBuffer<T> {
-        let mut rb = GrowableAllocRingBuffer::new();
+        let mut rb = GrowableAllocRingBuffer::with_capacity(v.capacity());
         rb.extend(v.drain());
         rb
     }
@@ -94,8 +95,9 @@ impl From<&str> for GrowableAllocRingBuffer<char> {
 impl<T, const CAP: usize> From<crate::ConstGenericRingBuffer<T, CAP>>
     for GrowableAllocRingBuffer<T>
 {
+    /// Keeps the capacity of `value`.
     fn from(mut value: crate::ConstGenericRingBuffer<T, CAP>) -> Self {
-        let mut res = GrowableAllocRingBuffer::new();
+        let mut res = GrowableAllocRingBuffer::with_capacity(CAP);
         res.extend(value.drain());
         res
     }
@@ -139,6 +141,19 @@ impl<T> GrowableAllocRingBuffer<T> {
     pub fn with_capacity(capacity: usize) -> Self {
         Self(VecDeque::with_capacity(capacity))
     }
+
+    /// Creates an empty ringbuffer with space for at least capacity elements, like
+    /// [`with_capacity`](GrowableAllocRingBuffer::with_capacity), but returns an error instead of
+    /// panicking or aborting when the memory can't be allocated.
+    ///
+    /// # Errors
+    /// Fails when the allocation would exceed `isize::MAX` bytes, or when the allocator is out
+    /// of memory.
+    pub fn try_with_capacity(capacity: usize) -> Result<Self, TryReserveError> {
+        let mut deque = VecDeque::new();
+        deque.try_reserve_exact(capacity)?;
+        Ok(Self(deque))
+    }
 }
 
 impl<T> IntoIterator for GrowableAllocRingBuffer<T> {
@@ -233,11 +248,13 @@ unsafe impl<T> RingBuffer<T> for GrowableAllocRingBuffer<T> {
         if RingBuffer::ptr_len(rb) == 0 {
             None
         } else if index >= 0 {
-            (*rb).0.get_mut(index.unsigned_abs())
+            (*rb)
+                .0
+                .get_mut(crate::mask_modulo(Self::ptr_len(rb), index.unsigned_abs()))
         } else {
             let len = Self::ptr_len(rb);
 
-            let positive_index = index.unsigned_abs() + 1;
+            let positive_index = index.unsigned_abs() - 1;
             let masked = crate::mask_modulo(len, positive_index);
             let index = len - 1 - masked;
 
@@ -319,6 +336,12 @@ unsafe impl<T> RingBuffer<T> for GrowableAllocRingBuffer<T> {
     }
 }
 
+impl<T> RingBufferSlices<T> for GrowableAllocRingBuffer<T> {
+    fn as_slices(&self) -> (&[T], &[T]) {
+        self.0.as_slices()
+    }
+}
+
 impl<T> Extend<T> for GrowableAllocRingBuffer<T> {
     fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
         self.0.extend(iter);
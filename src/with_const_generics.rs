@@ -1,5 +1,5 @@
 use crate::ringbuffer_trait::{RingBufferIntoIterator, RingBufferIterator, RingBufferMutIterator};
-use crate::{impl_ring_buffer_set_len, RingBuffer, SetLen};
+use crate::{impl_ring_buffer_set_len, CapacityError, RingBuffer, RingBufferSlices, SetLen};
 use core::iter::FromIterator;
 use core::mem::MaybeUninit;
 use core::mem::{self, ManuallyDrop};
@@ -42,6 +42,9 @@ pub struct ConstGenericRingBuffer<T, const CAP: usize> {
 
 impl<T, const CAP: usize> From<[T; CAP]> for ConstGenericRingBuffer<T, CAP> {
     fn from(value: [T; CAP]) -> Self {
+        #[allow(clippy::let_unit_value)]
+        let () = Self::ERROR_CAPACITY_IS_TOO_LARGE;
+
         let v = ManuallyDrop::new(value);
         Self {
             // Safety:
@@ -62,9 +65,12 @@ impl<T: Clone, const CAP: usize> From<&[T; CAP]> for ConstGenericRingBuffer<T, C
     }
 }
 
-impl<T: Clone, const CAP: usize> From<&[T]> for ConstGenericRingBuffer<T, CAP> {
-    fn from(value: &[T]) -> Self {
-        value.iter().cloned().collect()
+impl<'a, T: Clone, const CAP: usize> TryFrom<&'a [T]> for ConstGenericRingBuffer<T, CAP> {
+    type Error = CapacityError<&'a [T]>;
+
+    fn try_from(value: &'a [T]) -> Result<Self, Self::Error> {
+        let value = CapacityError::check(value, value.len(), CAP)?;
+        Ok(value.iter().cloned().collect())
     }
 }
 
@@ -74,61 +80,88 @@ impl<T: Clone, const CAP: usize> From<&mut [T; CAP]> for ConstGenericRingBuffer<
     }
 }
 
-impl<T: Clone, const CAP: usize> From<&mut [T]> for ConstGenericRingBuffer<T, CAP> {
-    fn from(value: &mut [T]) -> Self {
-        value.iter().cloned().collect()
+impl<'a, T: Clone, const CAP: usize> TryFrom<&'a mut [T]> for ConstGenericRingBuffer<T, CAP> {
+    type Error = CapacityError<&'a mut [T]>;
+
+    fn try_from(value: &'a mut [T]) -> Result<Self, Self::Error> {
+        let len = value.len();
+        let value = CapacityError::check(value, len, CAP)?;
+        Ok(value.iter().cloned().collect())
     }
 }
 
 #[cfg(feature = "alloc")]
-impl<T, const CAP: usize> From<alloc::vec::Vec<T>> for ConstGenericRingBuffer<T, CAP> {
-    fn from(value: alloc::vec::Vec<T>) -> Self {
-        value.into_iter().collect()
+impl<T, const CAP: usize> TryFrom<alloc::vec::Vec<T>> for ConstGenericRingBuffer<T, CAP> {
+    type Error = CapacityError<alloc::vec::Vec<T>>;
+
+    fn try_from(value: alloc::vec::Vec<T>) -> Result<Self, Self::Error> {
+        let len = value.len();
+        Ok(CapacityError::check(value, len, CAP)?.into_iter().collect())
     }
 }
 
 #[cfg(feature = "alloc")]
-impl<T, const CAP: usize> From<alloc::collections::VecDeque<T>> for ConstGenericRingBuffer<T, CAP> {
-    fn from(value: alloc::collections::VecDeque<T>) -> Self {
-        value.into_iter().collect()
+impl<T, const CAP: usize> TryFrom<alloc::collections::VecDeque<T>>
+    for ConstGenericRingBuffer<T, CAP>
+{
+    type Error = CapacityError<alloc::collections::VecDeque<T>>;
+
+    fn try_from(value: alloc::collections::VecDeque<T>) -> Result<Self, Self::Error> {
+        let len = value.len();
+        Ok(CapacityError::check(value, len, CAP)?.into_iter().collect())
     }
 }
 
 #[cfg(feature = "alloc")]
-impl<T, const CAP: usize> From<alloc::collections::LinkedList<T>>
+impl<T, const CAP: usize> TryFrom<alloc::collections::LinkedList<T>>
     for ConstGenericRingBuffer<T, CAP>
 {
-    fn from(value: alloc::collections::LinkedList<T>) -> Self {
-        value.into_iter().collect()
+    type Error = CapacityError<alloc::collections::LinkedList<T>>;
+
+    fn try_from(value: alloc::collections::LinkedList<T>) -> Result<Self, Self::Error> {
+        let len = value.len();
+        Ok(CapacityError::check(value, len, CAP)?.into_iter().collect())
     }
 }
 
 #[cfg(feature = "alloc")]
-impl<const CAP: usize> From<alloc::string::String> for ConstGenericRingBuffer<char, CAP> {
-    fn from(value: alloc::string::String) -> Self {
-        value.chars().collect()
+impl<const CAP: usize> TryFrom<alloc::string::String> for ConstGenericRingBuffer<char, CAP> {
+    type Error = CapacityError<alloc::string::String>;
+
+    fn try_from(value: alloc::string::String) -> Result<Self, Self::Error> {
+        let len = value.chars().count();
+        Ok(CapacityError::check(value, len, CAP)?.chars().collect())
     }
 }
 
-impl<const CAP: usize> From<&str> for ConstGenericRingBuffer<char, CAP> {
-    fn from(value: &str) -> Self {
-        value.chars().collect()
+impl<'a, const CAP: usize> TryFrom<&'a str> for ConstGenericRingBuffer<char, CAP> {
+    type Error = CapacityError<&'a str>;
+
+    fn try_from(value: &'a str) -> Result<Self, Self::Error> {
+        let value = CapacityError::check(value, value.chars().count(), CAP)?;
+        Ok(value.chars().collect())
     }
 }
 
 #[cfg(feature = "alloc")]
-impl<T, const CAP: usize> From<crate::GrowableAllocRingBuffer<T>>
+impl<T, const CAP: usize> TryFrom<crate::GrowableAllocRingBuffer<T>>
     for ConstGenericRingBuffer<T, CAP>
 {
-    fn from(mut value: crate::GrowableAllocRingBuffer<T>) -> Self {
-        value.drain().collect()
+    type Error = CapacityError<crate::GrowableAllocRingBuffer<T>>;
+
+    fn try_from(value: crate::GrowableAllocRingBuffer<T>) -> Result<Self, Self::Error> {
+        let len = value.len();
+        Ok(CapacityError::check(value, len, CAP)?.drain().collect())
     }
 }
 
 #[cfg(feature = "alloc")]
-impl<T, const CAP: usize> From<crate::AllocRingBuffer<T>> for ConstGenericRingBuffer<T, CAP> {
-    fn from(mut value: crate::AllocRingBuffer<T>) -> Self {
-        value.drain().collect()
+impl<T, const CAP: usize> TryFrom<crate::AllocRingBuffer<T>> for ConstGenericRingBuffer<T, CAP> {
+    type Error = CapacityError<crate::AllocRingBuffer<T>>;
+
+    fn try_from(value: crate::AllocRingBuffer<T>) -> Result<Self, Self::Error> {
+        let len = value.len();
+        Ok(CapacityError::check(value, len, CAP)?.drain().collect())
     }
 }
 
@@ -165,13 +198,16 @@ impl<T: PartialEq, const CAP: usize> PartialEq for ConstGenericRingBuffer<T, CAP
 impl<T: PartialEq, const CAP: usize> Eq for ConstGenericRingBuffer<T, CAP> {}
 
 impl<T, const CAP: usize> ConstGenericRingBuffer<T, CAP> {
+    impl_grants!(get_base_mut_ptr, readptr, writeptr, mask_ptr);
+
     const ERROR_CAPACITY_IS_NOT_ALLOWED_TO_BE_ZERO: () =
         assert!(CAP != 0, "Capacity is not allowed to be zero");
+    const ERROR_CAPACITY_IS_TOO_LARGE: () = assert!(CAP <= usize::MAX / 2, "Capacity is too large");
 
     /// Creates a const generic ringbuffer, size is passed as a const generic.
     ///
-    /// Note that the size does not have to be a power of two, but that not using a power
-    /// of two might be significantly (up to 3 times) slower.
+    /// The size does not have to be a power of two. Power of two sizes wrap around with a bit
+    /// mask, other sizes with a comparison, both without dividing.
     #[inline]
     #[must_use]
     pub const fn new<const N: usize>() -> Self
@@ -180,6 +216,8 @@ impl<T, const CAP: usize> ConstGenericRingBuffer<T, CAP> {
     {
         #[allow(clippy::let_unit_value)]
         let () = Self::ERROR_CAPACITY_IS_NOT_ALLOWED_TO_BE_ZERO;
+        #[allow(clippy::let_unit_value)]
+        let () = Self::ERROR_CAPACITY_IS_TOO_LARGE;
 
         Self {
             buf: [const { MaybeUninit::<T>::uninit() }; CAP],
@@ -187,8 +225,58 @@ impl<T, const CAP: usize> ConstGenericRingBuffer<T, CAP> {
             readptr: 0,
         }
     }
+
+    // When CAP is a power of two, the pointers only ever count up, wrapping around at
+    // `usize::MAX`, and are wrapped into the buffer with a bit mask. Otherwise, the read pointer
+    // stays in `0..CAP`, and the write pointer is the read pointer plus the length. Then any of
+    // them plus an offset within the buffer is below `2 * CAP`, so it can be wrapped with a
+    // single comparison instead of a division.
+
+    /// Moves the read pointer forward by one. Unless CAP is a power of two, this wraps both
+    /// pointers back once the read pointer reaches the end of the buffer.
+    #[inline]
+    fn advance_readptr(&mut self) {
+        self.readptr = self.readptr.wrapping_add(1);
+        if !CAP.is_power_of_two() && self.readptr == CAP {
+            cold();
+            self.readptr = 0;
+            self.writeptr -= CAP;
+        }
+    }
+
+    /// Maps a pointer, or a pointer plus an offset within the buffer, to an index into the buffer.
+    #[inline]
+    const fn mask(ptr: usize) -> usize {
+        if CAP.is_power_of_two() {
+            crate::mask_and(CAP, ptr)
+        } else if ptr >= CAP {
+            debug_assert!(ptr < 2 * CAP);
+            ptr - CAP
+        } else {
+            ptr
+        }
+    }
+
+    /// The slot in the buffer a read or write pointer refers to.
+    #[inline]
+    fn slot(&mut self, ptr: usize) -> &mut MaybeUninit<T> {
+        let index = Self::mask(ptr);
+        if index >= CAP {
+            // SAFETY: mask always returns an index below CAP, as long as the pointers stay below
+            // 2 * CAP when CAP isn't a power of two.
+            // Telling the compiler lets it see that writes to the buffer don't touch the pointers.
+            unsafe { core::hint::unreachable_unchecked() }
+        }
+        &mut self.buf[index]
+    }
 }
 
+/// Marks the branch calling it as unlikely. This makes the compiler emit a well predicted branch
+/// instead of conditional moves, which the next operation on the buffer would have to wait for.
+#[cold]
+#[inline(never)]
+fn cold() {}
+
 /// Get a const pointer to the buffer
 unsafe fn get_base_ptr<T, const N: usize>(rb: *const ConstGenericRingBuffer<T, N>) -> *const T {
     (*rb).buf.as_ptr().cast()
@@ -199,6 +287,15 @@ unsafe fn get_base_mut_ptr<T, const N: usize>(rb: *mut ConstGenericRingBuffer<T,
     (*rb).buf.as_mut_ptr().cast()
 }
 
+/// Wrap a pointer, or a pointer plus an offset within the buffer, into the buffer
+#[inline]
+unsafe fn mask_ptr<T, const N: usize>(
+    _rb: *const ConstGenericRingBuffer<T, N>,
+    ptr: usize,
+) -> usize {
+    ConstGenericRingBuffer::<T, N>::mask(ptr)
+}
+
 /// Get a reference from the buffer without checking it is initialized
 /// Caller MUST be sure this index is initialized, or undefined behavior will happen
 unsafe fn get_unchecked<'a, T, const N: usize>(
@@ -282,19 +379,16 @@ unsafe impl<T, const CAP: usize> RingBuffer<T> for ConstGenericRingBuffer<T, CAP
         let mut ret = None;
 
         if self.is_full() {
-            let previous_value = mem::replace(
-                &mut self.buf[crate::mask_modulo(CAP, self.readptr)],
-                MaybeUninit::uninit(),
-            );
+            let previous_value = mem::replace(self.slot(self.readptr), MaybeUninit::uninit());
             // make sure we drop whatever is being overwritten
             // SAFETY: the buffer is full, so this must be initialized
             //       : also, index has been masked
             ret = Some(unsafe { previous_value.assume_init() });
-            self.readptr += 1;
+            self.advance_readptr();
         }
-        let index = crate::mask_modulo(CAP, self.writeptr);
-        self.buf[index] = MaybeUninit::new(value);
-        self.writeptr += 1;
+        *self.slot(self.writeptr) = MaybeUninit::new(value);
+        // the buffer holds fewer than CAP items here, so this stays below readptr + CAP
+        self.writeptr = self.writeptr.wrapping_add(1);
 
         ret
     }
@@ -303,9 +397,8 @@ unsafe impl<T, const CAP: usize> RingBuffer<T> for ConstGenericRingBuffer<T, CAP
         if self.is_empty() {
             None
         } else {
-            let index = crate::mask_modulo(CAP, self.readptr);
-            let res = mem::replace(&mut self.buf[index], MaybeUninit::uninit());
-            self.readptr += 1;
+            let res = mem::replace(self.slot(self.readptr), MaybeUninit::uninit());
+            self.advance_readptr();
 
             // Safety: the fact that we got this maybeuninit from the buffer (with mask) means that
             // it's initialized. If it wasn't the is_empty call would have caught it. Values
@@ -321,7 +414,7 @@ unsafe impl<T, const CAP: usize> RingBuffer<T> for ConstGenericRingBuffer<T, CAP
         get_unchecked_mut,
         readptr,
         writeptr,
-        crate::mask_modulo
+        mask_ptr
     );
 
     #[inline]
@@ -368,6 +461,10 @@ impl<T, const CAP: usize> IndexMut<usize> for ConstGenericRingBuffer<T, CAP> {
     }
 }
 
+impl<T, const CAP: usize> RingBufferSlices<T> for ConstGenericRingBuffer<T, CAP> {
+    impl_ring_buffer_slices!(get_base_ptr, readptr, mask_ptr);
+}
+
 impl<T, const CAP: usize> SetLen for ConstGenericRingBuffer<T, CAP> {
     impl_ring_buffer_set_len!(readptr, writeptr);
 }
@@ -400,6 +497,67 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_counters_wrap_at_usize_max() {
+        use crate::tests::check_counters_wrap;
+        use crate::SetLen;
+
+        let mut rb = ConstGenericRingBuffer::<usize, 8>::new();
+        rb.readptr = usize::MAX - 3;
+        rb.writeptr = usize::MAX - 3;
+        check_counters_wrap(&mut rb);
+        unsafe { rb.set_len(8) };
+        assert_eq!(rb.len(), 8);
+
+        // other sizes keep their pointers below 2 * CAP, start them just before that wraps
+        let mut rb = ConstGenericRingBuffer::<usize, 7>::new();
+        rb.readptr = 5;
+        rb.writeptr = 5;
+        check_counters_wrap(&mut rb);
+        assert!(rb.readptr < 7);
+    }
+
+    #[test]
+    fn test_pointers_wrap() {
+        use crate::SetLen;
+        use alloc::vec::Vec;
+
+        fn test<const CAP: usize>() {
+            let mut rb = ConstGenericRingBuffer::<usize, CAP>::new();
+            // go around the pointer range a few times, at every possible length
+            for i in 0..6 * CAP {
+                let _ = rb.enqueue(i);
+                if i % 3 == 0 && rb.len() > 1 {
+                    let _ = rb.dequeue();
+                }
+
+                let expected: Vec<_> = (i + 1 - rb.len()..=i).collect();
+                assert_eq!(rb.to_vec(), expected);
+                assert_eq!(rb.get_signed(-1), Some(&i));
+                assert_eq!(rb.iter().next_back(), Some(&i));
+
+                let mut dst = vec![0; rb.len()];
+                rb.copy_to_slice(0, &mut dst);
+                assert_eq!(dst, expected);
+            }
+
+            rb.fill(1);
+            for _ in 0..CAP + 1 {
+                let _ = rb.dequeue();
+                let _ = rb.enqueue(2);
+                unsafe { rb.set_len(CAP / 2) };
+                unsafe { rb.set_len(CAP) };
+                assert_eq!(rb.len(), CAP);
+            }
+        }
+
+        test::<1>();
+        test::<7>();
+        test::<8>();
+        test::<60>();
+        test::<64>();
+    }
+
     #[test]
     #[should_panic]
     fn test_index_zero_length() {
@@ -460,7 +618,9 @@ mod tests {
 
         let v: &[i32] = &[1, 2, 3];
         assert_eq!(
-            ConstGenericRingBuffer::<i32, 3>::from(v).to_vec(),
+            ConstGenericRingBuffer::<i32, 3>::try_from(v)
+                .unwrap()
+                .to_vec(),
             vec![1, 2, 3]
         );
 
@@ -472,43 +632,56 @@ mod tests {
 
         let v: &mut [i32] = &mut [1, 2, 3];
         assert_eq!(
-            ConstGenericRingBuffer::<i32, 3>::from(v).to_vec(),
+            ConstGenericRingBuffer::<i32, 3>::try_from(v)
+                .unwrap()
+                .to_vec(),
             vec![1, 2, 3]
         );
 
         assert_eq!(
-            ConstGenericRingBuffer::<i32, 3>::from(vec![1, 2, 3]).to_vec(),
+            ConstGenericRingBuffer::<i32, 3>::try_from(vec![1, 2, 3])
+                .unwrap()
+                .to_vec(),
             vec![1, 2, 3]
         );
         assert_eq!(
-            ConstGenericRingBuffer::<i32, 3>::from(
+            ConstGenericRingBuffer::<i32, 3>::try_from(
                 vec![1, 2, 3].into_iter().collect::<VecDeque<_>>()
             )
+            .unwrap()
             .to_vec(),
             vec![1, 2, 3]
         );
         assert_eq!(
-            ConstGenericRingBuffer::<i32, 3>::from(
+            ConstGenericRingBuffer::<i32, 3>::try_from(
                 vec![1, 2, 3].into_iter().collect::<LinkedList<_>>()
             )
+            .unwrap()
             .to_vec(),
             vec![1, 2, 3]
         );
         assert_eq!(
-            ConstGenericRingBuffer::<_, 3>::from("abc".to_string()).to_vec(),
+            ConstGenericRingBuffer::<_, 3>::try_from("abc".to_string())
+                .unwrap()
+                .to_vec(),
             vec!['a', 'b', 'c']
         );
         assert_eq!(
-            ConstGenericRingBuffer::<_, 3>::from("abc").to_vec(),
+            ConstGenericRingBuffer::<_, 3>::try_from("abc")
+                .unwrap()
+                .to_vec(),
             vec!['a', 'b', 'c']
         );
         assert_eq!(
-            ConstGenericRingBuffer::<_, 3>::from(GrowableAllocRingBuffer::from(vec![1, 2, 3]))
+            ConstGenericRingBuffer::<_, 3>::try_from(GrowableAllocRingBuffer::from(vec![1, 2, 3]))
+                .unwrap()
                 .to_vec(),
             vec![1, 2, 3]
         );
         assert_eq!(
-            ConstGenericRingBuffer::<_, 3>::from(AllocRingBuffer::from(vec![1, 2, 3])).to_vec(),
+            ConstGenericRingBuffer::<_, 3>::try_from(AllocRingBuffer::from(vec![1, 2, 3]))
+                .unwrap()
+                .to_vec(),
             vec![1, 2, 3]
         );
     }
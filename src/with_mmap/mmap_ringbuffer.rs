@@ -0,0 +1,714 @@
+use core::marker::PhantomData;
+use core::mem::{align_of, size_of};
+use core::ops::{Index, IndexMut};
+use core::ptr;
+use core::sync::atomic::{fence, Ordering};
+
+use std::fs::{File, OpenOptions};
+use std::io;
+use std::path::Path;
+
+use super::sys;
+use crate::ringbuffer_trait::{
+    RingBuffer, RingBufferIntoIterator, RingBufferIterator, RingBufferMutIterator,
+};
+use crate::{mask_and, Pod, RingBufferSlices};
+
+/// Identifies files written by [`MmapRingBuffer`].
+const MAGIC: [u8; 8] = *b"RINGBUF\0";
+/// Bumped whenever the on-disk layout changes.
+const VERSION: u32 = 1;
+/// Space reserved for each of the two header slots at the start of the file.
+const HEADER_SLOT_SIZE: usize = 64;
+
+/// The header as it is stored in the file.
+///
+/// There are two header slots, which are written alternately. The slot with the highest
+/// `generation` and a valid checksum is the current state, so a header write that got torn
+/// by a crash just falls back to the previous state.
+#[repr(C)]
+#[derive(Debug, Clone, Copy)]
+struct Header {
+    magic: [u8; 8],
+    version: u32,
+    elem_size: u32,
+    capacity: u64,
+    size: u64,
+    generation: u64,
+    readptr: u64,
+    writeptr: u64,
+    checksum: u64,
+}
+
+const _: () = assert!(size_of::<Header>() <= HEADER_SLOT_SIZE);
+
+impl Header {
+    /// FNV-1a over every field except the checksum itself.
+    fn compute_checksum(&self) -> u64 {
+        let mut hash: u64 = 0xcbf2_9ce4_8422_2325;
+        let fields = [
+            u64::from_le_bytes(self.magic),
+            u64::from(self.version),
+            u64::from(self.elem_size),
+            self.capacity,
+            self.size,
+            self.generation,
+            self.readptr,
+            self.writeptr,
+        ];
+        for byte in fields.iter().flat_map(|f| f.to_le_bytes()) {
+            hash ^= u64::from(byte);
+            hash = hash.wrapping_mul(0x0000_0100_0000_01b3);
+        }
+        hash
+    }
+
+    /// Whether this header was completely written and describes a sane buffer of `T`s.
+    fn is_valid<T>(&self) -> bool {
+        self.magic == MAGIC
+            && self.version == VERSION
+            && self.checksum == self.compute_checksum()
+            && u64::try_from(size_of::<T>()) == Ok(u64::from(self.elem_size))
+            && self.capacity != 0
+            && self.size.is_power_of_two()
+            && self.capacity < self.size
+            && self.readptr <= self.writeptr
+            && self.writeptr - self.readptr <= self.capacity
+    }
+}
+
+/// The `MmapRingBuffer` is a `RingBuffer` which stores its elements, together with its read and
+/// write pointers, in a memory mapped file. The contents therefore survive the process, and the
+/// buffer can be reopened later with [`open`](MmapRingBuffer::open). This is useful for things
+/// like a crash-surviving "last N records" log.
+///
+/// Every operation which changes the length of the buffer publishes the new state to the file
+/// only after the elements themselves have been written. The allocated size is always strictly
+/// larger than the capacity, so an enqueue never overwrites an element the published state still
+/// refers to. After an unclean shutdown, reopening the file therefore always yields the state
+/// from before or after the last operation, never something in between. In-place modifications
+/// (through [`get_mut`](RingBuffer::get_mut), [`iter_mut`](RingBuffer::iter_mut) or
+/// [`copy_from_slice`](RingBuffer::copy_from_slice)) are not covered by this.
+///
+/// Writes land in the page cache, so they survive the process crashing. To also survive the
+/// machine losing power, call [`flush`](MmapRingBuffer::flush).
+///
+/// While a buffer is open, the file is exclusively locked so no other `MmapRingBuffer` can open
+/// it at the same time.
+///
+/// This requires the `mmap` feature and is only available on linux.
+///
+/// # Example
+/// ```
+/// use ringbuffer::{MmapRingBuffer, RingBuffer};
+///
+/// let path = std::env::temp_dir().join("ringbuffer-mmap-doctest");
+///
+/// let mut buffer = MmapRingBuffer::<u32>::create(&path, 2).unwrap();
+/// buffer.enqueue(5);
+/// buffer.enqueue(42);
+/// buffer.enqueue(1);
+/// drop(buffer);
+///
+/// // The contents are still there after reopening
+/// let buffer = MmapRingBuffer::<u32>::open(&path).unwrap();
+/// assert_eq!(buffer.to_vec(), vec![42, 1]);
+/// # drop(buffer);
+/// # std::fs::remove_file(&path).unwrap();
+/// ```
+#[derive(Debug)]
+pub struct MmapRingBuffer<T: Pod> {
+    buf: *mut T,
+    map: *mut u8,
+    map_len: usize,
+    // kept open to hold the lock on the file
+    _file: File,
+
+    // the number of element slots in the file. Next power of 2 up from capacity + 1
+    size: usize,
+    // maximum number of elements actually allowed in the ringbuffer.
+    // Always strictly less than the size
+    capacity: usize,
+    // generation of the most recently written header
+    generation: u64,
+
+    readptr: usize,
+    writeptr: usize,
+
+    phantom: PhantomData<T>,
+}
+
+// SAFETY: all methods that require mutable access take &mut, and the mapping is owned
+// exclusively by this buffer (the file is locked).
+unsafe impl<T: Pod + Sync> Sync for MmapRingBuffer<T> {}
+unsafe impl<T: Pod + Send> Send for MmapRingBuffer<T> {}
+
+impl<T: Pod> MmapRingBuffer<T> {
+    /// Creates a new file at `path` holding an empty `MmapRingBuffer` with a certain capacity.
+    /// Truncates the file if it already exists.
+    ///
+    /// # Errors
+    /// Fails when the capacity is zero, when the file can't be created, mapped or locked.
+    pub fn create<P: AsRef<Path>>(path: P, capacity: usize) -> io::Result<Self> {
+        if capacity == 0 {
+            return Err(sys::invalid_input("Capacity must be greater than 0"));
+        }
+        let size = capacity
+            .checked_add(1)
+            .and_then(usize::checked_next_power_of_two)
+            .ok_or_else(|| sys::invalid_input("Capacity is too large"))?;
+        let map_len =
+            Self::file_len(size).ok_or_else(|| sys::invalid_input("Capacity is too large"))?;
+
+        let file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)?;
+        sys::lock_exclusive(&file)?;
+        // only truncate after we got the lock, or we'd clobber a buffer someone else has open
+        file.set_len(0)?;
+        file.set_len(map_len as u64)?;
+
+        let mut rb = Self::map(file, map_len, size, capacity)?;
+        rb.commit();
+        Ok(rb)
+    }
+
+    /// Opens a file previously created with [`create`](MmapRingBuffer::create), restoring the
+    /// last state which was completely written to it.
+    ///
+    /// # Errors
+    /// Fails when the file can't be opened, mapped or locked, or when it doesn't contain a valid
+    /// `MmapRingBuffer` of `T`s.
+    pub fn open<P: AsRef<Path>>(path: P) -> io::Result<Self> {
+        let file = OpenOptions::new().read(true).write(true).open(path)?;
+        sys::lock_exclusive(&file)?;
+
+        let map_len = usize::try_from(file.metadata()?.len()).map_err(sys::invalid_data)?;
+        if map_len < Self::data_offset() {
+            return Err(sys::invalid_data("file is too small to be a ringbuffer"));
+        }
+
+        let mut rb = Self::map(file, map_len, 0, 0)?;
+
+        // Safety: both header slots are within the mapping, which is page aligned
+        let (a, b) = unsafe { (ptr::read(rb.header_slot(0)), ptr::read(rb.header_slot(1))) };
+        let header = match (a.is_valid::<T>(), b.is_valid::<T>()) {
+            (true, true) if a.generation > b.generation => a,
+            (true, true) | (false, true) => b,
+            (true, false) => a,
+            (false, false) => return Err(sys::invalid_data("no valid ringbuffer header found")),
+        };
+
+        let size = usize::try_from(header.size).map_err(sys::invalid_data)?;
+        if Self::file_len(size).map_or(true, |required| required > map_len) {
+            return Err(sys::invalid_data(
+                "file is too small for the capacity in its header",
+            ));
+        }
+        let len = (header.writeptr - header.readptr) as usize;
+        // the pointers only matter modulo the size, so bring them back into range
+        // in case they were written by a platform with a larger usize
+        let readptr = (header.readptr % header.size) as usize;
+
+        rb.size = size;
+        rb.capacity = usize::try_from(header.capacity).map_err(sys::invalid_data)?;
+        rb.generation = header.generation;
+        rb.readptr = readptr;
+        rb.writeptr = readptr + len;
+        Ok(rb)
+    }
+
+    /// Opens the `MmapRingBuffer` at `path` like [`open`](MmapRingBuffer::open), or creates one
+    /// with the given capacity if the file doesn't exist yet or is empty.
+    ///
+    /// # Errors
+    /// Fails like [`open`](MmapRingBuffer::open) and [`create`](MmapRingBuffer::create), and when
+    /// an existing buffer has a different capacity.
+    pub fn open_or_create<P: AsRef<Path>>(path: P, capacity: usize) -> io::Result<Self> {
+        let path = path.as_ref();
+        match path.metadata() {
+            Ok(m) if m.len() > 0 => {
+                let rb = Self::open(path)?;
+                if rb.capacity == capacity {
+                    Ok(rb)
+                } else {
+                    Err(sys::invalid_data(
+                        "existing ringbuffer has a different capacity",
+                    ))
+                }
+            }
+            Ok(_) => Self::create(path, capacity),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Self::create(path, capacity),
+            Err(e) => Err(e),
+        }
+    }
+
+    /// Synchronously writes all changes to the underlying storage device, so they survive
+    /// the machine losing power as well.
+    ///
+    /// # Errors
+    /// Fails when `msync` fails.
+    pub fn flush(&self) -> io::Result<()> {
+        // Safety: map and map_len describe a mapping we own
+        unsafe { sys::sync(self.map, self.map_len) }
+    }
+
+    /// Offset of the element storage from the start of the file
+    fn data_offset() -> usize {
+        let align = align_of::<T>();
+        (2 * HEADER_SLOT_SIZE).div_ceil(align) * align
+    }
+
+    /// The size of a file holding `size` element slots
+    fn file_len(size: usize) -> Option<usize> {
+        size.checked_mul(size_of::<T>())?
+            .checked_add(Self::data_offset())
+    }
+
+    fn map(file: File, map_len: usize, size: usize, capacity: usize) -> io::Result<Self> {
+        let map = sys::map_shared(&file, map_len)?;
+
+        Ok(Self {
+            // Safety: data_offset is within the mapping, and aligned for T since the
+            // mapping is page aligned
+            buf: unsafe { map.add(Self::data_offset()) }.cast(),
+            map,
+            map_len,
+            _file: file,
+            size,
+            capacity,
+            generation: 0,
+            readptr: 0,
+            writeptr: 0,
+            phantom: PhantomData,
+        })
+    }
+
+    /// Pointer to one of the two header slots
+    ///
+    /// # Safety
+    /// `slot` must be 0 or 1
+    unsafe fn header_slot(&self, slot: usize) -> *mut Header {
+        self.map.add(slot * HEADER_SLOT_SIZE).cast()
+    }
+
+    /// Publishes the current read and write pointers to the file.
+    fn commit(&mut self) {
+        // the elements must be in place before the header refers to them
+        fence(Ordering::Release);
+
+        // the pointers wrap around at usize::MAX, and a reader with a larger usize would then see
+        // a negative length. Only their position within the buffer matters, so store just that.
+        let readptr = mask_and(self.size, self.readptr);
+        let writeptr = readptr + self.len();
+
+        self.generation += 1;
+        let mut header = Header {
+            magic: MAGIC,
+            version: VERSION,
+            elem_size: size_of::<T>() as u32,
+            capacity: self.capacity as u64,
+            size: self.size as u64,
+            generation: self.generation,
+            readptr: readptr as u64,
+            writeptr: writeptr as u64,
+            checksum: 0,
+        };
+        header.checksum = header.compute_checksum();
+
+        // Safety: generation % 2 is 0 or 1. We never write the slot holding the previous state.
+        unsafe {
+            ptr::write(self.header_slot((self.generation % 2) as usize), header);
+        }
+    }
+}
+
+impl<T: Pod> Drop for MmapRingBuffer<T> {
+    fn drop(&mut self) {
+        // Safety: map and map_len describe a mapping we own, and nothing refers to it anymore
+        unsafe { sys::unmap(self.map, self.map_len) };
+    }
+}
+
+impl<T: Pod + PartialEq> PartialEq for MmapRingBuffer<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.capacity == other.capacity
+            && self.len() == other.len()
+            && self.iter().zip(other.iter()).all(|(a, b)| a == b)
+    }
+}
+
+impl<T: Pod + Eq> Eq for MmapRingBuffer<T> {}
+
+impl<T: Pod> IntoIterator for MmapRingBuffer<T> {
+    type Item = T;
+    type IntoIter = RingBufferIntoIterator<T, Self>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        RingBufferIntoIterator::new(self)
+    }
+}
+
+#[allow(clippy::into_iter_without_iter)]
+// iter() is implemented on the trait
+impl<'a, T: Pod> IntoIterator for &'a MmapRingBuffer<T> {
+    type Item = &'a T;
+    type IntoIter = RingBufferIterator<'a, T, MmapRingBuffer<T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[allow(clippy::into_iter_without_iter)]
+// iter_mut() is implemented on the trait
+impl<'a, T: Pod> IntoIterator for &'a mut MmapRingBuffer<T> {
+    type Item = &'a mut T;
+    type IntoIter = RingBufferMutIterator<'a, T, MmapRingBuffer<T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T: Pod> Extend<T> for MmapRingBuffer<T> {
+    fn extend<A: IntoIterator<Item = T>>(&mut self, iter: A) {
+        let iter = iter.into_iter();
+
+        for i in iter {
+            let _ = self.enqueue(i);
+        }
+    }
+}
+
+unsafe impl<T: Pod> RingBuffer<T> for MmapRingBuffer<T> {
+    #[inline]
+    unsafe fn ptr_capacity(rb: *const Self) -> usize {
+        (*rb).capacity
+    }
+
+    #[inline]
+    unsafe fn ptr_buffer_size(rb: *const Self) -> usize {
+        (*rb).size
+    }
+
+    impl_ringbuffer!(readptr, writeptr);
+
+    #[inline]
+    fn enqueue(&mut self, value: T) -> Option<T> {
+        let mut ret = None;
+
+        if self.is_full() {
+            // mask with and is allowed here because size is always a power of two
+            let previous_value =
+                unsafe { ptr::read(get_unchecked_mut(self, mask_and(self.size, self.readptr))) };
+
+            ret = Some(previous_value);
+            self.readptr = self.readptr.wrapping_add(1);
+        }
+
+        // size > capacity, so this slot is never part of the published state
+        let index = mask_and(self.size, self.writeptr);
+
+        unsafe {
+            ptr::write(get_unchecked_mut(self, index), value);
+        }
+
+        self.writeptr = self.writeptr.wrapping_add(1);
+        self.commit();
+
+        ret
+    }
+
+    fn dequeue(&mut self) -> Option<T> {
+        if self.is_empty() {
+            None
+        } else {
+            // mask with and is allowed here because size is always a power of two
+            let index = mask_and(self.size, self.readptr);
+            // Safety: the buffer isn't empty so the element at readptr is initialized
+            let res = unsafe { ptr::read(get_unchecked_mut(self, index)) };
+            self.readptr = self.readptr.wrapping_add(1);
+            self.commit();
+
+            Some(res)
+        }
+    }
+
+    impl_ringbuffer_ext!(
+        get_base_ptr,
+        get_base_mut_ptr,
+        get_unchecked,
+        get_unchecked_mut,
+        readptr,
+        writeptr,
+        mask_ptr
+    );
+
+    #[inline]
+    fn fill_with<F: FnMut() -> T>(&mut self, mut f: F) {
+        // publish an empty buffer first, so a crash halfway doesn't expose half-overwritten data
+        self.readptr = 0;
+        self.writeptr = 0;
+        self.commit();
+
+        for i in 0..self.capacity {
+            unsafe { ptr::write(get_unchecked_mut(self, i), f()) };
+        }
+
+        self.writeptr = self.capacity;
+        self.commit();
+    }
+}
+
+impl<T: Pod> RingBufferSlices<T> for MmapRingBuffer<T> {
+    impl_ring_buffer_slices!(get_base_ptr, readptr, mask_ptr);
+}
+
+/// Get a const pointer to the buffer
+unsafe fn get_base_ptr<T: Pod>(rb: *const MmapRingBuffer<T>) -> *const T {
+    (*rb).buf.cast()
+}
+
+/// Get a mut pointer to the buffer
+unsafe fn get_base_mut_ptr<T: Pod>(rb: *mut MmapRingBuffer<T>) -> *mut T {
+    (*rb).buf
+}
+
+/// Wrap a pointer, or a pointer plus an offset within the buffer, into the buffer
+#[inline]
+unsafe fn mask_ptr<T: Pod>(rb: *const MmapRingBuffer<T>, ptr: usize) -> usize {
+    mask_and((*rb).size, ptr)
+}
+
+/// Get a reference from the buffer without checking it is initialized.
+///
+/// Caller must be sure the index is in bounds, or this will panic.
+#[inline]
+unsafe fn get_unchecked<'a, T: Pod>(rb: *const MmapRingBuffer<T>, index: usize) -> &'a T {
+    let p = (*rb).buf.add(index);
+    // Safety: caller makes sure the index is in bounds for the ringbuffer.
+    // Every bit pattern is a valid T, so all in bounds values are initialized
+    &*p
+}
+
+/// Get a mut reference from the buffer without checking it is initialized.
+///
+/// Caller must be sure the index is in bounds, or this will panic.
+#[inline]
+unsafe fn get_unchecked_mut<T: Pod>(rb: *mut MmapRingBuffer<T>, index: usize) -> *mut T {
+    // Safety: caller makes sure the index is in bounds for the ringbuffer.
+    (*rb).buf.add(index)
+}
+
+impl<T: Pod> Index<usize> for MmapRingBuffer<T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get(index).expect("index out of bounds")
+    }
+}
+
+impl<T: Pod> IndexMut<usize> for MmapRingBuffer<T> {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        self.get_mut(index).expect("index out of bounds")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Header, HEADER_SLOT_SIZE};
+    use crate::{mask_and, MmapRingBuffer, RingBuffer};
+    use core::mem::size_of;
+    use std::format;
+    use std::fs;
+    use std::io::{ErrorKind, Read, Seek, SeekFrom, Write};
+    use std::path::PathBuf;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::vec;
+
+    /// A path in the temp dir which is removed again when dropped
+    struct TempPath(PathBuf);
+
+    impl TempPath {
+        fn new() -> Self {
+            static COUNTER: AtomicUsize = AtomicUsize::new(0);
+            Self(std::env::temp_dir().join(format!(
+                "ringbuffer-mmap-test-{}-{}",
+                std::process::id(),
+                COUNTER.fetch_add(1, Ordering::Relaxed)
+            )))
+        }
+    }
+
+    impl Drop for TempPath {
+        fn drop(&mut self) {
+            let _ = fs::remove_file(&self.0);
+        }
+    }
+
+    #[test]
+    fn test_reopen() {
+        let path = TempPath::new();
+
+        let mut rb = MmapRingBuffer::<u64>::create(&path.0, 4).unwrap();
+        rb.extend(1..=6);
+        assert_eq!(rb.dequeue(), Some(3));
+        drop(rb);
+
+        let mut rb = MmapRingBuffer::<u64>::open(&path.0).unwrap();
+        assert_eq!(rb.capacity(), 4);
+        assert_eq!(rb.to_vec(), vec![4, 5, 6]);
+
+        rb.extend(7..=8);
+        assert_eq!(rb.to_vec(), vec![5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn test_counters_wrap_at_usize_max() {
+        let path = TempPath::new();
+
+        let mut rb = MmapRingBuffer::<usize>::create(&path.0, 10).unwrap();
+        rb.readptr = usize::MAX - 3;
+        rb.writeptr = usize::MAX - 3;
+        crate::tests::check_counters_wrap(&mut rb);
+        let contents = rb.to_vec();
+        drop(rb);
+
+        // the header holds the pointers within the buffer, not the wrapped around counters
+        let rb = MmapRingBuffer::<usize>::open(&path.0).unwrap();
+        assert_eq!(rb.to_vec(), contents);
+    }
+
+    #[test]
+    fn test_not_power_of_two() {
+        let path = TempPath::new();
+        let mut rb = MmapRingBuffer::<usize>::create(&path.0, 10).unwrap();
+        const NUM_VALS: usize = 1000;
+
+        for _ in 0..10 {
+            for i in 0..NUM_VALS {
+                let _ = rb.enqueue(i);
+            }
+            assert!(rb.is_full());
+
+            for i in 0..10 {
+                assert_eq!(Some(i + NUM_VALS - rb.capacity()), rb.dequeue());
+            }
+
+            assert!(rb.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_recovers_from_torn_header() {
+        let path = TempPath::new();
+
+        let mut rb = MmapRingBuffer::<u32>::create(&path.0, 4).unwrap();
+        rb.extend([1, 2, 3]);
+        let current_slot = (rb.generation % 2) as usize;
+        drop(rb);
+
+        // pretend we crashed halfway through writing the header for the last enqueue
+        let mut file = fs::OpenOptions::new()
+            .read(true)
+            .write(true)
+            .open(&path.0)
+            .unwrap();
+        let checksum_offset = current_slot * HEADER_SLOT_SIZE + size_of::<Header>() - 1;
+        let mut byte = [0];
+        let _ = file.seek(SeekFrom::Start(checksum_offset as u64)).unwrap();
+        file.read_exact(&mut byte).unwrap();
+        let _ = file.seek(SeekFrom::Start(checksum_offset as u64)).unwrap();
+        file.write_all(&[!byte[0]]).unwrap();
+        drop(file);
+
+        let rb = MmapRingBuffer::<u32>::open(&path.0).unwrap();
+        assert_eq!(rb.to_vec(), vec![1, 2]);
+    }
+
+    #[test]
+    fn test_crash_during_enqueue_when_full() {
+        let path = TempPath::new();
+
+        let mut rb = MmapRingBuffer::<u32>::create(&path.0, 4).unwrap();
+        rb.extend([1, 2, 3, 4]);
+        assert!(rb.is_full());
+
+        // write the element of the next enqueue, but crash before publishing it
+        unsafe { *rb.buf.add(mask_and(rb.size, rb.writeptr)) = 99 };
+        drop(rb);
+
+        let rb = MmapRingBuffer::<u32>::open(&path.0).unwrap();
+        assert_eq!(rb.to_vec(), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn test_fill() {
+        let path = TempPath::new();
+
+        let mut rb = MmapRingBuffer::<u8>::create(&path.0, 3).unwrap();
+        rb.fill(7);
+        drop(rb);
+
+        let rb = MmapRingBuffer::<u8>::open(&path.0).unwrap();
+        assert_eq!(rb.to_vec(), vec![7, 7, 7]);
+    }
+
+    #[test]
+    fn test_open_wrong_type() {
+        let path = TempPath::new();
+
+        drop(MmapRingBuffer::<u32>::create(&path.0, 4).unwrap());
+        let err = MmapRingBuffer::<u64>::open(&path.0).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_open_garbage() {
+        let path = TempPath::new();
+
+        fs::write(&path.0, [0xab; 512]).unwrap();
+        let err = MmapRingBuffer::<u32>::open(&path.0).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_locked() {
+        let path = TempPath::new();
+
+        let rb = MmapRingBuffer::<u32>::create(&path.0, 4).unwrap();
+        assert!(MmapRingBuffer::<u32>::open(&path.0).is_err());
+        assert!(MmapRingBuffer::<u32>::create(&path.0, 4).is_err());
+        drop(rb);
+
+        assert!(MmapRingBuffer::<u32>::open(&path.0).is_ok());
+    }
+
+    #[test]
+    fn test_open_or_create() {
+        let path = TempPath::new();
+
+        let mut rb = MmapRingBuffer::<u32>::open_or_create(&path.0, 4).unwrap();
+        rb.extend([1, 2]);
+        drop(rb);
+
+        let rb = MmapRingBuffer::<u32>::open_or_create(&path.0, 4).unwrap();
+        assert_eq!(rb.to_vec(), vec![1, 2]);
+        drop(rb);
+
+        let err = MmapRingBuffer::<u32>::open_or_create(&path.0, 8).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_zero_capacity() {
+        let path = TempPath::new();
+
+        let err = MmapRingBuffer::<u32>::create(&path.0, 0).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+}
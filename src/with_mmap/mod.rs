@@ -0,0 +1,3 @@
+pub mod mmap_ringbuffer;
+pub mod shared_ringbuffer;
+pub(crate) mod sys;
@@ -0,0 +1,568 @@
+use core::marker::PhantomData;
+use core::mem::{align_of, size_of};
+use core::ptr;
+use core::sync::atomic::{AtomicU32, Ordering};
+
+use std::ffi::CString;
+use std::fs::File;
+use std::io;
+use std::os::unix::io::{AsFd, AsRawFd, BorrowedFd, FromRawFd, OwnedFd};
+
+use super::sys;
+use crate::grant::Counter;
+use crate::{mask_and, CacheAligned, Pod, ReadGrant, WriteGrant};
+
+/// Identifies memory regions initialized by [`SharedRingBuffer`]. Written last, so an attaching
+/// process never sees a half initialized header.
+const MAGIC: u32 = u32::from_le_bytes(*b"RBSH");
+/// Bumped whenever the layout of the shared memory region changes.
+const VERSION: u32 = 1;
+
+/// The header at the start of the shared memory region. The element slots follow it.
+///
+/// The counters are 32 bits wide and wrap around, so processes with a different pointer width
+/// can share a buffer. That's why the size is limited to `2^31` slots.
+#[repr(C)]
+struct SharedHeader {
+    magic: AtomicU32,
+    version: u32,
+    elem_size: u32,
+    capacity: u32,
+    size: u32,
+
+    readptr: CacheAligned<AtomicU32>,
+    writeptr: CacheAligned<AtomicU32>,
+}
+
+/// The `SharedRingBuffer` is a single-producer single-consumer queue of which the header and
+/// element slots live in shared memory, so that it can be used to move data between processes.
+///
+/// A buffer is created with [`create_memfd`](SharedRingBuffer::create_memfd) or
+/// [`create_named`](SharedRingBuffer::create_named). A second process can then attach to it
+/// with [`attach`](SharedRingBuffer::attach) (after inheriting or receiving the file descriptor)
+/// or [`open_named`](SharedRingBuffer::open_named). Processes which are forked after the
+/// buffer was created share it already.
+///
+/// Exactly one process may enqueue and exactly one process may dequeue at the same time.
+/// Since the consumer may be reading the oldest element at any moment, this buffer does not
+/// overwrite elements once it is full like the other ringbuffers do:
+/// [`try_enqueue`](SharedRingBuffer::try_enqueue) hands the value back instead.
+///
+/// This requires the `mmap` feature and is only available on linux.
+///
+/// # Example
+/// ```
+/// use ringbuffer::SharedRingBuffer;
+/// use std::os::fd::AsFd;
+///
+/// let mut producer = SharedRingBuffer::<u32>::create_memfd("frames", 2).unwrap();
+///
+/// // This could also be another process which got the fd passed
+/// let fd = producer.as_fd().try_clone_to_owned().unwrap();
+/// let mut consumer = SharedRingBuffer::<u32>::attach(fd).unwrap();
+///
+/// assert_eq!(producer.try_enqueue(1), Ok(()));
+/// assert_eq!(producer.try_enqueue(2), Ok(()));
+/// // the buffer is full, so we get the value back
+/// assert_eq!(producer.try_enqueue(3), Err(3));
+///
+/// assert_eq!(consumer.dequeue(), Some(1));
+/// assert_eq!(consumer.dequeue(), Some(2));
+/// assert_eq!(consumer.dequeue(), None);
+/// ```
+#[derive(Debug)]
+pub struct SharedRingBuffer<T: Pod> {
+    header: *const SharedHeader,
+    buf: *mut T,
+    map_len: usize,
+    file: File,
+
+    // the number of element slots. Next power of 2 up from the capacity
+    size: usize,
+    // maximum number of elements actually allowed in the ringbuffer.
+    // Always less than or equal than the size
+    capacity: usize,
+
+    phantom: PhantomData<T>,
+}
+
+// SAFETY: all methods that change the buffer take &mut, and the counters are atomic
+unsafe impl<T: Pod + Sync> Sync for SharedRingBuffer<T> {}
+unsafe impl<T: Pod + Send> Send for SharedRingBuffer<T> {}
+
+impl<T: Pod> SharedRingBuffer<T> {
+    /// Creates a `SharedRingBuffer` with a certain capacity in an anonymous memory file
+    /// (see `memfd_create(2)`). `name` is only used for debugging, it doesn't have to be unique.
+    ///
+    /// The file descriptor, available through [`as_fd`](AsFd::as_fd), can be inherited by a
+    /// child process or sent over a unix socket, after which the other process can
+    /// [`attach`](SharedRingBuffer::attach) to it.
+    ///
+    /// # Errors
+    /// Fails when the capacity is zero or larger than `2^31`, when `name` contains a nul byte,
+    /// or when the memory file can't be created or mapped.
+    pub fn create_memfd(name: &str, capacity: usize) -> io::Result<Self> {
+        let name = CString::new(name).map_err(sys::invalid_input)?;
+        // Safety: name is a valid nul terminated string
+        let fd = unsafe { libc::memfd_create(name.as_ptr(), libc::MFD_CLOEXEC) };
+        if fd == -1 {
+            return Err(io::Error::last_os_error());
+        }
+        // Safety: memfd_create just gave us this fd, nobody else owns it
+        Self::init(unsafe { File::from_raw_fd(fd) }, capacity)
+    }
+
+    /// Creates a `SharedRingBuffer` with a certain capacity in a named shared memory object
+    /// (see `shm_open(3)`), which other processes can open with
+    /// [`open_named`](SharedRingBuffer::open_named). `name` should start with a `/`.
+    ///
+    /// The object stays around until it is removed with
+    /// [`unlink_named`](SharedRingBuffer::unlink_named).
+    ///
+    /// # Errors
+    /// Fails when the capacity is zero or larger than `2^31`, when `name` contains a nul byte,
+    /// when an object with this name already exists or when it can't be created or mapped.
+    pub fn create_named(name: &str, capacity: usize) -> io::Result<Self> {
+        let file = shm_open(name, libc::O_RDWR | libc::O_CREAT | libc::O_EXCL)?;
+        Self::init(file, capacity)
+    }
+
+    /// Attaches to a `SharedRingBuffer` created with
+    /// [`create_named`](SharedRingBuffer::create_named) by another process.
+    ///
+    /// # Errors
+    /// Fails when no object with this name exists, when it can't be mapped or when it doesn't
+    /// contain a `SharedRingBuffer` of `T`s.
+    pub fn open_named(name: &str) -> io::Result<Self> {
+        Self::attach(shm_open(name, libc::O_RDWR)?.into())
+    }
+
+    /// Removes the named shared memory object created with
+    /// [`create_named`](SharedRingBuffer::create_named). Processes which already have it
+    /// open can keep using it.
+    ///
+    /// # Errors
+    /// Fails when `name` contains a nul byte or when `shm_unlink` fails.
+    pub fn unlink_named(name: &str) -> io::Result<()> {
+        let name = CString::new(name).map_err(sys::invalid_input)?;
+        // Safety: name is a valid nul terminated string
+        if unsafe { libc::shm_unlink(name.as_ptr()) } == 0 {
+            Ok(())
+        } else {
+            Err(io::Error::last_os_error())
+        }
+    }
+
+    /// Attaches to a `SharedRingBuffer` through a file descriptor referring to its memory,
+    /// for example one inherited from or sent by the process which created it.
+    ///
+    /// # Errors
+    /// Fails when the memory can't be mapped or when it doesn't contain a `SharedRingBuffer`
+    /// of `T`s.
+    pub fn attach(fd: OwnedFd) -> io::Result<Self> {
+        let file = File::from(fd);
+        let map_len = usize::try_from(file.metadata()?.len()).map_err(sys::invalid_data)?;
+        if map_len < Self::data_offset() {
+            return Err(sys::invalid_data("memory is too small to be a ringbuffer"));
+        }
+
+        let mut rb = Self::map(file, map_len, 0, 0)?;
+        // Safety: the mapping is large enough for a header, and page aligned
+        let header = unsafe { &*rb.header };
+
+        // Acquire, so we see the rest of the header as the creator wrote it
+        if header.magic.load(Ordering::Acquire) != MAGIC || header.version != VERSION {
+            return Err(sys::invalid_data("no valid ringbuffer header found"));
+        }
+        if usize::try_from(header.elem_size) != Ok(size_of::<T>()) {
+            return Err(sys::invalid_data("ringbuffer holds a different type"));
+        }
+
+        let size = header.size as usize;
+        let capacity = header.capacity as usize;
+        if !size.is_power_of_two()
+            || capacity == 0
+            || capacity > size
+            || Self::map_len(size).map_or(true, |required| required > map_len)
+        {
+            return Err(sys::invalid_data("ringbuffer header is corrupt"));
+        }
+
+        rb.size = size;
+        rb.capacity = capacity;
+        Ok(rb)
+    }
+
+    /// Adds a value onto the buffer, or hands it back when the buffer is full.
+    ///
+    /// # Errors
+    /// Returns the value when the buffer is full.
+    pub fn try_enqueue(&mut self, value: T) -> Result<(), T> {
+        let header = self.header();
+        // only we write the writeptr
+        let writeptr = header.writeptr.0.load(Ordering::Relaxed);
+        // Acquire, so we don't overwrite an element the consumer is still reading
+        let readptr = header.readptr.0.load(Ordering::Acquire);
+
+        if writeptr.wrapping_sub(readptr) as usize >= self.capacity {
+            return Err(value);
+        }
+
+        // mask with and is allowed here because size is always a power of two
+        let index = mask_and(self.size, writeptr as usize);
+        // Safety: index is masked to be in bounds, and the consumer won't touch this slot
+        // until we publish it below
+        unsafe { ptr::write(self.buf.add(index), value) };
+
+        // Release, so the consumer sees the element once it sees the new writeptr
+        header
+            .writeptr
+            .0
+            .store(writeptr.wrapping_add(1), Ordering::Release);
+        Ok(())
+    }
+
+    /// Dequeues the oldest item off the buffer, and moves this item out.
+    pub fn dequeue(&mut self) -> Option<T> {
+        let header = self.header();
+        // only we write the readptr
+        let readptr = header.readptr.0.load(Ordering::Relaxed);
+        // Acquire, so we see the element the producer wrote before publishing it
+        let writeptr = header.writeptr.0.load(Ordering::Acquire);
+
+        if readptr == writeptr {
+            return None;
+        }
+
+        // mask with and is allowed here because size is always a power of two
+        let index = mask_and(self.size, readptr as usize);
+        // Safety: index is masked to be in bounds, and the producer won't touch this slot
+        // until we publish the new readptr below. Every bit pattern is a valid T.
+        let res = unsafe { ptr::read(self.buf.add(index)) };
+
+        // Release, so the producer only overwrites the slot after we read it
+        header
+            .readptr
+            .0
+            .store(readptr.wrapping_add(1), Ordering::Release);
+        Some(res)
+    }
+
+    /// Reserves `n` free slots at the back of the buffer, which can be written and then
+    /// committed without copying. See [`WriteGrant`].
+    ///
+    /// Since the memory is shared, the slots hold whatever was there before, and all of them
+    /// can be committed without writing them first.
+    ///
+    /// Returns `None` when fewer than `n` slots are free.
+    pub fn reserve(&mut self, n: usize) -> Option<WriteGrant<'_, T>> {
+        // Safety: the header is at the start of the mapping, which lives as long as self
+        let header = unsafe { &*self.header };
+        // only we write the writeptr
+        let writeptr = header.writeptr.0.load(Ordering::Relaxed);
+        // Acquire, so we don't overwrite elements the consumer is still reading
+        let readptr = header.readptr.0.load(Ordering::Acquire);
+
+        let len = writeptr.wrapping_sub(readptr) as usize;
+        if n > self.capacity.saturating_sub(len) {
+            return None;
+        }
+
+        // mask with and is allowed here because size is always a power of two
+        let start = mask_and(self.size, writeptr as usize);
+        // Safety: the n slots from the writeptr are free, and the consumer won't touch them until
+        // the grant publishes them. Every bit pattern in them is a valid T.
+        Some(unsafe {
+            WriteGrant::new(
+                self.buf.cast(),
+                self.size,
+                start,
+                n,
+                n,
+                Counter::Shared {
+                    counter: &header.writeptr.0,
+                    value: writeptr,
+                },
+            )
+        })
+    }
+
+    /// Grants access to the first `n` elements of the buffer, which can be read and then
+    /// released without copying. See [`ReadGrant`].
+    ///
+    /// Returns `None` when the buffer holds fewer than `n` elements.
+    pub fn read_grant(&mut self, n: usize) -> Option<ReadGrant<'_, T>> {
+        // Safety: the header is at the start of the mapping, which lives as long as self
+        let header = unsafe { &*self.header };
+        // only we write the readptr
+        let readptr = header.readptr.0.load(Ordering::Relaxed);
+        // Acquire, so we see the elements the producer wrote before publishing them
+        let writeptr = header.writeptr.0.load(Ordering::Acquire);
+
+        let len = writeptr.wrapping_sub(readptr) as usize;
+        if n > len.min(self.capacity) {
+            return None;
+        }
+
+        // mask with and is allowed here because size is always a power of two
+        let start = mask_and(self.size, readptr as usize);
+        // Safety: the n slots from the readptr hold elements, and the producer won't touch them
+        // until the grant releases them
+        Some(unsafe {
+            ReadGrant::new(
+                self.buf,
+                self.size,
+                start,
+                n,
+                Counter::Shared {
+                    counter: &header.readptr.0,
+                    value: readptr,
+                },
+            )
+        })
+    }
+
+    /// Returns the number of elements in the buffer. Since the other side may be enqueueing or
+    /// dequeueing concurrently, this is only a snapshot.
+    #[must_use]
+    pub fn len(&self) -> usize {
+        let header = self.header();
+        let readptr = header.readptr.0.load(Ordering::Acquire);
+        let writeptr = header.writeptr.0.load(Ordering::Acquire);
+        (writeptr.wrapping_sub(readptr) as usize).min(self.capacity)
+    }
+
+    /// Returns true if the buffer is entirely empty.
+    #[must_use]
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns true when the length of the buffer equals the capacity.
+    #[must_use]
+    pub fn is_full(&self) -> bool {
+        self.len() == self.capacity
+    }
+
+    /// Returns the capacity of the buffer.
+    #[must_use]
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    fn header(&self) -> &SharedHeader {
+        // Safety: the header is at the start of the mapping, which lives as long as self
+        unsafe { &*self.header }
+    }
+
+    /// Offset of the element storage from the start of the memory region
+    fn data_offset() -> usize {
+        let align = align_of::<T>();
+        size_of::<SharedHeader>().div_ceil(align) * align
+    }
+
+    /// The size of a memory region holding `size` element slots
+    fn map_len(size: usize) -> Option<usize> {
+        size.checked_mul(size_of::<T>())?
+            .checked_add(Self::data_offset())
+    }
+
+    fn init(file: File, capacity: usize) -> io::Result<Self> {
+        if capacity == 0 {
+            return Err(sys::invalid_input("Capacity must be greater than 0"));
+        }
+        let size = capacity
+            .checked_next_power_of_two()
+            .filter(|&size| size <= 1 << 31)
+            .ok_or_else(|| sys::invalid_input("Capacity is too large"))?;
+        let map_len =
+            Self::map_len(size).ok_or_else(|| sys::invalid_input("Capacity is too large"))?;
+        file.set_len(map_len as u64)?;
+
+        let rb = Self::map(file, map_len, size, capacity)?;
+        let header = rb.header.cast_mut();
+        // Safety: nobody can have attached yet since the magic isn't there, so we can write
+        // the header without atomics
+        unsafe {
+            (*header).version = VERSION;
+            (*header).elem_size = size_of::<T>() as u32;
+            (*header).capacity = capacity as u32;
+            (*header).size = size as u32;
+            (*header).readptr.0 = AtomicU32::new(0);
+            (*header).writeptr.0 = AtomicU32::new(0);
+        }
+        // Release, so anyone who sees the magic sees the rest of the header
+        rb.header().magic.store(MAGIC, Ordering::Release);
+        Ok(rb)
+    }
+
+    fn map(file: File, map_len: usize, size: usize, capacity: usize) -> io::Result<Self> {
+        let map = sys::map_shared(&file, map_len)?;
+
+        Ok(Self {
+            header: map.cast(),
+            // Safety: data_offset is within the mapping, and aligned for T since the
+            // mapping is page aligned
+            buf: unsafe { map.add(Self::data_offset()) }.cast(),
+            map_len,
+            file,
+            size,
+            capacity,
+            phantom: PhantomData,
+        })
+    }
+}
+
+fn shm_open(name: &str, flags: libc::c_int) -> io::Result<File> {
+    let name = CString::new(name).map_err(sys::invalid_input)?;
+    // Safety: name is a valid nul terminated string
+    let fd = unsafe { libc::shm_open(name.as_ptr(), flags | libc::O_CLOEXEC, 0o600) };
+    if fd == -1 {
+        Err(io::Error::last_os_error())
+    } else {
+        // Safety: shm_open just gave us this fd, nobody else owns it
+        Ok(unsafe { File::from_raw_fd(fd) })
+    }
+}
+
+impl<T: Pod> AsFd for SharedRingBuffer<T> {
+    fn as_fd(&self) -> BorrowedFd<'_> {
+        self.file.as_fd()
+    }
+}
+
+impl<T: Pod> AsRawFd for SharedRingBuffer<T> {
+    fn as_raw_fd(&self) -> libc::c_int {
+        self.file.as_raw_fd()
+    }
+}
+
+impl<T: Pod> Drop for SharedRingBuffer<T> {
+    fn drop(&mut self) {
+        // Safety: header and map_len describe a mapping we own, and nothing refers to it anymore
+        unsafe { sys::unmap(self.header.cast_mut().cast(), self.map_len) };
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::SharedRingBuffer;
+    use std::format;
+    use std::io::ErrorKind;
+    use std::os::unix::io::AsFd;
+
+    fn attach_second<T: crate::Pod>(rb: &SharedRingBuffer<T>) -> SharedRingBuffer<T> {
+        SharedRingBuffer::attach(rb.as_fd().try_clone_to_owned().unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_grants() {
+        let mut producer = SharedRingBuffer::<u32>::create_memfd("test", 4).unwrap();
+        let mut consumer = attach_second(&producer);
+
+        assert!(producer.reserve(5).is_none());
+        for round in 0..5 {
+            let mut grant = producer.reserve(3).unwrap();
+            assert_eq!(grant.len(), 3);
+            let (first, second) = grant.as_mut_slices();
+            for (i, v) in first.iter_mut().chain(second).enumerate() {
+                *v = round * 10 + i as u32;
+            }
+            grant.commit(3);
+            assert!(producer.reserve(2).is_none());
+
+            assert!(consumer.read_grant(4).is_none());
+            let grant = consumer.read_grant(3).unwrap();
+            let (first, second) = grant.as_slices();
+            let read: std::vec::Vec<_> = first.iter().chain(second).copied().collect();
+            assert_eq!(read, [round * 10, round * 10 + 1, round * 10 + 2]);
+            grant.release(3);
+            assert!(consumer.is_empty());
+        }
+    }
+
+    #[test]
+    fn test_enqueue_dequeue() {
+        let mut producer = SharedRingBuffer::<u64>::create_memfd("test", 4).unwrap();
+        let mut consumer = attach_second(&producer);
+
+        assert!(consumer.is_empty());
+        for i in 0..4 {
+            assert_eq!(producer.try_enqueue(i), Ok(()));
+        }
+        assert!(consumer.is_full());
+        assert_eq!(producer.try_enqueue(4), Err(4));
+
+        assert_eq!(consumer.dequeue(), Some(0));
+        assert_eq!(producer.try_enqueue(4), Ok(()));
+        assert_eq!(consumer.len(), 4);
+
+        for i in 1..5 {
+            assert_eq!(consumer.dequeue(), Some(i));
+        }
+        assert_eq!(consumer.dequeue(), None);
+    }
+
+    #[test]
+    fn test_not_power_of_two() {
+        let mut rb = SharedRingBuffer::<usize>::create_memfd("test", 10).unwrap();
+        const NUM_VALS: usize = 1000;
+
+        for i in 0..NUM_VALS {
+            if rb.try_enqueue(i).is_err() {
+                assert_eq!(rb.len(), 10);
+                assert_eq!(rb.dequeue(), Some(i - 10));
+                assert_eq!(rb.try_enqueue(i), Ok(()));
+            }
+        }
+        for i in NUM_VALS - 10..NUM_VALS {
+            assert_eq!(rb.dequeue(), Some(i));
+        }
+        assert!(rb.is_empty());
+    }
+
+    #[test]
+    fn test_attach_wrong_type() {
+        let rb = SharedRingBuffer::<u32>::create_memfd("test", 4).unwrap();
+        let fd = rb.as_fd().try_clone_to_owned().unwrap();
+        let err = SharedRingBuffer::<u64>::attach(fd).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn test_invalid_capacity() {
+        let err = SharedRingBuffer::<u8>::create_memfd("test", 0).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        let err = SharedRingBuffer::<u8>::create_memfd("test", (1 << 31) + 1).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+        // next_power_of_two would overflow
+        let err = SharedRingBuffer::<u8>::create_memfd("test", usize::MAX).unwrap_err();
+        assert_eq!(err.kind(), ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn test_named() {
+        let name = format!("/ringbuffer-test-{}", std::process::id());
+
+        let mut producer = SharedRingBuffer::<u16>::create_named(&name, 2).unwrap();
+        assert_eq!(
+            SharedRingBuffer::<u16>::create_named(&name, 2)
+                .unwrap_err()
+                .kind(),
+            ErrorKind::AlreadyExists
+        );
+
+        let mut consumer = SharedRingBuffer::<u16>::open_named(&name).unwrap();
+        SharedRingBuffer::<u16>::unlink_named(&name).unwrap();
+
+        assert_eq!(producer.try_enqueue(7), Ok(()));
+        assert_eq!(consumer.dequeue(), Some(7));
+
+        assert_eq!(
+            SharedRingBuffer::<u16>::open_named(&name)
+                .unwrap_err()
+                .kind(),
+            ErrorKind::NotFound
+        );
+    }
+}
@@ -0,0 +1,130 @@
+use core::ptr;
+use std::boxed::Box;
+use std::fs::File;
+use std::io;
+use std::os::unix::io::{AsRawFd, FromRawFd};
+
+/// Map `len` bytes of `file` into memory, shared with every other mapping of the same file.
+pub(crate) fn map_shared(file: &File, len: usize) -> io::Result<*mut u8> {
+    // Safety: we pass a valid fd and let the kernel pick the address
+    let map = unsafe {
+        libc::mmap(
+            ptr::null_mut(),
+            len,
+            libc::PROT_READ | libc::PROT_WRITE,
+            libc::MAP_SHARED,
+            file.as_raw_fd(),
+            0,
+        )
+    };
+    if map == libc::MAP_FAILED {
+        Err(io::Error::last_os_error())
+    } else {
+        Ok(map.cast())
+    }
+}
+
+/// Undo a [`map_shared`].
+///
+/// # Safety
+/// `map` and `len` must describe a mapping returned by [`map_shared`], which nothing refers to
+/// anymore.
+pub(crate) unsafe fn unmap(map: *mut u8, len: usize) {
+    let _ = libc::munmap(map.cast(), len);
+}
+
+/// Synchronously write a mapping back to the underlying storage device.
+///
+/// # Safety
+/// `map` and `len` must describe a mapping returned by [`map_shared`].
+pub(crate) unsafe fn sync(map: *mut u8, len: usize) -> io::Result<()> {
+    if libc::msync(map.cast(), len, libc::MS_SYNC) == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+/// Take an exclusive lock on the file, failing instead of blocking when someone else holds it.
+pub(crate) fn lock_exclusive(file: &File) -> io::Result<()> {
+    // Safety: the fd is valid for the lifetime of file
+    if unsafe { libc::flock(file.as_raw_fd(), libc::LOCK_EX | libc::LOCK_NB) } == 0 {
+        Ok(())
+    } else {
+        Err(io::Error::last_os_error())
+    }
+}
+
+pub(crate) fn invalid_data<E: Into<Box<dyn std::error::Error + Send + Sync>>>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, e)
+}
+
+pub(crate) fn invalid_input<E: Into<Box<dyn std::error::Error + Send + Sync>>>(e: E) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidInput, e)
+}
+
+/// The size of a page of virtual memory.
+pub(crate) fn page_size() -> usize {
+    // Safety: sysconf has no preconditions
+    let size = unsafe { libc::sysconf(libc::_SC_PAGESIZE) };
+    usize::try_from(size).unwrap_or(4096)
+}
+
+/// Map `len` bytes of fresh, zeroed memory twice, back to back, so that the byte at `i` and the
+/// byte at `i + len` are the same. `len` must be a multiple of the page size.
+///
+/// Returns the start of the `2 * len` byte region, to be unmapped with [`unmap`].
+pub(crate) fn map_mirrored(len: usize) -> io::Result<*mut u8> {
+    let name = b"ringbuffer-mirror\0";
+    // Safety: name is a valid nul terminated string
+    let fd = unsafe { libc::memfd_create(name.as_ptr().cast(), libc::MFD_CLOEXEC) };
+    if fd == -1 {
+        return Err(io::Error::last_os_error());
+    }
+    // Safety: memfd_create just gave us this fd, nobody else owns it.
+    // The mappings keep the memory alive after this is closed.
+    let file = unsafe { File::from_raw_fd(fd) };
+    file.set_len(len as u64)?;
+
+    let total = len
+        .checked_mul(2)
+        .ok_or_else(|| invalid_input("mirrored region is too large"))?;
+    // reserve the address space for both halves first, so nothing else can end up in between
+    // Safety: we let the kernel pick the address
+    let base = unsafe {
+        libc::mmap(
+            ptr::null_mut(),
+            total,
+            libc::PROT_NONE,
+            libc::MAP_PRIVATE | libc::MAP_ANONYMOUS | libc::MAP_NORESERVE,
+            -1,
+            0,
+        )
+    };
+    if base == libc::MAP_FAILED {
+        return Err(io::Error::last_os_error());
+    }
+    let base: *mut u8 = base.cast();
+
+    for half in [base, base.wrapping_add(len)] {
+        // Safety: both halves lie within the reservation we own, which MAP_FIXED replaces
+        let map = unsafe {
+            libc::mmap(
+                half.cast(),
+                len,
+                libc::PROT_READ | libc::PROT_WRITE,
+                libc::MAP_SHARED | libc::MAP_FIXED,
+                file.as_raw_fd(),
+                0,
+            )
+        };
+        if map == libc::MAP_FAILED {
+            let err = io::Error::last_os_error();
+            // Safety: base and total describe the reservation, nothing refers to it
+            unsafe { unmap(base, total) };
+            return Err(err);
+        }
+    }
+
+    Ok(base)
+}
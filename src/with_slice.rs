@@ -0,0 +1,410 @@
+use crate::ringbuffer_trait::{RingBufferIntoIterator, RingBufferIterator, RingBufferMutIterator};
+use crate::{
+    advance_readptr, impl_ring_buffer_set_len, mask_any, RingBuffer, RingBufferSlices, SetLen,
+};
+use core::mem::{self, MaybeUninit};
+use core::ops::{Index, IndexMut};
+
+/// The `SliceRingBuffer` struct is a `RingBuffer` implementation which stores its elements in a
+/// slice borrowed from the caller, for when the memory has to come from somewhere specific, like
+/// a DMA-capable region or an arena.
+///
+/// The slice can have any non-zero length, which is also the capacity of the ringbuffer.
+/// Like [`ConstGenericRingBuffer`](crate::ConstGenericRingBuffer), lengths that are not a power
+/// of two work, but are slower to index.
+///
+/// The elements left in the buffer are dropped when it is dropped, after which the slice is
+/// uninitialized again.
+///
+/// # Example
+/// ```
+/// use core::mem::MaybeUninit;
+/// use ringbuffer::{RingBuffer, SliceRingBuffer};
+///
+/// let mut storage = [const { MaybeUninit::uninit() }; 2];
+/// let mut buffer = SliceRingBuffer::new(&mut storage);
+///
+/// buffer.enqueue(5);
+/// buffer.enqueue(42);
+/// assert!(buffer.is_full());
+///
+/// // Because capacity is reached the next enqueue will be the first item of the buffer.
+/// buffer.enqueue(1);
+/// assert_eq!(buffer.to_vec(), vec![42, 1]);
+/// ```
+#[derive(Debug)]
+pub struct SliceRingBuffer<'a, T> {
+    buf: &'a mut [MaybeUninit<T>],
+    readptr: usize,
+    writeptr: usize,
+}
+
+impl<'a, T> SliceRingBuffer<'a, T> {
+    impl_grants!(get_base_mut_ptr, readptr, writeptr, mask_ptr);
+
+    /// Creates an empty ringbuffer which stores its elements in `buf`.
+    ///
+    /// # Panics
+    /// Panics if `buf` is empty.
+    #[must_use]
+    pub fn new(buf: &'a mut [MaybeUninit<T>]) -> Self {
+        assert!(!buf.is_empty(), "Capacity must be greater than zero");
+
+        Self {
+            buf,
+            readptr: 0,
+            writeptr: 0,
+        }
+    }
+
+    /// Drops the elements in the buffer and gives the, now uninitialized, storage back.
+    #[must_use]
+    pub fn into_inner(mut self) -> &'a mut [MaybeUninit<T>] {
+        self.clear();
+        mem::take(&mut self.buf)
+    }
+}
+
+impl<T> Drop for SliceRingBuffer<'_, T> {
+    fn drop(&mut self) {
+        self.drain().for_each(drop);
+    }
+}
+
+// We need to manually implement PartialEq because MaybeUninit isn't PartialEq
+impl<T: PartialEq> PartialEq for SliceRingBuffer<'_, T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len() == other.len() && self.iter().zip(other.iter()).all(|(a, b)| a == b)
+    }
+}
+
+impl<T: Eq> Eq for SliceRingBuffer<'_, T> {}
+
+/// Get a const pointer to the buffer
+unsafe fn get_base_ptr<T>(rb: *const SliceRingBuffer<'_, T>) -> *const T {
+    (*rb).buf.as_ptr().cast()
+}
+
+/// Get a mut pointer to the buffer
+unsafe fn get_base_mut_ptr<T>(rb: *mut SliceRingBuffer<'_, T>) -> *mut T {
+    (*rb).buf.as_mut_ptr().cast()
+}
+
+/// Wrap a pointer, or a pointer plus an offset within the buffer, into the buffer
+#[inline]
+unsafe fn mask_ptr<T>(rb: *const SliceRingBuffer<'_, T>, ptr: usize) -> usize {
+    let rb = &*rb;
+    mask_any(rb.buf.len(), ptr)
+}
+
+/// Get a reference from the buffer without checking it is initialized
+/// Caller MUST be sure this index is initialized, or undefined behavior will happen
+unsafe fn get_unchecked<'a, T>(rb: *const SliceRingBuffer<'_, T>, index: usize) -> &'a T {
+    &*(*rb).buf[index].as_ptr()
+}
+
+/// Get a mutable reference from the buffer without checking it is initialized
+/// Caller MUST be sure this index is initialized, or undefined behavior will happen
+unsafe fn get_unchecked_mut<T>(rb: *mut SliceRingBuffer<'_, T>, index: usize) -> *mut T {
+    (*rb).buf[index].as_mut_ptr()
+}
+
+impl<'a, T> IntoIterator for SliceRingBuffer<'a, T> {
+    type Item = T;
+    type IntoIter = RingBufferIntoIterator<T, Self>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        RingBufferIntoIterator::new(self)
+    }
+}
+
+#[allow(clippy::into_iter_without_iter)]
+// iter() is implemented on the trait
+impl<'a, 'b, T> IntoIterator for &'b SliceRingBuffer<'a, T> {
+    type Item = &'b T;
+    type IntoIter = RingBufferIterator<'b, T, SliceRingBuffer<'a, T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+#[allow(clippy::into_iter_without_iter)]
+// iter_mut() is implemented on the trait
+impl<'a, 'b, T> IntoIterator for &'b mut SliceRingBuffer<'a, T> {
+    type Item = &'b mut T;
+    type IntoIter = RingBufferMutIterator<'b, T, SliceRingBuffer<'a, T>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl<T> Extend<T> for SliceRingBuffer<'_, T> {
+    fn extend<A: IntoIterator<Item = T>>(&mut self, iter: A) {
+        for i in iter {
+            let _ = self.enqueue(i);
+        }
+    }
+}
+
+unsafe impl<T> RingBuffer<T> for SliceRingBuffer<'_, T> {
+    #[inline]
+    unsafe fn ptr_capacity(rb: *const Self) -> usize {
+        let rb = &*rb;
+        rb.buf.len()
+    }
+
+    #[inline]
+    unsafe fn ptr_buffer_size(rb: *const Self) -> usize {
+        let rb = &*rb;
+        rb.buf.len()
+    }
+
+    impl_ringbuffer!(readptr, writeptr);
+
+    #[inline]
+    fn enqueue(&mut self, value: T) -> Option<T> {
+        let mut ret = None;
+        let size = self.buf.len();
+
+        if self.is_full() {
+            let previous_value = mem::replace(
+                &mut self.buf[mask_any(size, self.readptr)],
+                MaybeUninit::uninit(),
+            );
+            // make sure we drop whatever is being overwritten
+            // SAFETY: the buffer is full, so this must be initialized
+            //       : also, index has been masked
+            ret = Some(unsafe { previous_value.assume_init() });
+            advance_readptr(size, 1, &mut self.readptr, &mut self.writeptr);
+        }
+        self.buf[mask_any(size, self.writeptr)] = MaybeUninit::new(value);
+        self.writeptr = self.writeptr.wrapping_add(1);
+
+        ret
+    }
+
+    fn dequeue(&mut self) -> Option<T> {
+        if self.is_empty() {
+            None
+        } else {
+            let size = self.buf.len();
+            let index = mask_any(size, self.readptr);
+            let res = mem::replace(&mut self.buf[index], MaybeUninit::uninit());
+            advance_readptr(size, 1, &mut self.readptr, &mut self.writeptr);
+
+            // Safety: the fact that we got this maybeuninit from the buffer (with mask) means that
+            // it's initialized. If it wasn't the is_empty call would have caught it. Values
+            // are always initialized when inserted so this is safe.
+            unsafe { Some(res.assume_init()) }
+        }
+    }
+
+    impl_ringbuffer_ext!(
+        get_base_ptr,
+        get_base_mut_ptr,
+        get_unchecked,
+        get_unchecked_mut,
+        readptr,
+        writeptr,
+        mask_ptr
+    );
+
+    #[inline]
+    fn fill_with<F: FnMut() -> T>(&mut self, mut f: F) {
+        self.clear();
+        for slot in self.buf.iter_mut() {
+            *slot = MaybeUninit::new(f());
+            // count every element as soon as it is written, so a panicking f doesn't leak
+            self.writeptr += 1;
+        }
+    }
+}
+
+impl<T> Index<usize> for SliceRingBuffer<'_, T> {
+    type Output = T;
+
+    fn index(&self, index: usize) -> &Self::Output {
+        self.get(index).expect("index out of bounds")
+    }
+}
+
+impl<T> IndexMut<usize> for SliceRingBuffer<'_, T> {
+    fn index_mut(&mut self, index: usize) -> &mut Self::Output {
+        self.get_mut(index).expect("index out of bounds")
+    }
+}
+
+impl<T> RingBufferSlices<T> for SliceRingBuffer<'_, T> {
+    impl_ring_buffer_slices!(get_base_ptr, readptr, mask_ptr);
+}
+
+impl<T> SetLen for SliceRingBuffer<'_, T> {
+    impl_ring_buffer_set_len!(readptr, writeptr);
+}
+
+#[cfg(feature = "alloc")]
+impl<T> From<SliceRingBuffer<'_, T>> for crate::AllocRingBuffer<T> {
+    /// Keeps the capacity of `value`.
+    fn from(mut value: SliceRingBuffer<'_, T>) -> Self {
+        let mut res = crate::AllocRingBuffer::new(value.capacity());
+        res.extend(value.drain());
+        res
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl<T> From<SliceRingBuffer<'_, T>> for crate::GrowableAllocRingBuffer<T> {
+    /// Keeps the capacity of `value`.
+    fn from(mut value: SliceRingBuffer<'_, T>) -> Self {
+        let mut res = crate::GrowableAllocRingBuffer::with_capacity(value.capacity());
+        res.extend(value.drain());
+        res
+    }
+}
+
+impl<'a, T, const CAP: usize> TryFrom<SliceRingBuffer<'a, T>>
+    for crate::ConstGenericRingBuffer<T, CAP>
+{
+    type Error = crate::CapacityError<SliceRingBuffer<'a, T>>;
+
+    fn try_from(value: SliceRingBuffer<'a, T>) -> Result<Self, Self::Error> {
+        let len = value.len();
+        Ok(crate::CapacityError::check(value, len, CAP)?
+            .drain()
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::SliceRingBuffer;
+    use crate::{
+        AllocRingBuffer, ConstGenericRingBuffer, GrowableAllocRingBuffer, RingBuffer, SetLen,
+    };
+    use alloc::vec;
+    use alloc::vec::Vec;
+    use core::cell::Cell;
+    use core::mem::MaybeUninit;
+
+    fn storage<T, const N: usize>() -> [MaybeUninit<T>; N] {
+        [const { MaybeUninit::uninit() }; N]
+    }
+
+    #[test]
+    fn test_power_of_two_and_not() {
+        let mut s4 = storage::<usize, 4>();
+        let mut s5 = storage::<usize, 5>();
+
+        for buf in [&mut s4[..], &mut s5[..]] {
+            let size = buf.len();
+            let mut rb = SliceRingBuffer::new(buf);
+            assert_eq!(rb.capacity(), size);
+
+            for i in 0..1000 {
+                let _ = rb.enqueue(i);
+                let expected: Vec<_> = (i.saturating_sub(size - 1)..=i).collect();
+                assert_eq!(rb.to_vec(), expected);
+                assert_eq!(rb[0], expected[0]);
+                assert_eq!(rb.get_signed(-1), Some(&i));
+            }
+
+            let mut dst = vec![0; size];
+            rb.copy_to_slice(0, &mut dst);
+            assert_eq!(dst, rb.to_vec());
+        }
+    }
+
+    #[test]
+    #[should_panic]
+    fn test_empty_slice() {
+        let _ = SliceRingBuffer::<i32>::new(&mut []);
+    }
+
+    #[test]
+    fn test_drops() {
+        struct Counted<'c>(&'c Cell<usize>);
+        impl Drop for Counted<'_> {
+            fn drop(&mut self) {
+                self.0.set(self.0.get() + 1);
+            }
+        }
+
+        let drops = Cell::new(0);
+        let mut buf = storage::<Counted<'_>, 3>();
+        let mut rb = SliceRingBuffer::new(&mut buf);
+        for _ in 0..5 {
+            // the overwritten elements are handed back and dropped here
+            let _ = rb.enqueue(Counted(&drops));
+        }
+        assert_eq!(drops.get(), 2);
+
+        let buf = rb.into_inner();
+        assert_eq!(drops.get(), 5);
+        assert_eq!(buf.len(), 3);
+
+        // the storage can be reused
+        let mut rb = SliceRingBuffer::new(buf);
+        rb.fill_with(|| Counted(&drops));
+        assert_eq!(drops.get(), 5);
+        drop(rb);
+        assert_eq!(drops.get(), 8);
+    }
+
+    #[test]
+    fn test_counters_wrap_at_usize_max() {
+        let mut storage = [MaybeUninit::uninit(); 8];
+        let mut rb = SliceRingBuffer::new(&mut storage);
+        rb.readptr = usize::MAX - 3;
+        rb.writeptr = usize::MAX - 3;
+        crate::tests::check_counters_wrap(&mut rb);
+        unsafe { rb.set_len(8) };
+        assert_eq!(rb.len(), 8);
+
+        // other sizes keep their pointers within twice the size
+        let mut storage = [MaybeUninit::uninit(); 7];
+        let mut rb = SliceRingBuffer::new(&mut storage);
+        rb.readptr = 5;
+        rb.writeptr = 5;
+        crate::tests::check_counters_wrap(&mut rb);
+        assert!(rb.readptr < 7);
+    }
+
+    #[test]
+    fn test_set_len() {
+        let mut buf = storage::<i32, 4>();
+        let mut rb = SliceRingBuffer::new(&mut buf);
+        rb.fill(7);
+        unsafe { rb.set_len(2) };
+        assert_eq!(rb.to_vec(), vec![7, 7]);
+        unsafe { rb.set_len(4) };
+        assert_eq!(rb.to_vec(), vec![7, 7, 7, 7]);
+    }
+
+    #[test]
+    fn test_into_owned() {
+        let mut buf = storage::<i32, 5>();
+        let mut rb = SliceRingBuffer::new(&mut buf);
+        rb.extend([1, 2, 3]);
+        let arb = AllocRingBuffer::from(rb);
+        assert_eq!(arb.capacity(), 5);
+        assert_eq!(arb.to_vec(), vec![1, 2, 3]);
+
+        let mut rb = SliceRingBuffer::new(&mut buf);
+        rb.extend([1, 2, 3]);
+        let garb = GrowableAllocRingBuffer::from(rb);
+        assert!(garb.capacity() >= 5);
+        assert_eq!(garb.to_vec(), vec![1, 2, 3]);
+
+        let mut rb = SliceRingBuffer::new(&mut buf);
+        rb.extend([1, 2, 3]);
+        let cgrb = ConstGenericRingBuffer::<_, 3>::try_from(rb).unwrap();
+        assert_eq!(cgrb.to_vec(), vec![1, 2, 3]);
+
+        let mut rb = SliceRingBuffer::new(&mut buf);
+        rb.extend([1, 2, 3]);
+        let err = ConstGenericRingBuffer::<_, 2>::try_from(rb).unwrap_err();
+        assert_eq!(err.into_inner().to_vec(), vec![1, 2, 3]);
+    }
+}
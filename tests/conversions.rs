@@ -32,6 +32,31 @@ macro_rules! convert_tests {
     };
 }
 
+macro_rules! try_convert_test {
+    ($name: ident: $from: expr => $to: ty) => {
+        #[test]
+        fn $name() {
+            let a = $from;
+
+            let mut b: $to = a.try_into().unwrap();
+            assert_eq!(b.to_vec(), vec!['1', '2']);
+            b.enqueue('3');
+            assert_eq!(b, b);
+        }
+    };
+}
+
+macro_rules! try_convert_tests {
+    (
+        [$($name: ident: $from: expr),* $(,)?]
+        => $to: ty
+    ) => {
+        $(
+            try_convert_test!($name: $from => $to);
+        )*
+    };
+}
+
 convert_tests!(
     [
         alloc_from_vec: vec!['1', '2'],
@@ -66,20 +91,75 @@ convert_tests!(
 
 convert_tests!(
     [
-        const_from_vec: vec!['1', '2'],
-        const_from_ll: {let mut l = LinkedList::new(); l.push_back('1'); l.push_back('2'); l},
-        const_from_vd: {let mut l = VecDeque::new(); l.push_back('1'); l.push_back('2'); l},
-        const_from_str: "12".to_string(),
-        const_from_str_slice: "12",
-        const_from_slice: {let a: &[char] = &['1', '2']; a},
         const_from_const_slice: {let a: &[char; 2] = &['1', '2']; a},
         const_from_arr: {let a: [char; 2] = ['1', '2']; a},
+    ] => ConstGenericRingBuffer::<_, 2>
+);
 
-        const_from_garb: { GrowableAllocRingBuffer::from(['1', '2'])},
-        const_from_arb: { AllocRingBuffer::from(['1', '2'])},
+try_convert_tests!(
+    [
+        const_try_from_vec: vec!['1', '2'],
+        const_try_from_ll: {let mut l = LinkedList::new(); l.push_back('1'); l.push_back('2'); l},
+        const_try_from_vd: {let mut l = VecDeque::new(); l.push_back('1'); l.push_back('2'); l},
+        const_try_from_str: "12".to_string(),
+        const_try_from_str_slice: "12",
+        const_try_from_slice: {let a: &[char] = &['1', '2']; a},
+
+        const_try_from_garb: { GrowableAllocRingBuffer::from(['1', '2'])},
+        const_try_from_arb: { AllocRingBuffer::from(['1', '2'])},
     ] => ConstGenericRingBuffer::<_, 2>
 );
 
+#[test]
+fn test_const_try_from_too_long() {
+    let err = ConstGenericRingBuffer::<_, 2>::try_from(vec![1, 2, 3]).unwrap_err();
+    assert_eq!(err.source_len(), 3);
+    assert_eq!(err.capacity(), 2);
+    assert_eq!(err.into_inner(), vec![1, 2, 3]);
+
+    let a: &[i32] = &[1, 2, 3];
+    assert_eq!(
+        ConstGenericRingBuffer::<_, 2>::try_from(a)
+            .unwrap_err()
+            .into_inner(),
+        a
+    );
+
+    let err = ConstGenericRingBuffer::<_, 2>::try_from("añb").unwrap_err();
+    assert_eq!(err.source_len(), 3);
+    assert_eq!(err.into_inner(), "añb");
+    // counts chars, not bytes
+    assert!(ConstGenericRingBuffer::<_, 2>::try_from("ñ").is_ok());
+
+    let err =
+        ConstGenericRingBuffer::<_, 2>::try_from(AllocRingBuffer::from([1, 2, 3])).unwrap_err();
+    assert_eq!(err.into_inner().to_vec(), vec![1, 2, 3]);
+
+    let err = ConstGenericRingBuffer::<_, 2>::try_from(GrowableAllocRingBuffer::from([1, 2, 3]))
+        .unwrap_err();
+    assert_eq!(err.into_inner().to_vec(), vec![1, 2, 3]);
+}
+
+#[test]
+fn test_conversions_keep_capacity() {
+    let mut a = ConstGenericRingBuffer::<i32, 8>::new();
+    a.enqueue(1);
+    let a = AllocRingBuffer::from(a);
+    assert_eq!(a.capacity(), 8);
+    assert_eq!(a.to_vec(), vec![1]);
+
+    let a = GrowableAllocRingBuffer::from(a);
+    assert!(a.capacity() >= 8);
+    assert_eq!(a.to_vec(), vec![1]);
+
+    let a = GrowableAllocRingBuffer::from(ConstGenericRingBuffer::<i32, 8>::new());
+    assert!(a.capacity() >= 8);
+
+    // converting empty buffers doesn't panic
+    assert!(AllocRingBuffer::from(ConstGenericRingBuffer::<i32, 4>::new()).is_empty());
+    assert!(AllocRingBuffer::from(GrowableAllocRingBuffer::<i32>::new()).is_empty());
+}
+
 #[test]
 fn test_extra_conversions_growable() {
     let a: &mut [i32; 2] = &mut [1, 2];
@@ -115,7 +195,7 @@ fn test_extra_conversions_const() {
     assert_eq!(a.to_vec(), vec![1, 2]);
 
     let a: &mut [i32] = &mut [1, 2];
-    let a = ConstGenericRingBuffer::<_, 2>::from(a);
+    let a = ConstGenericRingBuffer::<_, 2>::try_from(a).unwrap();
     assert_eq!(a.to_vec(), vec![1, 2]);
 }
 
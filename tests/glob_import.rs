@@ -0,0 +1,11 @@
+// `DynRingBuffer` has methods of the same names as `RingBuffer`, so if a glob import of the crate
+// root brought it in, these calls on a concrete ringbuffer would be ambiguous and not compile.
+use ringbuffer::*;
+
+#[test]
+fn glob_import_is_unambiguous() {
+    let mut rb = AllocRingBuffer::new(4);
+    let _ = rb.enqueue(1);
+    assert_eq!(rb.len(), 1);
+    assert_eq!(rb.dequeue(), Some(1));
+}
@@ -0,0 +1,98 @@
+#![cfg(all(feature = "mmap", target_os = "linux"))]
+
+use ringbuffer::SharedRingBuffer;
+use std::os::fd::AsFd;
+
+/// Runs `child` in a forked process and returns its exit code.
+///
+/// The child must not allocate or panic: the test harness is multithreaded, and only the
+/// forking thread survives in the child, possibly while another thread held a lock.
+fn fork_and_wait(child: impl FnOnce() -> i32, parent: impl FnOnce()) -> i32 {
+    let pid = unsafe { libc::fork() };
+    assert_ne!(pid, -1, "fork failed");
+
+    if pid == 0 {
+        let code = child();
+        unsafe { libc::_exit(code) };
+    }
+
+    parent();
+
+    let mut status = 0;
+    assert_eq!(unsafe { libc::waitpid(pid, &mut status, 0) }, pid);
+    assert!(libc::WIFEXITED(status), "child didn't exit normally");
+    libc::WEXITSTATUS(status)
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn child_produces_parent_consumes() {
+    const NUM_VALS: u64 = 10_000;
+
+    let mut rb = SharedRingBuffer::<u64>::create_memfd("frames", 16).unwrap();
+    let mut consumer =
+        SharedRingBuffer::<u64>::attach(rb.as_fd().try_clone_to_owned().unwrap()).unwrap();
+
+    let code = fork_and_wait(
+        || {
+            for i in 0..NUM_VALS {
+                let mut value = i;
+                while let Err(v) = rb.try_enqueue(value) {
+                    value = v;
+                    std::thread::yield_now();
+                }
+            }
+            0
+        },
+        || {
+            for i in 0..NUM_VALS {
+                let value = loop {
+                    if let Some(v) = consumer.dequeue() {
+                        break v;
+                    }
+                    std::thread::yield_now();
+                };
+                assert_eq!(value, i);
+            }
+            assert!(consumer.is_empty());
+        },
+    );
+    assert_eq!(code, 0);
+}
+
+#[test]
+#[cfg_attr(miri, ignore)]
+fn parent_produces_child_consumes() {
+    const NUM_VALS: u32 = 10_000;
+
+    let mut rb = SharedRingBuffer::<[u32; 4]>::create_memfd("frames", 5).unwrap();
+    let mut producer =
+        SharedRingBuffer::<[u32; 4]>::attach(rb.as_fd().try_clone_to_owned().unwrap()).unwrap();
+
+    let code = fork_and_wait(
+        || {
+            for i in 0..NUM_VALS {
+                let frame = loop {
+                    if let Some(v) = rb.dequeue() {
+                        break v;
+                    }
+                    std::thread::yield_now();
+                };
+                if frame != [i, i + 1, i + 2, i + 3] {
+                    return 1;
+                }
+            }
+            0
+        },
+        || {
+            for i in 0..NUM_VALS {
+                let mut frame = [i, i + 1, i + 2, i + 3];
+                while let Err(v) = producer.try_enqueue(frame) {
+                    frame = v;
+                    std::thread::yield_now();
+                }
+            }
+        },
+    );
+    assert_eq!(code, 0);
+}